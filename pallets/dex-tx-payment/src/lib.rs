@@ -0,0 +1,279 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Transaction fees paid in multi-tokens.
+//!
+//! `ChargeDexTxPayment` takes the place of `ChargeTransactionPayment` among the signed
+//! extensions. Without an `asset_id` it charges the native currency just like it. With one, the
+//! signer pays in that token instead: before dispatch, as much of it as buys the whole fee, tip
+//! included, of `NativeTokenId` in their DEX pool is held in the pallet account. After dispatch,
+//! what buys the actual fee is swapped through the pool, unwrapped and paid to `FeeCollector` in
+//! the native currency, and the rest is refunded in the same token. Should the swap or the unwrap
+//! fail by then, the collector is paid in the token itself.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode};
+use frame_support::{
+	dispatch::DispatchResult,
+	storage::{with_transaction, TransactionOutcome},
+	traits::Get,
+	weights::{DispatchInfo, PostDispatchInfo},
+};
+use pallet_dex::swap::Swap;
+use pallet_multi_token::multitoken::MultiToken;
+use pallet_transaction_payment::OnChargeTransaction;
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{
+		AccountIdConversion, DispatchInfoOf, Dispatchable, PostDispatchInfoOf, SaturatedConversion, SignedExtension,
+		Zero,
+	},
+	transaction_validity::{InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction},
+	DispatchError, FixedPointOperand,
+};
+
+type OnChargeTransactionOf<T> = <T as pallet_transaction_payment::Config>::OnChargeTransaction;
+type BalanceOf<T> = <OnChargeTransactionOf<T> as OnChargeTransaction<T>>::Balance;
+type LiquidityInfoOf<T> = <OnChargeTransactionOf<T> as OnChargeTransaction<T>>::LiquidityInfo;
+
+/// What was charged before dispatch.
+pub enum InitialPayment<T: Config> {
+	Nothing,
+	Native(LiquidityInfoOf<T>),
+	/// `amount` of `asset_id` held in the pallet account.
+	Asset { asset_id: u64, amount: u64 },
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{pallet_prelude::*, PalletId};
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_transaction_payment::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+		type MultiToken: MultiToken<Self::AccountId>;
+		/// Buys the native currency with the tokens fees are paid in.
+		type Dex: Swap<Self::AccountId>;
+		/// Multi-token id of the native currency in DEX pools.
+		#[pallet::constant]
+		type NativeTokenId: Get<u64>;
+		/// Receives the fees paid in multi-tokens, in the native currency.
+		type FeeCollector: Get<Self::AccountId>;
+		/// Used to derive the account holding fees during dispatch.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// `who` paid a fee of `amount_in` of `asset_id`, converted to `amount_out` of the
+		/// native currency. `amount_out` is zero if the conversion failed.
+		AssetTxFeePaid { who: T::AccountId, asset_id: u64, amount_in: u64, amount_out: u64 },
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Account holding fees paid in multi-tokens until the call is dispatched.
+		pub fn account_id() -> T::AccountId {
+			T::PalletId::get().into_account_truncating()
+		}
+
+		/// Amount of `asset_id` buying `fee` of the native token.
+		fn quote_fee(asset_id: u64, fee: u64) -> Option<u64> {
+			let native_token_id = T::NativeTokenId::get();
+			if asset_id == native_token_id {
+				Some(fee)
+			} else {
+				T::Dex::quote_amount_in(asset_id, native_token_id, fee)
+			}
+		}
+
+		/// Holds enough of `asset_id` from `who` to pay `fee`, returning the amount held.
+		pub(crate) fn withdraw_asset_fee(
+			who: &T::AccountId,
+			asset_id: u64,
+			fee: u64,
+		) -> Result<u64, TransactionValidityError> {
+			let amount = Self::quote_fee(asset_id, fee).ok_or(InvalidTransaction::Payment)?;
+			T::MultiToken::transfer_to(who, &Self::account_id(), &asset_id, &amount)
+				.map_err(|_| InvalidTransaction::Payment)?;
+			Ok(amount)
+		}
+
+		/// Converts the part of the `held` tokens paying `fee` to the native currency for the fee
+		/// collector, refunding the rest to `who`.
+		pub(crate) fn correct_and_deposit_asset_fee(
+			who: &T::AccountId,
+			asset_id: u64,
+			held: u64,
+			fee: u64,
+		) -> Result<(), TransactionValidityError> {
+			let account = Self::account_id();
+			let collector = T::FeeCollector::get();
+			let native_token_id = T::NativeTokenId::get();
+			// The call may have moved the pool price, its fee is paid with what was held anyway.
+			let amount_in = Self::quote_fee(asset_id, fee).unwrap_or(held).min(held);
+
+			let amount_out = if amount_in == 0 {
+				0
+			} else {
+				let converted = with_transaction(|| {
+					let swapped = if asset_id == native_token_id {
+						Ok(amount_in)
+					} else {
+						T::Dex::swap_exact_in(&account, &account, asset_id, native_token_id, amount_in)
+					};
+					let unwrapped = swapped.and_then(|amount_out| {
+						T::MultiToken::unwrap_to(&account, &collector, &amount_out).map(|_| amount_out)
+					});
+					match unwrapped {
+						Ok(amount_out) => TransactionOutcome::Commit(Ok(amount_out)),
+						Err(e) => TransactionOutcome::Rollback(Err::<u64, DispatchError>(e)),
+					}
+				});
+				match converted {
+					Ok(amount_out) => amount_out,
+					Err(_) => {
+						T::MultiToken::transfer_to(&account, &collector, &asset_id, &amount_in)
+							.map_err(|_| InvalidTransaction::Payment)?;
+						0
+					},
+				}
+			};
+
+			let refund = held - amount_in;
+			if refund > 0 {
+				T::MultiToken::refund(&account, who, &asset_id, &refund).map_err(|_| InvalidTransaction::Payment)?;
+			}
+			Self::deposit_event(Event::<T>::AssetTxFeePaid { who: who.clone(), asset_id, amount_in, amount_out });
+			Ok(())
+		}
+	}
+}
+
+/// Charges the transaction fee in the native currency, or in the multi-token `asset_id`.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct ChargeDexTxPayment<T: Config> {
+	#[codec(compact)]
+	tip: BalanceOf<T>,
+	asset_id: Option<u64>,
+}
+
+impl<T: Config> ChargeDexTxPayment<T>
+where
+	T::Call: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+	BalanceOf<T>: Send + Sync + From<u64> + FixedPointOperand,
+{
+	/// Pays `tip` on top of the fee, both in `asset_id` if given.
+	pub fn from(tip: BalanceOf<T>, asset_id: Option<u64>) -> Self {
+		Self { tip, asset_id }
+	}
+
+	fn withdraw_fee(
+		&self,
+		who: &T::AccountId,
+		call: &T::Call,
+		info: &DispatchInfoOf<T::Call>,
+		len: usize,
+	) -> Result<(BalanceOf<T>, InitialPayment<T>), TransactionValidityError> {
+		let fee = pallet_transaction_payment::Pallet::<T>::compute_fee(len as u32, info, self.tip);
+		if fee.is_zero() {
+			return Ok((fee, InitialPayment::Nothing))
+		}
+		match self.asset_id {
+			None => <OnChargeTransactionOf<T> as OnChargeTransaction<T>>::withdraw_fee(who, call, info, fee, self.tip)
+				.map(|already_withdrawn| (fee, InitialPayment::Native(already_withdrawn))),
+			Some(asset_id) => Pallet::<T>::withdraw_asset_fee(who, asset_id, fee.saturated_into())
+				.map(|amount| (fee, InitialPayment::Asset { asset_id, amount })),
+		}
+	}
+}
+
+impl<T: Config> sp_std::fmt::Debug for ChargeDexTxPayment<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "ChargeDexTxPayment<{:?}, {:?}>", self.tip, self.asset_id)
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Config> SignedExtension for ChargeDexTxPayment<T>
+where
+	T::Call: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+	BalanceOf<T>: Send + Sync + From<u64> + FixedPointOperand,
+{
+	const IDENTIFIER: &'static str = "ChargeDexTxPayment";
+	type AccountId = T::AccountId;
+	type Call = T::Call;
+	type AdditionalSigned = ();
+	type Pre = (BalanceOf<T>, T::AccountId, InitialPayment<T>);
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		let (fee, _) = self.withdraw_fee(who, call, info, len)?;
+		let priority =
+			pallet_transaction_payment::ChargeTransactionPayment::<T>::get_priority(info, len, self.tip, fee);
+		Ok(ValidTransaction { priority, ..Default::default() })
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		let (_, initial_payment) = self.withdraw_fee(who, call, info, len)?;
+		Ok((self.tip, who.clone(), initial_payment))
+	}
+
+	fn post_dispatch(
+		pre: Option<Self::Pre>,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		match pre {
+			Some((tip, who, InitialPayment::Native(already_withdrawn))) =>
+				pallet_transaction_payment::ChargeTransactionPayment::<T>::post_dispatch(
+					Some((tip, who, already_withdrawn)),
+					info,
+					post_info,
+					len,
+					result,
+				),
+			Some((tip, who, InitialPayment::Asset { asset_id, amount })) => {
+				let actual_fee =
+					pallet_transaction_payment::Pallet::<T>::compute_actual_fee(len as u32, info, post_info, tip);
+				Pallet::<T>::correct_and_deposit_asset_fee(&who, asset_id, amount, actual_fee.saturated_into())
+			},
+			Some((_, _, InitialPayment::Nothing)) | None => Ok(()),
+		}
+	}
+}
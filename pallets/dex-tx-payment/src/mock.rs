@@ -0,0 +1,166 @@
+use crate as pallet_dex_tx_payment;
+use frame_support::{
+	traits::{ConstU16, ConstU32, ConstU64, ConstU8, Get},
+	weights::{DispatchClass, IdentityFee, Weight},
+	PalletId,
+};
+use frame_system as system;
+use pallet_transaction_payment::CurrencyAdapter;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	Permill,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::parameter_types! {
+	pub const ExtrinsicBaseWeight: Weight = 10;
+	pub const NativeTokenId: u64 = u64::MAX;
+	pub const MultiTokenPalletId: PalletId = PalletId(*b"py/mtokn");
+	pub const FeeCollector: u64 = 99;
+	pub const TxPaymentPalletId: PalletId = PalletId(*b"py/txpay");
+	pub const Fee: u64 = 3;
+	pub const DexPalletId: PalletId = PalletId(*b"py/dexpl");
+	pub const StableSwapFee: Permill = Permill::from_parts(400);
+	pub const MaxPriceMove: Permill = Permill::from_percent(100);
+}
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		Balances: pallet_balances,
+		TransactionPayment: pallet_transaction_payment,
+		MultiToken: pallet_multi_token,
+		Dex: pallet_dex,
+		DexTxPayment: pallet_dex_tx_payment,
+	}
+);
+
+/// Block weights charging `ExtrinsicBaseWeight` for every extrinsic and nothing per block.
+pub struct BlockWeights;
+impl Get<frame_system::limits::BlockWeights> for BlockWeights {
+	fn get() -> frame_system::limits::BlockWeights {
+		frame_system::limits::BlockWeights::builder()
+			.base_block(0)
+			.for_class(DispatchClass::all(), |weights| {
+				weights.base_extrinsic = ExtrinsicBaseWeight::get();
+			})
+			.for_class(DispatchClass::non_mandatory(), |weights| {
+				weights.max_total = Some(1024);
+			})
+			.build_or_panic()
+	}
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = BlockWeights;
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU64<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+impl pallet_transaction_payment::Config for Test {
+	type Event = Event;
+	type OnChargeTransaction = CurrencyAdapter<Balances, ()>;
+	type OperationalFeeMultiplier = ConstU8<5>;
+	type WeightToFee = IdentityFee<u64>;
+	type LengthToFee = IdentityFee<u64>;
+	type FeeMultiplierUpdate = ();
+}
+
+impl pallet_multi_token::Config for Test {
+	type Event = Event;
+	type WeightInfo = pallet_multi_token::weights::SubstrateWeight<Test>;
+	type Currency = Balances;
+	type NativeTokenId = NativeTokenId;
+	type PalletId = MultiTokenPalletId;
+	type FirstIssuedTokenId = ConstU64<100>;
+	type AssetDeposit = ConstU64<10>;
+	type OnTransfer = ();
+	type ExemptAccounts = frame_support::traits::Nothing;
+	type OnReceived = ();
+	type StringLimit = ConstU32<64>;
+}
+
+impl pallet_dex::Config for Test {
+	type Event = Event;
+	type Fee = Fee;
+	type MultiToken = MultiToken;
+	type MaxObservations = ConstU32<16>;
+	type PalletId = DexPalletId;
+	type MaxPoolAssets = ConstU32<8>;
+	type StableSwapFee = StableSwapFee;
+	type MaxTicks = ConstU32<64>;
+	type FlashBorrower = ();
+	type Currency = Balances;
+	type PoolDeposit = ConstU64<10>;
+	type AdminOrigin = frame_system::EnsureRoot<u64>;
+	type MaxPriceMove = MaxPriceMove;
+	type MaxOrdersPerPool = ConstU32<4>;
+	type MinLimitOrderAmount = ConstU64<1_000>;
+	type MaxOrderLifetime = ConstU64<1_000>;
+	type MaxIntentsPerBatch = ConstU32<8>;
+	type MaxAuctionsPerBlock = ConstU32<8>;
+	type CommitmentDeposit = ConstU64<5>;
+	type RevealPeriod = ConstU64<3>;
+	type MaxCommitmentsPerBlock = ConstU32<8>;
+	type FarmOrigin = frame_system::EnsureSigned<u64>;
+}
+
+impl pallet_dex_tx_payment::Config for Test {
+	type Event = Event;
+	type MultiToken = MultiToken;
+	type Dex = Dex;
+	type NativeTokenId = NativeTokenId;
+	type FeeCollector = FeeCollector;
+	type PalletId = TxPaymentPalletId;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> { balances: vec![(1, 1_000), (2, 2_000_000)] }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+	storage.into()
+}
@@ -0,0 +1,98 @@
+use crate::{mock::*, ChargeDexTxPayment};
+use frame_support::{
+	assert_ok,
+	weights::{DispatchInfo, PostDispatchInfo, Weight},
+};
+use sp_runtime::{
+	traits::SignedExtension,
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+};
+
+const CALL: &Call = &Call::System(frame_system::Call::remark { remark: vec![] });
+
+fn info_from_weight(weight: Weight) -> DispatchInfo {
+	DispatchInfo { weight, ..Default::default() }
+}
+
+fn post_info_from_weight(weight: Weight) -> PostDispatchInfo {
+	PostDispatchInfo { actual_weight: Some(weight), pays_fee: Default::default() }
+}
+
+/// Pool of token 5 against the wrapped native currency, one million of each.
+fn create_fee_pool() {
+	assert_ok!(MultiToken::wrap(Origin::signed(2), 1_000_000));
+	assert_ok!(MultiToken::mint(Origin::signed(2), 5, 1_000_000));
+	assert_ok!(Dex::init(Origin::signed(2), NativeTokenId::get(), 1_000_000, 5, 1_000_000));
+}
+
+#[test]
+fn should_charge_native_fee_without_asset() {
+	new_test_ext().execute_with(|| {
+		// Fees are the base weight of 10, the length and the weight.
+		let pre = ChargeDexTxPayment::<Test>::from(0, None).pre_dispatch(&1, CALL, &info_from_weight(500), 10).unwrap();
+		assert_eq!(Balances::free_balance(1), 1_000 - 520);
+
+		assert_ok!(ChargeDexTxPayment::<Test>::post_dispatch(
+			Some(pre),
+			&info_from_weight(500),
+			&post_info_from_weight(200),
+			10,
+			&Ok(())
+		));
+		assert_eq!(Balances::free_balance(1), 1_000 - 220);
+	});
+}
+
+#[test]
+fn should_pay_fee_in_asset_and_refund_the_rest() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_fee_pool();
+		assert_ok!(MultiToken::mint(Origin::signed(1), 5, 10_000));
+
+		// 538 of token 5 buy the 520 native fee at 3% swap fee.
+		let pre =
+			ChargeDexTxPayment::<Test>::from(0, Some(5)).pre_dispatch(&1, CALL, &info_from_weight(500), 10).unwrap();
+		assert_eq!(MultiToken::balances(5, 1), Some(10_000 - 538));
+		assert_eq!(MultiToken::balances(5, DexTxPayment::account_id()), Some(538));
+		assert_eq!(Balances::free_balance(1), 1_000);
+
+		// Only the 228 buying the actual fee of 220 are swapped.
+		assert_ok!(ChargeDexTxPayment::<Test>::post_dispatch(
+			Some(pre),
+			&info_from_weight(500),
+			&post_info_from_weight(200),
+			10,
+			&Ok(())
+		));
+		assert_eq!(MultiToken::balances(5, 1), Some(10_000 - 228));
+		assert_eq!(MultiToken::balances(5, DexTxPayment::account_id()), Some(0));
+		// The collector is paid in the native currency, not in wrapped tokens.
+		assert_eq!(Balances::free_balance(FeeCollector::get()), 220);
+		assert_eq!(MultiToken::balances(NativeTokenId::get(), FeeCollector::get()), None);
+		assert_eq!(MultiToken::balances(NativeTokenId::get(), DexTxPayment::account_id()), Some(0));
+		System::assert_last_event(
+			crate::Event::<Test>::AssetTxFeePaid { who: 1, asset_id: 5, amount_in: 228, amount_out: 220 }.into(),
+		);
+	});
+}
+
+#[test]
+fn should_reject_asset_without_pool_or_balance() {
+	new_test_ext().execute_with(|| {
+		create_fee_pool();
+		assert_ok!(MultiToken::mint(Origin::signed(1), 6, 10_000));
+		assert_ok!(MultiToken::mint(Origin::signed(1), 5, 100));
+
+		let payment_error = Err(TransactionValidityError::Invalid(InvalidTransaction::Payment));
+		assert_eq!(
+			ChargeDexTxPayment::<Test>::from(0, Some(6)).pre_dispatch(&1, CALL, &info_from_weight(500), 10).map(|_| ()),
+			payment_error
+		);
+		assert_eq!(
+			ChargeDexTxPayment::<Test>::from(0, Some(5)).pre_dispatch(&1, CALL, &info_from_weight(500), 10).map(|_| ()),
+			payment_error
+		);
+		assert_eq!(MultiToken::balances(5, 1), Some(100));
+	});
+}
@@ -0,0 +1,314 @@
+//! Batch auctions for constant-product pools.
+//!
+//! Pools in batch-auction mode take no immediate swaps. Swap intents are escrowed instead and
+//! the ones of a block are cleared together in `on_initialize` of the next block, so their
+//! order within the block does not matter. At most `MaxAuctionsPerBlock` pools are cleared in a
+//! block, going round them in turn; intents of the others wait for a later block.
+//!
+//! Every intent in a batch trades at the same price: opposite intents are matched with each
+//! other and only the net flow is swapped against the pool. Intents whose limit the price
+//! misses are dropped, the rest cleared again without them until all limits hold. Intents are
+//! all-or-nothing: a cleared intent sells its whole amount and a dropped one is refunded whole.
+//!
+//! With `A` first and `B` second tokens sold, and the fee taken from the output of the pool as
+//! in regular swaps, the price of the first token in the second one is
+//! `(y·(1 - f) + B) / (x + A)` when the net flow goes into the pool in the first token,
+//! `(y + B) / (x·(1 - f) + A)` when it goes in in the second one and `B / A` when neither
+//! holds and the pool is not traded against at all.
+
+use crate::{
+	AuctionCursor, BatchAuctionPools, Config, Error, Event, FirstTokeId, Pallet, PoolId, SecondTokeId, SwapIntents,
+};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{
+	ensure,
+	pallet_prelude::{DispatchResult, Weight},
+	storage::{with_transaction, TransactionOutcome},
+	traits::Get,
+};
+use pallet_multi_token::multitoken::MultiToken;
+use scale_info::TypeInfo;
+use sp_core::U256;
+use sp_runtime::{FixedPointNumber, FixedU128, RuntimeDebug};
+use sp_std::{vec, vec::Vec};
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct SwapIntent<T: Config> {
+	pub who: T::AccountId,
+	pub token_in: u64,
+	pub amount_in: u64,
+	pub min_amount_out: u64,
+}
+
+/// `a * b / c` rounded down.
+fn mul_div(a: u128, b: u128, c: u128) -> u128 {
+	(U256::from(a) * U256::from(b) / U256::from(c)).low_u128()
+}
+
+/// Clears a batch whose net flow goes into the pool in the token with `reserve_in`, returns the
+/// price in the other token and the output of every intent. `None` if the flow goes the other
+/// way at that price.
+fn clear_net_flow(
+	reserve_in: u64,
+	reserve_out: u64,
+	fee: u64,
+	intents: &[(bool, u64)],
+	first_in: bool,
+) -> Option<(FixedU128, Vec<u64>)> {
+	let (reserve_in, reserve_out, keep) = (reserve_in as u128, reserve_out as u128, 100 - fee as u128);
+	let (mut in_sum, mut out_sum) = (0u128, 0u128);
+	for (intent_first_in, amount) in intents {
+		if *intent_first_in == first_in {
+			in_sum += *amount as u128;
+		} else {
+			out_sum += *amount as u128;
+		}
+	}
+	let numerator = reserve_out * keep + 100 * out_sum;
+	let denominator = 100 * (reserve_in + in_sum);
+	if U256::from(in_sum) * U256::from(numerator) <= U256::from(out_sum) * U256::from(denominator) {
+		return None
+	}
+
+	let matched: u128 = intents
+		.iter()
+		.filter(|(intent_first_in, _)| *intent_first_in != first_in)
+		.map(|(_, amount)| mul_div(*amount as u128, denominator, numerator))
+		.sum();
+	let net_in = in_sum - matched;
+	let pool_out = mul_div(reserve_out, net_in, reserve_in + net_in) * keep / 100;
+	let available = out_sum + pool_out;
+	let amounts_out = intents
+		.iter()
+		.map(|(intent_first_in, amount)| {
+			if *intent_first_in == first_in {
+				mul_div(*amount as u128, available, in_sum) as u64
+			} else {
+				mul_div(*amount as u128, denominator, numerator) as u64
+			}
+		})
+		.collect();
+	Some((FixedU128::checked_from_rational(available, in_sum)?, amounts_out))
+}
+
+/// Clears `intents`, `(first_in, amount_in)`, against a pool with the given reserves and
+/// percent `fee`. Returns the price of the first token in the second one and the output of
+/// every intent, in the other token. The pool keeps whatever rounding leaves over.
+pub fn clear_batch(
+	first_reserve: u64,
+	second_reserve: u64,
+	fee: u64,
+	intents: &[(bool, u64)],
+) -> Option<(FixedU128, Vec<u64>)> {
+	if let Some(cleared) = clear_net_flow(first_reserve, second_reserve, fee, intents, true) {
+		return Some(cleared)
+	}
+	if let Some((price, amounts_out)) = clear_net_flow(second_reserve, first_reserve, fee, intents, false) {
+		return Some((price.reciprocal()?, amounts_out))
+	}
+
+	let first_in: u128 = intents.iter().filter(|(first_in, _)| *first_in).map(|(_, amount)| *amount as u128).sum();
+	let second_in: u128 = intents.iter().filter(|(first_in, _)| !*first_in).map(|(_, amount)| *amount as u128).sum();
+	if first_in == 0 || second_in == 0 {
+		return None
+	}
+	let amounts_out = intents
+		.iter()
+		.map(|(intent_first_in, amount)| {
+			if *intent_first_in {
+				mul_div(*amount as u128, second_in, first_in) as u64
+			} else {
+				mul_div(*amount as u128, first_in, second_in) as u64
+			}
+		})
+		.collect();
+	Some((FixedU128::checked_from_rational(second_in, first_in)?, amounts_out))
+}
+
+impl<T: Config> Pallet<T> {
+	pub(crate) fn do_submit_swap_intent(
+		who: &T::AccountId,
+		pool_id: PoolId,
+		token_in: u64,
+		amount_in: u64,
+		min_amount_out: u64,
+	) -> DispatchResult {
+		Self::constant_product_pool(pool_id)?;
+		ensure!(BatchAuctionPools::<T>::contains_key(pool_id), Error::<T>::NotBatchAuction);
+		Self::ensure_pool_active(pool_id)?;
+		ensure!(
+			token_in == FirstTokeId::<T>::get(pool_id) || token_in == SecondTokeId::<T>::get(pool_id),
+			Error::<T>::UnknownToken
+		);
+		ensure!(amount_in > 0, Error::<T>::ZeroAmount);
+
+		SwapIntents::<T>::try_mutate(pool_id, |intents| {
+			intents.try_push(SwapIntent { who: who.clone(), token_in, amount_in, min_amount_out })
+		})
+		.map_err(|_| Error::<T>::TooManyIntents)?;
+		T::MultiToken::transfer_to(who, &Self::escrow_account(), &token_in, &amount_in)?;
+		Self::deposit_event(Event::<T>::SwapIntentSubmitted {
+			who: who.clone(),
+			pool_id,
+			token_in,
+			amount_in,
+			min_amount_out,
+		});
+		Ok(())
+	}
+
+	/// Clears the intents of up to `MaxAuctionsPerBlock` pools, starting after the pool cleared
+	/// last and wrapping around.
+	pub(crate) fn clear_batch_auctions() -> Weight {
+		let max_auctions = T::MaxAuctionsPerBlock::get() as usize;
+		let mut weight = T::DbWeight::get().reads(1);
+		let mut pool_ids: Vec<PoolId> = match AuctionCursor::<T>::get() {
+			Some(cursor) => SwapIntents::<T>::iter_keys_from(SwapIntents::<T>::hashed_key_for(cursor))
+				.take(max_auctions)
+				.collect(),
+			None => Vec::new(),
+		};
+		if pool_ids.len() < max_auctions {
+			let wrapped: Vec<PoolId> = SwapIntents::<T>::iter_keys()
+				.filter(|pool_id| !pool_ids.contains(pool_id))
+				.take(max_auctions - pool_ids.len())
+				.collect();
+			pool_ids.extend(wrapped);
+		}
+		weight = weight.saturating_add(T::DbWeight::get().reads(pool_ids.len() as Weight + 1));
+
+		for pool_id in &pool_ids {
+			let intents = SwapIntents::<T>::take(pool_id);
+			weight = weight.saturating_add(
+				T::DbWeight::get().reads_writes(6, 3).saturating_add(
+					T::DbWeight::get().reads_writes(2, 2).saturating_mul(intents.len() as Weight),
+				),
+			);
+			Self::clear_batch_auction(*pool_id, intents.into_inner());
+		}
+		if let Some(pool_id) = pool_ids.last() {
+			AuctionCursor::<T>::put(pool_id);
+			weight = weight.saturating_add(T::DbWeight::get().writes(1));
+		}
+		weight
+	}
+
+	/// Refunds and removes the intents against `pool_id` waiting to be cleared.
+	pub(crate) fn refund_swap_intents(pool_id: PoolId) -> DispatchResult {
+		for intent in SwapIntents::<T>::take(pool_id) {
+			T::MultiToken::refund(&Self::escrow_account(), &intent.who, &intent.token_in, &intent.amount_in)?;
+			Self::deposit_event(Event::<T>::SwapIntentRefunded {
+				who: intent.who,
+				pool_id,
+				token_in: intent.token_in,
+				amount_in: intent.amount_in,
+			});
+		}
+		Ok(())
+	}
+
+	fn clear_batch_auction(pool_id: PoolId, intents: Vec<SwapIntent<T>>) {
+		let mut included = vec![true; intents.len()];
+		let cleared = Self::constant_product_pool(pool_id)
+			.and_then(|pool_address| Self::ensure_pool_active(pool_id).map(|_| pool_address))
+			.ok()
+			.and_then(|pool_address| {
+				let first_token_id = FirstTokeId::<T>::get(pool_id);
+				let first_reserve = T::MultiToken::balances(&first_token_id, &pool_address);
+				let second_reserve = T::MultiToken::balances(&SecondTokeId::<T>::get(pool_id), &pool_address);
+				loop {
+					let batch: Vec<(bool, u64)> = intents
+						.iter()
+						.zip(&included)
+						.filter(|(_, included)| **included)
+						.map(|(intent, _)| (intent.token_in == first_token_id, intent.amount_in))
+						.collect();
+					let (price, amounts_out) = clear_batch(first_reserve, second_reserve, T::Fee::get(), &batch)?;
+
+					let mut all_limits_hold = true;
+					let batch_included = included.iter_mut().zip(&intents).filter(|(included, _)| **included);
+					for ((included, intent), amount_out) in batch_included.zip(&amounts_out) {
+						if *amount_out < intent.min_amount_out {
+							*included = false;
+							all_limits_hold = false;
+						}
+					}
+					if all_limits_hold {
+						return Some((pool_address, price, batch, amounts_out))
+					}
+				}
+			});
+
+		if let Some((pool_address, price, batch, amounts_out)) = cleared {
+			let settled = with_transaction(|| {
+				match Self::settle_batch_auction(pool_id, &pool_address, price, &intents, &included, &batch, amounts_out) {
+					Ok(()) => TransactionOutcome::Commit(Ok(())),
+					Err(e) => TransactionOutcome::Rollback(Err(e)),
+				}
+			});
+			if settled.is_err() {
+				included = vec![false; intents.len()];
+			}
+		} else {
+			included = vec![false; intents.len()];
+		}
+
+		for (intent, _) in intents.iter().zip(&included).filter(|(_, included)| !**included) {
+			if T::MultiToken::refund(&Self::escrow_account(), &intent.who, &intent.token_in, &intent.amount_in).is_ok() {
+				Self::deposit_event(Event::<T>::SwapIntentRefunded {
+					who: intent.who.clone(),
+					pool_id,
+					token_in: intent.token_in,
+					amount_in: intent.amount_in,
+				});
+			}
+		}
+	}
+
+	/// Moves the escrowed input of the `included` intents into the pool and pays their output.
+	/// Fails if that moves the price of the pool more than `MaxPriceMove`.
+	fn settle_batch_auction(
+		pool_id: PoolId,
+		pool_address: &T::AccountId,
+		price: FixedU128,
+		intents: &[SwapIntent<T>],
+		included: &[bool],
+		batch: &[(bool, u64)],
+		amounts_out: Vec<u64>,
+	) -> DispatchResult {
+		let first_token_id = FirstTokeId::<T>::get(pool_id);
+		let second_token_id = SecondTokeId::<T>::get(pool_id);
+		Self::block_start_price(pool_id, pool_address);
+
+		let escrow = Self::escrow_account();
+		for (first_in, token_id) in [(true, first_token_id), (false, second_token_id)] {
+			let amount: u64 =
+				batch.iter().filter(|(intent_first_in, _)| *intent_first_in == first_in).map(|(_, amount)| amount).sum();
+			if amount > 0 {
+				T::MultiToken::transfer_to(&escrow, pool_address, &token_id, &amount)?;
+			}
+		}
+
+		let filled = intents.iter().zip(included).filter(|(_, included)| **included).map(|(intent, _)| intent);
+		for (intent, amount_out) in filled.zip(amounts_out) {
+			let token_out = if intent.token_in == first_token_id { second_token_id } else { first_token_id };
+			if amount_out > 0 {
+				T::MultiToken::transfer_to(pool_address, &intent.who, &token_out, &amount_out)?;
+			}
+			Self::deposit_event(Event::<T>::SwapIntentFilled {
+				who: intent.who.clone(),
+				pool_id,
+				token_in: intent.token_in,
+				amount_in: intent.amount_in,
+				token_out,
+				amount_out,
+			});
+		}
+		Self::check_price_move(pool_id, pool_address, Self::spot_prices(pool_id, pool_address).0)?;
+		Self::update_price_observations(pool_id, pool_address);
+		Self::deposit_event(Event::<T>::BatchCleared { pool_id, price, intents: batch.len() as u32 });
+		Ok(())
+	}
+}
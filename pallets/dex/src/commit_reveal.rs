@@ -0,0 +1,75 @@
+//! Commit-reveal swaps against constant-product pools.
+//!
+//! A trader first commits to the hash of a swap, reserving `CommitmentDeposit`, and reveals
+//! the swap in one of the next `RevealPeriod` blocks. The swap is only public once it executes.
+//! Revealing returns the deposit, commitments still unrevealed after the period forfeit it.
+
+use crate::{CommitmentExpiries, Commitments, Config, Error, Event, Pallet, PoolId};
+use frame_support::{
+	ensure,
+	pallet_prelude::{DispatchResult, Weight},
+	traits::{Get, ReservableCurrency},
+};
+use sp_runtime::traits::{Hash, Saturating};
+
+impl<T: Config> Pallet<T> {
+	/// Hash to commit to for swapping `amount` of `token_id` in `pool_id` for at least
+	/// `min_amount_out`.
+	pub fn swap_commitment(pool_id: PoolId, token_id: u64, amount: u64, min_amount_out: u64, salt: [u8; 32]) -> T::Hash {
+		T::Hashing::hash_of(&(pool_id, token_id, amount, min_amount_out, salt))
+	}
+
+	pub(crate) fn do_commit_swap(who: &T::AccountId, commitment: T::Hash) -> DispatchResult {
+		ensure!(!Commitments::<T>::contains_key(who, commitment), Error::<T>::CommitmentExists);
+		let now = frame_system::Pallet::<T>::block_number();
+		let expires_at = now.saturating_add(T::RevealPeriod::get()).saturating_add(1u32.into());
+		CommitmentExpiries::<T>::try_mutate(expires_at, |commitments| commitments.try_push((who.clone(), commitment)))
+			.map_err(|_| Error::<T>::TooManyCommitments)?;
+
+		let deposit = T::CommitmentDeposit::get();
+		T::Currency::reserve(who, deposit)?;
+		Commitments::<T>::insert(who, commitment, (now, deposit));
+		Self::deposit_event(Event::<T>::SwapCommitted { who: who.clone(), commitment });
+		Ok(())
+	}
+
+	pub(crate) fn do_reveal_swap(
+		who: &T::AccountId,
+		pool_id: PoolId,
+		token_id: u64,
+		amount: u64,
+		min_amount_out: u64,
+		salt: [u8; 32],
+	) -> DispatchResult {
+		let commitment = Self::swap_commitment(pool_id, token_id, amount, min_amount_out, salt);
+		let (committed_at, deposit) = Commitments::<T>::get(who, commitment).ok_or(Error::<T>::NoCommitment)?;
+		let now = frame_system::Pallet::<T>::block_number();
+		ensure!(now > committed_at, Error::<T>::RevealTooEarly);
+		ensure!(now <= committed_at.saturating_add(T::RevealPeriod::get()), Error::<T>::CommitmentExpired);
+
+		Commitments::<T>::remove(who, commitment);
+		T::Currency::unreserve(who, deposit);
+		let amount_out = Self::do_swap(who, who, pool_id, token_id, amount)?;
+		ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+		Self::deposit_event(Event::<T>::SwapRevealed { who: who.clone(), commitment });
+		Ok(())
+	}
+
+	/// Slashes the deposits of commitments whose reveal period ended in the previous block.
+	pub(crate) fn forfeit_expired_commitments(now: T::BlockNumber) -> Weight {
+		let expired = CommitmentExpiries::<T>::take(now);
+		for (who, commitment) in expired.iter() {
+			// Revealed commitments are gone, a recommitment of the same hash expires later.
+			let deposit = match Commitments::<T>::get(who, commitment) {
+				Some((committed_at, deposit)) if committed_at.saturating_add(T::RevealPeriod::get()) < now => deposit,
+				_ => continue,
+			};
+			Commitments::<T>::remove(who, commitment);
+			let _ = T::Currency::slash_reserved(who, deposit);
+			Self::deposit_event(Event::<T>::CommitmentForfeited { who: who.clone(), commitment: *commitment, deposit });
+		}
+		T::DbWeight::get().reads_writes(1, 1).saturating_add(
+			T::DbWeight::get().reads_writes(2, 2).saturating_mul(expired.len() as Weight),
+		)
+	}
+}
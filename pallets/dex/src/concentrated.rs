@@ -0,0 +1,650 @@
+//! Concentrated liquidity (Uniswap v3-style) pools of two assets.
+//!
+//! Liquidity is provided in ranges between two ticks, where tick `i` stands for the price
+//! `1.0001^i` of the first token in the second one. Prices are tracked as their square roots,
+//! which keeps the token amounts of a range linear in its liquidity. Swap fees are accounted per
+//! unit of in-range liquidity and attributed to ranges through the fee growth recorded on the
+//! far side of every initialized tick.
+
+use crate::weighted::MAX_SWAP_FEE;
+use crate::{
+	ConcentratedPools, Config, Error, Event, InitializedTicks, NextPositionId, Pallet, PoolId,
+	PoolKind, PositionId, Positions, Ticks,
+};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{
+	ensure,
+	pallet_prelude::{DispatchResult, Weight},
+	traits::Get,
+};
+use pallet_multi_token::multitoken::MultiToken;
+use scale_info::TypeInfo;
+use sp_core::U256;
+use sp_runtime::{traits::CheckedMul, DispatchError, FixedPointNumber, FixedU128, PerThing, Permill, RuntimeDebug};
+
+/// Lowest usable tick, the price `1.0001^-200000 ≈ 2·10^-9`.
+pub const MIN_TICK: i32 = -200_000;
+
+/// Highest usable tick, the price `1.0001^200000 ≈ 4.85·10^8`.
+pub const MAX_TICK: i32 = 200_000;
+
+/// Widest accepted distance between usable ticks.
+pub const MAX_TICK_SPACING: u32 = 16_384;
+
+/// Inner value of `sqrt(1.0001)`, the ratio between square root prices of neighbouring ticks.
+const SQRT_TICK_BASE: u128 = 1_000_049_998_750_062_496;
+
+/// State of a concentrated liquidity pool. Reserves and uncollected fees live on the pool account.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct ConcentratedPool {
+	pub first_token_id: u64,
+	pub second_token_id: u64,
+	pub fee: Permill,
+	pub tick_spacing: u32,
+	/// Square root of the price of the first token in the second one.
+	pub sqrt_price: FixedU128,
+	/// Tick of the current price.
+	pub tick: i32,
+	/// Liquidity of the ranges containing the current price.
+	pub liquidity: u128,
+	/// Fees earned per unit of liquidity over the life of the pool, as `FixedU128` inner values.
+	/// Allowed to wrap, only differences matter.
+	pub fee_growth_global_first: u128,
+	pub fee_growth_global_second: u128,
+	/// Number of open positions, the pool can only be destroyed without any.
+	pub positions: u32,
+}
+
+/// Liquidity and fee bookkeeping of a tick used as a range bound.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct TickInfo {
+	/// Liquidity of all ranges bounded by the tick.
+	pub liquidity_gross: u128,
+	/// Liquidity added to the pool when the price crosses the tick upwards.
+	pub liquidity_net: i128,
+	/// Fee growth on the other side of the tick from the current price.
+	pub fee_growth_outside_first: u128,
+	pub fee_growth_outside_second: u128,
+}
+
+/// Liquidity provided by `owner` between two ticks of a pool.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct Position<T: Config> {
+	pub owner: T::AccountId,
+	pub pool_id: PoolId,
+	pub tick_lower: i32,
+	pub tick_upper: i32,
+	pub liquidity: u128,
+	/// Fee growth inside the range when the position was last updated.
+	pub fee_growth_inside_first_last: u128,
+	pub fee_growth_inside_second_last: u128,
+	/// Burned liquidity and earned fees waiting to be collected.
+	pub tokens_owed_first: u64,
+	pub tokens_owed_second: u64,
+}
+
+/// Square root of the price at `tick`, `None` outside of `MIN_TICK..=MAX_TICK`.
+pub fn sqrt_price_at_tick(tick: i32) -> Option<FixedU128> {
+	if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+		return None
+	}
+	let power = FixedU128::from_inner(SQRT_TICK_BASE).saturating_pow(tick.unsigned_abs() as usize);
+	if tick < 0 {
+		power.reciprocal()
+	} else {
+		Some(power)
+	}
+}
+
+/// Highest tick whose square root price does not exceed `sqrt_price`.
+pub fn tick_at_sqrt_price(sqrt_price: FixedU128) -> Option<i32> {
+	if sqrt_price < sqrt_price_at_tick(MIN_TICK)? {
+		return None
+	}
+	let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+	while low < high {
+		let middle = low + (high - low + 1) / 2;
+		if sqrt_price_at_tick(middle)? <= sqrt_price {
+			low = middle;
+		} else {
+			high = middle - 1;
+		}
+	}
+	Some(low)
+}
+
+fn mul_div(a: U256, b: U256, denominator: U256, round_up: bool) -> Option<U256> {
+	if denominator.is_zero() {
+		return None
+	}
+	let (quotient, remainder) = a.checked_mul(b)?.div_mod(denominator);
+	if round_up && !remainder.is_zero() {
+		quotient.checked_add(U256::one())
+	} else {
+		Some(quotient)
+	}
+}
+
+fn sorted_inner(a: FixedU128, b: FixedU128) -> (U256, U256) {
+	let (a, b) = (U256::from(a.into_inner()), U256::from(b.into_inner()));
+	if a < b {
+		(a, b)
+	} else {
+		(b, a)
+	}
+}
+
+/// Amount of the first token `liquidity` holds between two square root prices,
+/// `L·(√b - √a) / (√a·√b)`.
+fn first_amount_delta(sqrt_price_a: FixedU128, sqrt_price_b: FixedU128, liquidity: u128, round_up: bool) -> Option<U256> {
+	let (lower, upper) = sorted_inner(sqrt_price_a, sqrt_price_b);
+	let numerator = mul_div(U256::from(liquidity), upper - lower, upper, round_up)?;
+	mul_div(numerator, U256::from(FixedU128::DIV), lower, round_up)
+}
+
+/// Amount of the second token `liquidity` holds between two square root prices, `L·(√b - √a)`.
+fn second_amount_delta(sqrt_price_a: FixedU128, sqrt_price_b: FixedU128, liquidity: u128, round_up: bool) -> Option<U256> {
+	let (lower, upper) = sorted_inner(sqrt_price_a, sqrt_price_b);
+	mul_div(U256::from(liquidity), upper - lower, U256::from(FixedU128::DIV), round_up)
+}
+
+/// Square root price after adding `amount_in` to `liquidity`. Rounded so that the price never
+/// moves further than the input pays for.
+fn next_sqrt_price_from_input(
+	sqrt_price: FixedU128,
+	liquidity: u128,
+	amount_in: U256,
+	first_for_second: bool,
+) -> Option<FixedU128> {
+	let price = U256::from(sqrt_price.into_inner());
+	let next = if first_for_second {
+		// L·√P / (L + x·√P)
+		let scaled_liquidity = U256::from(liquidity).checked_mul(U256::from(FixedU128::DIV))?;
+		let denominator = scaled_liquidity.checked_add(amount_in.checked_mul(price)?)?;
+		mul_div(scaled_liquidity, price, denominator, true)?
+	} else {
+		// √P + y / L
+		price.checked_add(mul_div(amount_in, U256::from(FixedU128::DIV), U256::from(liquidity), false)?)?
+	};
+	next.try_into().ok().map(FixedU128::from_inner)
+}
+
+/// Swaps at most `amount_remaining` (fee included) within a single range of constant
+/// `liquidity`, moving the price from `sqrt_price` towards `sqrt_price_target`.
+///
+/// Returns the reached square root price, the input spent, the output received and the fee.
+pub fn compute_swap_step(
+	sqrt_price: FixedU128,
+	sqrt_price_target: FixedU128,
+	liquidity: u128,
+	amount_remaining: u64,
+	fee: Permill,
+	first_for_second: bool,
+) -> Option<(FixedU128, u64, u64, u64)> {
+	let amount_remaining_less_fee = U256::from(amount_remaining - fee.mul_ceil(amount_remaining));
+	let amount_in_to_target = if first_for_second {
+		first_amount_delta(sqrt_price_target, sqrt_price, liquidity, true)?
+	} else {
+		second_amount_delta(sqrt_price, sqrt_price_target, liquidity, true)?
+	};
+	let reached_target = amount_remaining_less_fee >= amount_in_to_target;
+	let next = if reached_target {
+		sqrt_price_target
+	} else {
+		next_sqrt_price_from_input(sqrt_price, liquidity, amount_remaining_less_fee, first_for_second)?
+	};
+
+	let (amount_in, amount_out) = if first_for_second {
+		(
+			if reached_target { amount_in_to_target } else { first_amount_delta(next, sqrt_price, liquidity, true)? },
+			second_amount_delta(next, sqrt_price, liquidity, false)?,
+		)
+	} else {
+		(
+			if reached_target { amount_in_to_target } else { second_amount_delta(sqrt_price, next, liquidity, true)? },
+			first_amount_delta(sqrt_price, next, liquidity, false)?,
+		)
+	};
+	let amount_in: u64 = amount_in.try_into().ok()?;
+	let amount_out: u64 = amount_out.try_into().ok()?;
+	let fee_amount = if reached_target {
+		let parts = U256::from(fee.deconstruct());
+		mul_div(U256::from(amount_in), parts, U256::from(Permill::ACCURACY) - parts, true)?.try_into().ok()?
+	} else {
+		// The whole remainder is consumed, what the price move did not take is the fee.
+		amount_remaining.saturating_sub(amount_in)
+	};
+	Some((next, amount_in, amount_out, fee_amount))
+}
+
+fn apply_liquidity_delta(liquidity: u128, delta: i128) -> Option<u128> {
+	if delta >= 0 {
+		liquidity.checked_add(delta as u128)
+	} else {
+		liquidity.checked_sub(delta.unsigned_abs())
+	}
+}
+
+/// Fees earned by `liquidity` over a `fee_growth` per unit of liquidity.
+fn fees_owed(liquidity: u128, fee_growth: u128) -> Option<u64> {
+	mul_div(U256::from(liquidity), U256::from(fee_growth), U256::from(FixedU128::DIV), false)?
+		.try_into()
+		.ok()
+}
+
+fn to_amount<T: Config>(amount: Option<U256>) -> Result<u64, DispatchError> {
+	amount.and_then(|amount| amount.try_into().ok()).ok_or_else(|| Error::<T>::Overflow.into())
+}
+
+impl<T: Config> Pallet<T> {
+	fn concentrated_pool(pool_id: PoolId) -> Result<(ConcentratedPool, T::AccountId), DispatchError> {
+		let pool = ConcentratedPools::<T>::get(pool_id).ok_or(Error::<T>::NoPool)?;
+		Ok((pool, Self::pool_account(pool_id)))
+	}
+
+	/// Fee growth per unit of liquidity inside `tick_lower..tick_upper` of both pool tokens.
+	fn fee_growth_inside(pool_id: PoolId, pool: &ConcentratedPool, tick_lower: i32, tick_upper: i32) -> (u128, u128) {
+		let lower = Ticks::<T>::get(pool_id, tick_lower).unwrap_or_default();
+		let upper = Ticks::<T>::get(pool_id, tick_upper).unwrap_or_default();
+		let (below_first, below_second) = if pool.tick >= tick_lower {
+			(lower.fee_growth_outside_first, lower.fee_growth_outside_second)
+		} else {
+			(
+				pool.fee_growth_global_first.wrapping_sub(lower.fee_growth_outside_first),
+				pool.fee_growth_global_second.wrapping_sub(lower.fee_growth_outside_second),
+			)
+		};
+		let (above_first, above_second) = if pool.tick < tick_upper {
+			(upper.fee_growth_outside_first, upper.fee_growth_outside_second)
+		} else {
+			(
+				pool.fee_growth_global_first.wrapping_sub(upper.fee_growth_outside_first),
+				pool.fee_growth_global_second.wrapping_sub(upper.fee_growth_outside_second),
+			)
+		};
+		(
+			pool.fee_growth_global_first.wrapping_sub(below_first).wrapping_sub(above_first),
+			pool.fee_growth_global_second.wrapping_sub(below_second).wrapping_sub(above_second),
+		)
+	}
+
+	/// Adds `liquidity_delta` to the ranges bounded by `tick`. Returns whether the tick no
+	/// longer bounds any range and has to be cleared.
+	fn update_tick(
+		pool_id: PoolId,
+		pool: &ConcentratedPool,
+		tick: i32,
+		liquidity_delta: i128,
+		upper: bool,
+	) -> Result<bool, DispatchError> {
+		let mut info = Ticks::<T>::get(pool_id, tick).unwrap_or_default();
+		let liquidity_gross_before = info.liquidity_gross;
+		info.liquidity_gross =
+			apply_liquidity_delta(liquidity_gross_before, liquidity_delta).ok_or(Error::<T>::Overflow)?;
+		if liquidity_gross_before == 0 && info.liquidity_gross > 0 {
+			// By convention all growth before initialization happened below the tick.
+			if tick <= pool.tick {
+				info.fee_growth_outside_first = pool.fee_growth_global_first;
+				info.fee_growth_outside_second = pool.fee_growth_global_second;
+			}
+			InitializedTicks::<T>::try_mutate(pool_id, |ticks| {
+				let index = ticks.binary_search(&tick).unwrap_or_else(|index| index);
+				ticks.try_insert(index, tick).map_err(|_| Error::<T>::TooManyTicks)
+			})?;
+		}
+		info.liquidity_net = if upper {
+			info.liquidity_net.checked_sub(liquidity_delta)
+		} else {
+			info.liquidity_net.checked_add(liquidity_delta)
+		}
+		.ok_or(Error::<T>::Overflow)?;
+		let cleared = info.liquidity_gross == 0;
+		Ticks::<T>::insert(pool_id, tick, info);
+		Ok(cleared)
+	}
+
+	fn clear_tick(pool_id: PoolId, tick: i32) {
+		Ticks::<T>::remove(pool_id, tick);
+		InitializedTicks::<T>::mutate(pool_id, |ticks| ticks.retain(|t| *t != tick));
+	}
+
+	/// Credits the fees earned by `position` and changes its liquidity by `liquidity_delta`.
+	/// Returns the token amounts the change is worth, rounded in favour of the pool.
+	fn modify_position(
+		pool: &mut ConcentratedPool,
+		position: &mut Position<T>,
+		liquidity_delta: i128,
+	) -> Result<(u64, u64), DispatchError> {
+		let pool_id = position.pool_id;
+		let (tick_lower, tick_upper) = (position.tick_lower, position.tick_upper);
+		let (mut lower_cleared, mut upper_cleared) = (false, false);
+		if liquidity_delta != 0 {
+			lower_cleared = Self::update_tick(pool_id, pool, tick_lower, liquidity_delta, false)?;
+			upper_cleared = Self::update_tick(pool_id, pool, tick_upper, liquidity_delta, true)?;
+		}
+
+		let (inside_first, inside_second) = Self::fee_growth_inside(pool_id, pool, tick_lower, tick_upper);
+		let earned_first = fees_owed(position.liquidity, inside_first.wrapping_sub(position.fee_growth_inside_first_last));
+		let earned_second =
+			fees_owed(position.liquidity, inside_second.wrapping_sub(position.fee_growth_inside_second_last));
+		position.tokens_owed_first = earned_first
+			.and_then(|earned| position.tokens_owed_first.checked_add(earned))
+			.ok_or(Error::<T>::Overflow)?;
+		position.tokens_owed_second = earned_second
+			.and_then(|earned| position.tokens_owed_second.checked_add(earned))
+			.ok_or(Error::<T>::Overflow)?;
+		position.fee_growth_inside_first_last = inside_first;
+		position.fee_growth_inside_second_last = inside_second;
+		position.liquidity = apply_liquidity_delta(position.liquidity, liquidity_delta).ok_or(Error::<T>::NoLiquidity)?;
+
+		if lower_cleared {
+			Self::clear_tick(pool_id, tick_lower);
+		}
+		if upper_cleared {
+			Self::clear_tick(pool_id, tick_upper);
+		}
+
+		let round_up = liquidity_delta > 0;
+		let liquidity = liquidity_delta.unsigned_abs();
+		let lower_price = sqrt_price_at_tick(tick_lower).ok_or(Error::<T>::InvalidTick)?;
+		let upper_price = sqrt_price_at_tick(tick_upper).ok_or(Error::<T>::InvalidTick)?;
+		if pool.tick < tick_lower {
+			Ok((to_amount::<T>(first_amount_delta(lower_price, upper_price, liquidity, round_up))?, 0))
+		} else if pool.tick < tick_upper {
+			pool.liquidity = apply_liquidity_delta(pool.liquidity, liquidity_delta).ok_or(Error::<T>::Overflow)?;
+			Ok((
+				to_amount::<T>(first_amount_delta(pool.sqrt_price, upper_price, liquidity, round_up))?,
+				to_amount::<T>(second_amount_delta(lower_price, pool.sqrt_price, liquidity, round_up))?,
+			))
+		} else {
+			Ok((0, to_amount::<T>(second_amount_delta(lower_price, upper_price, liquidity, round_up))?))
+		}
+	}
+
+	fn position_of(who: &T::AccountId, position_id: PositionId) -> Result<Position<T>, DispatchError> {
+		let position = Positions::<T>::get(position_id).ok_or(Error::<T>::NoPosition)?;
+		ensure!(position.owner == *who, Error::<T>::NotPositionOwner);
+		Ok(position)
+	}
+
+	pub(crate) fn do_create_concentrated_pool(
+		creator: &T::AccountId,
+		first_token_id: u64,
+		second_token_id: u64,
+		fee: Permill,
+		tick_spacing: u32,
+		initial_tick: i32,
+	) -> DispatchResult {
+		ensure!(first_token_id != second_token_id, Error::<T>::DuplicateToken);
+		ensure!(fee <= MAX_SWAP_FEE, Error::<T>::InvalidFee);
+		ensure!(tick_spacing > 0 && tick_spacing <= MAX_TICK_SPACING, Error::<T>::InvalidTick);
+		let sqrt_price = sqrt_price_at_tick(initial_tick).ok_or(Error::<T>::InvalidTick)?;
+
+		let (pool_id, _) = Self::register_pool(creator, PoolKind::Concentrated)?;
+		ConcentratedPools::<T>::insert(
+			pool_id,
+			ConcentratedPool {
+				first_token_id,
+				second_token_id,
+				fee,
+				tick_spacing,
+				sqrt_price,
+				tick: initial_tick,
+				liquidity: 0,
+				fee_growth_global_first: 0,
+				fee_growth_global_second: 0,
+				positions: 0,
+			},
+		);
+		Ok(())
+	}
+
+	/// Opens a position of `liquidity` between `tick_lower` and `tick_upper`.
+	pub(crate) fn do_mint_position(
+		who: &T::AccountId,
+		pool_id: PoolId,
+		tick_lower: i32,
+		tick_upper: i32,
+		liquidity: u128,
+		max_first_amount: u64,
+		max_second_amount: u64,
+	) -> Result<PositionId, DispatchError> {
+		let (mut pool, pool_address) = Self::concentrated_pool(pool_id)?;
+		Self::ensure_pool_active(pool_id)?;
+		let tick_spacing = pool.tick_spacing as i32;
+		ensure!(
+			tick_lower < tick_upper &&
+				tick_lower >= MIN_TICK &&
+				tick_upper <= MAX_TICK &&
+				tick_lower % tick_spacing == 0 &&
+				tick_upper % tick_spacing == 0,
+			Error::<T>::InvalidTick
+		);
+		ensure!(liquidity > 0, Error::<T>::ZeroAmount);
+		let liquidity_delta = i128::try_from(liquidity).map_err(|_| Error::<T>::Overflow)?;
+
+		let mut position = Position {
+			owner: who.clone(),
+			pool_id,
+			tick_lower,
+			tick_upper,
+			liquidity: 0,
+			fee_growth_inside_first_last: 0,
+			fee_growth_inside_second_last: 0,
+			tokens_owed_first: 0,
+			tokens_owed_second: 0,
+		};
+		let (first_amount, second_amount) = Self::modify_position(&mut pool, &mut position, liquidity_delta)?;
+		ensure!(
+			first_amount <= max_first_amount && second_amount <= max_second_amount,
+			Error::<T>::SlippageExceeded
+		);
+		if first_amount > 0 {
+			T::MultiToken::transfer_to(who, &pool_address, &pool.first_token_id, &first_amount)?;
+		}
+		if second_amount > 0 {
+			T::MultiToken::transfer_to(who, &pool_address, &pool.second_token_id, &second_amount)?;
+		}
+
+		pool.positions = pool.positions.checked_add(1).ok_or(Error::<T>::Overflow)?;
+		let position_id = NextPositionId::<T>::get();
+		NextPositionId::<T>::put(position_id + 1);
+		Positions::<T>::insert(position_id, position);
+		ConcentratedPools::<T>::insert(pool_id, pool);
+		Self::deposit_event(Event::<T>::PositionMinted {
+			who: who.clone(),
+			pool_id,
+			position_id,
+			tick_lower,
+			tick_upper,
+			liquidity,
+			first_amount,
+			second_amount,
+		});
+		Ok(position_id)
+	}
+
+	/// Removes `liquidity` from a position. The tokens it was worth are added to the amounts
+	/// owed to the position and paid out by `do_collect`.
+	pub(crate) fn do_burn_position(
+		who: &T::AccountId,
+		position_id: PositionId,
+		liquidity: u128,
+		min_first_amount: u64,
+		min_second_amount: u64,
+	) -> DispatchResult {
+		let mut position = Self::position_of(who, position_id)?;
+		ensure!(liquidity > 0, Error::<T>::ZeroAmount);
+		ensure!(liquidity <= position.liquidity, Error::<T>::NoLiquidity);
+		let (mut pool, _) = Self::concentrated_pool(position.pool_id)?;
+		let liquidity_delta = i128::try_from(liquidity).map_err(|_| Error::<T>::Overflow)?;
+
+		let (first_amount, second_amount) = Self::modify_position(&mut pool, &mut position, -liquidity_delta)?;
+		ensure!(
+			first_amount >= min_first_amount && second_amount >= min_second_amount,
+			Error::<T>::SlippageExceeded
+		);
+		position.tokens_owed_first = position.tokens_owed_first.checked_add(first_amount).ok_or(Error::<T>::Overflow)?;
+		position.tokens_owed_second =
+			position.tokens_owed_second.checked_add(second_amount).ok_or(Error::<T>::Overflow)?;
+
+		ConcentratedPools::<T>::insert(position.pool_id, pool);
+		Positions::<T>::insert(position_id, position);
+		Self::deposit_event(Event::<T>::PositionBurned {
+			who: who.clone(),
+			position_id,
+			liquidity,
+			first_amount,
+			second_amount,
+		});
+		Ok(())
+	}
+
+	/// Pays out the fees earned and the liquidity burned by a position. Positions without
+	/// liquidity are closed once everything is collected.
+	pub(crate) fn do_collect(who: &T::AccountId, position_id: PositionId) -> Result<(u64, u64), DispatchError> {
+		let mut position = Self::position_of(who, position_id)?;
+		let (mut pool, pool_address) = Self::concentrated_pool(position.pool_id)?;
+		if position.liquidity > 0 {
+			Self::modify_position(&mut pool, &mut position, 0)?;
+		}
+
+		let first_amount = core::mem::take(&mut position.tokens_owed_first);
+		let second_amount = core::mem::take(&mut position.tokens_owed_second);
+		if first_amount > 0 {
+			T::MultiToken::transfer_to(&pool_address, who, &pool.first_token_id, &first_amount)?;
+		}
+		if second_amount > 0 {
+			T::MultiToken::transfer_to(&pool_address, who, &pool.second_token_id, &second_amount)?;
+		}
+
+		if position.liquidity == 0 {
+			pool.positions = pool.positions.saturating_sub(1);
+			ConcentratedPools::<T>::insert(position.pool_id, pool);
+			Positions::<T>::remove(position_id);
+		} else {
+			Positions::<T>::insert(position_id, position);
+		}
+		Self::deposit_event(Event::<T>::Collected { who: who.clone(), position_id, first_amount, second_amount });
+		Ok((first_amount, second_amount))
+	}
+
+	/// Weight of a concentrated swap crossing `ticks` initialized ticks.
+	pub fn concentrated_swap_weight(ticks: u32) -> Weight {
+		(1000 as Weight).saturating_add(T::DbWeight::get().reads_writes(1, 1).saturating_mul(ticks as Weight))
+	}
+
+	/// Sells `amount_in` of `token_in`, crossing as many ranges as needed. Input that cannot
+	/// be filled before the price hits the tick bounds is not taken. Returns the output and
+	/// the number of ticks crossed.
+	pub(crate) fn do_concentrated_swap(
+		who: &T::AccountId,
+		pool_id: PoolId,
+		token_in: u64,
+		amount_in: u64,
+		min_amount_out: u64,
+	) -> Result<(u64, u32), DispatchError> {
+		let (mut pool, pool_address) = Self::concentrated_pool(pool_id)?;
+		Self::ensure_pool_active(pool_id)?;
+		let first_for_second = if token_in == pool.first_token_id {
+			true
+		} else {
+			ensure!(token_in == pool.second_token_id, Error::<T>::UnknownToken);
+			false
+		};
+		ensure!(amount_in > 0, Error::<T>::ZeroAmount);
+		let sqrt_price_limit = if first_for_second {
+			sqrt_price_at_tick(MIN_TICK)
+		} else {
+			sqrt_price_at_tick(MAX_TICK)
+		}
+		.ok_or(Error::<T>::InvalidTick)?;
+		let ticks = InitializedTicks::<T>::get(pool_id);
+		let sqrt_price_before = pool.sqrt_price;
+
+		let mut amount_remaining = amount_in;
+		let mut amount_out: u64 = 0;
+		let mut ticks_crossed = 0;
+		while amount_remaining > 0 && pool.sqrt_price != sqrt_price_limit {
+			let next_tick = if first_for_second {
+				ticks.iter().rev().find(|tick| **tick <= pool.tick)
+			} else {
+				ticks.iter().find(|tick| **tick > pool.tick)
+			}
+			.copied();
+			let sqrt_price_target = match next_tick {
+				Some(tick) => sqrt_price_at_tick(tick).ok_or(Error::<T>::InvalidTick)?,
+				None => sqrt_price_limit,
+			};
+
+			let (sqrt_price, step_in, step_out, fee_amount) = compute_swap_step(
+				pool.sqrt_price,
+				sqrt_price_target,
+				pool.liquidity,
+				amount_remaining,
+				pool.fee,
+				first_for_second,
+			)
+			.ok_or(Error::<T>::Overflow)?;
+			amount_remaining = step_in
+				.checked_add(fee_amount)
+				.and_then(|spent| amount_remaining.checked_sub(spent))
+				.ok_or(Error::<T>::Overflow)?;
+			amount_out = amount_out.checked_add(step_out).ok_or(Error::<T>::Overflow)?;
+			if pool.liquidity > 0 {
+				let fee_growth = FixedU128::checked_from_rational(fee_amount, pool.liquidity)
+					.ok_or(Error::<T>::Overflow)?
+					.into_inner();
+				if first_for_second {
+					pool.fee_growth_global_first = pool.fee_growth_global_first.wrapping_add(fee_growth);
+				} else {
+					pool.fee_growth_global_second = pool.fee_growth_global_second.wrapping_add(fee_growth);
+				}
+			}
+
+			pool.sqrt_price = sqrt_price;
+			match next_tick {
+				Some(tick) if sqrt_price == sqrt_price_target => {
+					let mut info = Ticks::<T>::get(pool_id, tick).unwrap_or_default();
+					info.fee_growth_outside_first =
+						pool.fee_growth_global_first.wrapping_sub(info.fee_growth_outside_first);
+					info.fee_growth_outside_second =
+						pool.fee_growth_global_second.wrapping_sub(info.fee_growth_outside_second);
+					let liquidity_delta =
+						if first_for_second { info.liquidity_net.checked_neg() } else { Some(info.liquidity_net) };
+					pool.liquidity = liquidity_delta
+						.and_then(|delta| apply_liquidity_delta(pool.liquidity, delta))
+						.ok_or(Error::<T>::Overflow)?;
+					pool.tick = if first_for_second { tick - 1 } else { tick };
+					Ticks::<T>::insert(pool_id, tick, info);
+					ticks_crossed += 1;
+				},
+				_ => {
+					pool.tick = tick_at_sqrt_price(sqrt_price).ok_or(Error::<T>::InvalidTick)?;
+				},
+			}
+		}
+
+		ensure!(amount_out > 0, Error::<T>::NoLiquidity);
+		ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+		let price = sqrt_price_before.checked_mul(&sqrt_price_before).ok_or(Error::<T>::Overflow)?;
+		let new_price = pool.sqrt_price.checked_mul(&pool.sqrt_price).ok_or(Error::<T>::Overflow)?;
+		Self::check_swap_price_move(pool_id, pool.first_token_id, pool.second_token_id, price, new_price)?;
+		let amount_in = amount_in - amount_remaining;
+		let token_out = if first_for_second { pool.second_token_id } else { pool.first_token_id };
+		T::MultiToken::transfer_to(who, &pool_address, &token_in, &amount_in)?;
+		T::MultiToken::transfer_to(&pool_address, who, &token_out, &amount_out)?;
+		ConcentratedPools::<T>::insert(pool_id, pool);
+		Self::deposit_event(Event::<T>::ConcentratedSwapped {
+			who: who.clone(),
+			pool_id,
+			token_in,
+			amount_in,
+			token_out,
+			amount_out,
+		});
+		Ok((amount_out, ticks_crossed))
+	}
+}
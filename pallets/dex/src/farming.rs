@@ -0,0 +1,199 @@
+//! Liquidity mining for LP shares.
+//!
+//! A farm pays `reward_per_block` of a token to the LP shares of a pool staked in it, from
+//! `start` until `end`, pro rata to the stake. The whole schedule is funded up front into the
+//! escrow account. Rewards are tracked with an accumulator of reward per staked share,
+//! scaled by `REWARD_PER_SHARE_SCALE`, as in MasterChef: a stake is owed
+//! `amount * reward_per_share - reward_debt`, and its debt is reset on every change. Rewards of
+//! blocks nothing was staked in are set aside for the funder to reclaim once the farm ended.
+
+use crate::{BalanceOf, Config, Error, Event, Farms, NextFarmId, Pallet, PoolId, PoolKind, PoolKinds, Stakes};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{ensure, pallet_prelude::DispatchResult};
+use pallet_multi_token::multitoken::MultiToken;
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{Saturating, UniqueSaturatedInto},
+	RuntimeDebug,
+};
+
+/// Identifier of a farm.
+pub type FarmId = u64;
+
+/// Fixed-point scale of `Farm::reward_per_share`.
+pub const REWARD_PER_SHARE_SCALE: u128 = 1_000_000_000_000;
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct Farm<T: Config> {
+	pub funder: T::AccountId,
+	pub pool_id: PoolId,
+	pub reward_token: u64,
+	pub reward_per_block: u64,
+	pub start: T::BlockNumber,
+	pub end: T::BlockNumber,
+	pub total_staked: u64,
+	/// Rewards paid per staked share since `start`, times `REWARD_PER_SHARE_SCALE`.
+	pub reward_per_share: u128,
+	/// Block up to which `reward_per_share` is accrued.
+	pub last_reward_block: T::BlockNumber,
+	/// Rewards of the blocks nothing was staked in, not reclaimed yet.
+	pub unallocated: u64,
+}
+
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Stake {
+	pub amount: u64,
+	/// Part of `amount * reward_per_share` already paid or accrued before staking.
+	pub reward_debt: u128,
+}
+
+impl<T: Config> Farm<T> {
+	/// Accrues the rewards of the blocks since `last_reward_block`, up to `now`.
+	fn accrue(&mut self, now: T::BlockNumber) {
+		let until = if now < self.end { now } else { self.end };
+		if until <= self.last_reward_block {
+			return
+		}
+		let blocks: u128 = until.saturating_sub(self.last_reward_block).unique_saturated_into();
+		let reward = blocks.saturating_mul(self.reward_per_block as u128);
+		if self.total_staked > 0 {
+			self.reward_per_share = self
+				.reward_per_share
+				.saturating_add(reward.saturating_mul(REWARD_PER_SHARE_SCALE) / self.total_staked as u128);
+		} else {
+			self.unallocated = self.unallocated.saturating_add(reward as u64);
+		}
+		self.last_reward_block = until;
+	}
+
+	fn reward_debt(&self, amount: u64) -> u128 {
+		(amount as u128).saturating_mul(self.reward_per_share) / REWARD_PER_SHARE_SCALE
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	pub(crate) fn do_create_farm(
+		funder: &T::AccountId,
+		pool_id: PoolId,
+		reward_token: u64,
+		reward_per_block: u64,
+		start: T::BlockNumber,
+		end: T::BlockNumber,
+	) -> DispatchResult {
+		let kind = PoolKinds::<T>::get(pool_id).ok_or(Error::<T>::NoPool)?;
+		ensure!(!matches!(kind, PoolKind::Concentrated | PoolKind::Lbp), Error::<T>::NoPool);
+		ensure!(reward_per_block > 0, Error::<T>::ZeroAmount);
+		ensure!(start >= frame_system::Pallet::<T>::block_number() && start < end, Error::<T>::InvalidSchedule);
+		let blocks: u64 = end.saturating_sub(start).unique_saturated_into();
+		let total_reward = reward_per_block.checked_mul(blocks).ok_or(Error::<T>::Overflow)?;
+		T::MultiToken::transfer_to(funder, &Self::escrow_account(), &reward_token, &total_reward)?;
+
+		let farm_id = NextFarmId::<T>::get();
+		NextFarmId::<T>::put(farm_id + 1);
+		Farms::<T>::insert(
+			farm_id,
+			Farm {
+				funder: funder.clone(),
+				pool_id,
+				reward_token,
+				reward_per_block,
+				start,
+				end,
+				total_staked: 0,
+				reward_per_share: 0,
+				last_reward_block: start,
+				unallocated: 0,
+			},
+		);
+		Self::deposit_event(Event::<T>::FarmCreated { farm_id, pool_id, reward_token, reward_per_block, start, end });
+		Ok(())
+	}
+
+	/// Pays `who` the rewards its stake accrued, then changes the stake by `staked` and
+	/// `unstaked` LP shares.
+	fn update_stake(who: &T::AccountId, farm_id: FarmId, staked: u64, unstaked: u64) -> Result<u64, sp_runtime::DispatchError> {
+		let mut farm = Farms::<T>::get(farm_id).ok_or(Error::<T>::NoFarm)?;
+		let mut stake = Stakes::<T>::get(farm_id, who);
+		farm.accrue(frame_system::Pallet::<T>::block_number());
+
+		let reward = farm.reward_debt(stake.amount).saturating_sub(stake.reward_debt) as u64;
+		if reward > 0 {
+			T::MultiToken::transfer_to(&Self::escrow_account(), who, &farm.reward_token, &reward)?;
+			Self::deposit_event(Event::<T>::RewardsClaimed { who: who.clone(), farm_id, amount: reward });
+		}
+
+		if staked > 0 {
+			let balance = BalanceOf::<T>::get(farm.pool_id, who);
+			ensure!(balance >= staked, Error::<T>::NoLiquidity);
+			BalanceOf::<T>::insert(farm.pool_id, who, balance - staked);
+			BalanceOf::<T>::mutate(farm.pool_id, Self::escrow_account(), |escrowed| *escrowed += staked);
+			stake.amount += staked;
+			farm.total_staked += staked;
+		}
+		if unstaked > 0 {
+			ensure!(stake.amount >= unstaked, Error::<T>::NotEnoughStaked);
+			BalanceOf::<T>::mutate(farm.pool_id, Self::escrow_account(), |escrowed| *escrowed -= unstaked);
+			BalanceOf::<T>::mutate(farm.pool_id, who, |balance| *balance += unstaked);
+			stake.amount -= unstaked;
+			farm.total_staked -= unstaked;
+		}
+
+		stake.reward_debt = farm.reward_debt(stake.amount);
+		if stake.amount == 0 {
+			Stakes::<T>::remove(farm_id, who);
+		} else {
+			Stakes::<T>::insert(farm_id, who, stake);
+		}
+		Farms::<T>::insert(farm_id, farm);
+		Ok(reward)
+	}
+
+	pub(crate) fn do_stake(who: &T::AccountId, farm_id: FarmId, amount: u64) -> DispatchResult {
+		ensure!(amount > 0, Error::<T>::ZeroAmount);
+		Self::update_stake(who, farm_id, amount, 0)?;
+		Self::deposit_event(Event::<T>::Staked { who: who.clone(), farm_id, amount });
+		Ok(())
+	}
+
+	pub(crate) fn do_unstake(who: &T::AccountId, farm_id: FarmId, amount: u64) -> DispatchResult {
+		ensure!(amount > 0, Error::<T>::ZeroAmount);
+		Self::update_stake(who, farm_id, 0, amount)?;
+		Self::deposit_event(Event::<T>::Unstaked { who: who.clone(), farm_id, amount });
+		Ok(())
+	}
+
+	pub(crate) fn do_claim(who: &T::AccountId, farm_id: FarmId) -> DispatchResult {
+		Self::update_stake(who, farm_id, 0, 0)?;
+		Ok(())
+	}
+
+	/// Returns the rewards of `farm_id` nobody was staked for to its funder, once it ended.
+	pub(crate) fn do_reclaim_rewards(who: &T::AccountId, farm_id: FarmId) -> DispatchResult {
+		let mut farm = Farms::<T>::get(farm_id).ok_or(Error::<T>::NoFarm)?;
+		ensure!(farm.funder == *who, Error::<T>::NotFarmFunder);
+		let now = frame_system::Pallet::<T>::block_number();
+		ensure!(now >= farm.end, Error::<T>::FarmNotEnded);
+		farm.accrue(now);
+
+		let amount = farm.unallocated;
+		if amount > 0 {
+			T::MultiToken::refund(&Self::escrow_account(), who, &farm.reward_token, &amount)?;
+		}
+		farm.unallocated = 0;
+		Farms::<T>::insert(farm_id, farm);
+		Self::deposit_event(Event::<T>::RewardsReclaimed { farm_id, funder: who.clone(), amount });
+		Ok(())
+	}
+
+	/// Rewards `who` could claim from `farm_id` now.
+	pub fn pending_rewards(farm_id: FarmId, who: &T::AccountId) -> u64 {
+		let (mut farm, stake) = match Farms::<T>::get(farm_id) {
+			Some(farm) => (farm, Stakes::<T>::get(farm_id, who)),
+			None => return 0,
+		};
+		farm.accrue(frame_system::Pallet::<T>::block_number());
+		farm.reward_debt(stake.amount).saturating_sub(stake.reward_debt) as u64
+	}
+}
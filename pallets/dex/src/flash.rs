@@ -0,0 +1,94 @@
+//! Flash loans and flash swaps from constant-product pools.
+//!
+//! Reserves are lent out before the borrower callback runs and the pool is locked until it
+//! returns. Afterwards the balances, less the swap fee on whatever was paid in, must keep the
+//! product of the reserves at least where it was, the same check Uniswap v2 applies to flash
+//! swaps. Repaying in the other pool token is therefore allowed, within the `MaxPriceMove` limit
+//! of regular swaps. A failed check reverts the whole extrinsic, loan included.
+
+use crate::{BatchAuctionPools, Config, Error, Event, FirstTokeId, LockedPools, Pallet, PoolId, SecondTokeId};
+use frame_support::{ensure, pallet_prelude::DispatchResult, traits::Get};
+use pallet_multi_token::multitoken::MultiToken;
+use sp_core::U256;
+
+/// Receiver of flash loans, called while the borrower holds the loaned reserves.
+pub trait FlashBorrower<AccountId> {
+	/// Uses the loan and pays the pool back, plus fees, to `pool_account`.
+	fn on_flash_loan(
+		borrower: &AccountId,
+		pool_account: &AccountId,
+		first_token_id: u64,
+		first_amount: u64,
+		second_token_id: u64,
+		second_amount: u64,
+		data: &[u8],
+	) -> DispatchResult;
+}
+
+/// Never repays, so every flash loan is reverted.
+impl<AccountId> FlashBorrower<AccountId> for () {
+	fn on_flash_loan(_: &AccountId, _: &AccountId, _: u64, _: u64, _: u64, _: u64, _: &[u8]) -> DispatchResult {
+		Ok(())
+	}
+}
+
+/// Pool balance scaled by 100 with the fee on the `paid_in` part taken off.
+fn fee_adjusted(balance: u64, reserve_left: u64, fee: u64) -> U256 {
+	let paid_in = balance.saturating_sub(reserve_left);
+	U256::from(balance) * U256::from(100) - U256::from(paid_in) * U256::from(fee)
+}
+
+impl<T: Config> Pallet<T> {
+	pub(crate) fn do_flash_loan(
+		borrower: &T::AccountId,
+		pool_id: PoolId,
+		first_amount: u64,
+		second_amount: u64,
+		data: &[u8],
+	) -> DispatchResult {
+		let pool_address = Self::constant_product_pool(pool_id)?;
+		Self::ensure_pool_active(pool_id)?;
+		ensure!(!BatchAuctionPools::<T>::contains_key(pool_id), Error::<T>::BatchAuctionOnly);
+		ensure!(first_amount > 0 || second_amount > 0, Error::<T>::ZeroAmount);
+		let first_token_id = FirstTokeId::<T>::get(pool_id);
+		let second_token_id = SecondTokeId::<T>::get(pool_id);
+		let first_reserve = T::MultiToken::balances(&first_token_id, &pool_address);
+		let second_reserve = T::MultiToken::balances(&second_token_id, &pool_address);
+		ensure!(first_amount < first_reserve && second_amount < second_reserve, Error::<T>::NoLiquidity);
+		Self::block_start_price(pool_id, &pool_address);
+
+		if first_amount > 0 {
+			T::MultiToken::transfer_to(&pool_address, borrower, &first_token_id, &first_amount)?;
+		}
+		if second_amount > 0 {
+			T::MultiToken::transfer_to(&pool_address, borrower, &second_token_id, &second_amount)?;
+		}
+		LockedPools::<T>::insert(pool_id, ());
+		T::FlashBorrower::on_flash_loan(
+			borrower,
+			&pool_address,
+			first_token_id,
+			first_amount,
+			second_token_id,
+			second_amount,
+			data,
+		)?;
+		LockedPools::<T>::remove(pool_id);
+
+		let fee = T::Fee::get();
+		let first_balance = T::MultiToken::balances(&first_token_id, &pool_address);
+		let second_balance = T::MultiToken::balances(&second_token_id, &pool_address);
+		let first_adjusted = fee_adjusted(first_balance, first_reserve - first_amount, fee);
+		let second_adjusted = fee_adjusted(second_balance, second_reserve - second_amount, fee);
+		ensure!(
+			first_adjusted * second_adjusted >=
+				U256::from(first_reserve) * U256::from(second_reserve) * U256::from(100 * 100),
+			Error::<T>::FlashLoanNotRepaid
+		);
+		Self::check_price_move(pool_id, &pool_address, Self::spot_prices(pool_id, &pool_address).0)?;
+		Self::update_price_observations(pool_id, &pool_address);
+
+		Self::deposit_event(Event::<T>::FlashLoan { borrower: borrower.clone(), pool_id, first_amount, second_amount });
+		Ok(())
+	}
+}
@@ -0,0 +1,170 @@
+//! Liquidity bootstrapping pools.
+//!
+//! Two-asset weighted pools whose weights move linearly from `start_weights` to `end_weights`
+//! between the `start` and `end` blocks, and which only trade in that window. Launching a token
+//! with a high starting weight that falls over the sale lets its price drop until buyers step in,
+//! like a Dutch auction. Only the owner, the creator of the pool, provides liquidity, so there
+//! are no LP shares; the owner may only withdraw outside the sale.
+
+use crate::{
+	weighted::{out_given_in, spot_price, MAX_IN_RATIO_DIVISOR, MAX_OUT_RATIO_DIVISOR, MAX_SWAP_FEE, MIN_WEIGHT_PERCENT},
+	Config, Error, Event, LbpPools, Pallet, PoolDeposits, PoolId, PoolKind,
+};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{ensure, pallet_prelude::DispatchResult};
+use pallet_multi_token::multitoken::MultiToken;
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{Saturating, UniqueSaturatedInto},
+	DispatchError, Permill, RuntimeDebug,
+};
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct LbpPool<T: Config> {
+	pub assets: [u64; 2],
+	/// Denormalized weights at `start`, in the order of `assets`.
+	pub start_weights: [u32; 2],
+	/// Denormalized weights from `end` on.
+	pub end_weights: [u32; 2],
+	pub start: T::BlockNumber,
+	pub end: T::BlockNumber,
+	pub swap_fee: Permill,
+}
+
+impl<T: Config> LbpPool<T> {
+	/// Weights of the pool at block `now`.
+	pub fn weights_at(&self, now: T::BlockNumber) -> [u32; 2] {
+		if now <= self.start {
+			return self.start_weights
+		}
+		if now >= self.end {
+			return self.end_weights
+		}
+		let elapsed: u64 = now.saturating_sub(self.start).unique_saturated_into();
+		let duration: u64 = self.end.saturating_sub(self.start).unique_saturated_into();
+		let mut weights = self.start_weights;
+		for (weight, end_weight) in weights.iter_mut().zip(self.end_weights.iter()) {
+			let (from, to) = (*weight as u64, *end_weight as u64);
+			*weight = if to >= from {
+				from + (to - from) * elapsed / duration
+			} else {
+				from - (from - to) * elapsed / duration
+			} as u32;
+		}
+		weights
+	}
+
+	/// Whether the pool trades at block `now`.
+	pub fn is_trading(&self, now: T::BlockNumber) -> bool {
+		self.start <= now && now < self.end
+	}
+}
+
+fn valid_weights(weights: [u32; 2]) -> bool {
+	let total_weight = weights[0] as u64 + weights[1] as u64;
+	weights.iter().all(|w| *w > 0 && *w as u64 * 100 >= total_weight * MIN_WEIGHT_PERCENT as u64)
+}
+
+impl<T: Config> Pallet<T> {
+	/// Pool of `pool_id` and its account, checking `who` owns it.
+	fn owned_lbp(who: &T::AccountId, pool_id: PoolId) -> Result<(LbpPool<T>, T::AccountId), DispatchError> {
+		let pool = LbpPools::<T>::get(pool_id).ok_or(Error::<T>::NoPool)?;
+		let (owner, _) = PoolDeposits::<T>::get(pool_id).ok_or(Error::<T>::NoPool)?;
+		ensure!(*who == owner, Error::<T>::NotPoolCreator);
+		Ok((pool, Self::pool_account(pool_id)))
+	}
+
+	#[allow(clippy::too_many_arguments)]
+	pub(crate) fn do_create_lbp(
+		creator: &T::AccountId,
+		assets: [u64; 2],
+		amounts: [u64; 2],
+		start_weights: [u32; 2],
+		end_weights: [u32; 2],
+		start: T::BlockNumber,
+		end: T::BlockNumber,
+		swap_fee: Permill,
+	) -> DispatchResult {
+		ensure!(assets[0] != assets[1], Error::<T>::DuplicateToken);
+		ensure!(amounts.iter().all(|amount| *amount > 0), Error::<T>::ZeroAmount);
+		ensure!(swap_fee <= MAX_SWAP_FEE, Error::<T>::InvalidFee);
+		ensure!(valid_weights(start_weights) && valid_weights(end_weights), Error::<T>::InvalidWeights);
+		ensure!(start >= frame_system::Pallet::<T>::block_number() && start < end, Error::<T>::InvalidSchedule);
+
+		let (pool_id, pool_address) = Self::register_pool(creator, PoolKind::Lbp)?;
+		for (id, amount) in assets.iter().zip(amounts.iter()) {
+			T::MultiToken::transfer_to(creator, &pool_address, id, amount)?;
+		}
+		LbpPools::<T>::insert(pool_id, LbpPool { assets, start_weights, end_weights, start, end, swap_fee });
+		Ok(())
+	}
+
+	pub(crate) fn do_add_lbp_liquidity(who: &T::AccountId, pool_id: PoolId, amounts: [u64; 2]) -> DispatchResult {
+		let (pool, pool_address) = Self::owned_lbp(who, pool_id)?;
+		for (id, amount) in pool.assets.iter().zip(amounts.iter()).filter(|(_, amount)| **amount > 0) {
+			T::MultiToken::transfer_to(who, &pool_address, id, amount)?;
+		}
+		Self::deposit_event(Event::<T>::LbpLiquidityAdded { pool_id, amounts });
+		Ok(())
+	}
+
+	pub(crate) fn do_remove_lbp_liquidity(who: &T::AccountId, pool_id: PoolId, amounts: [u64; 2]) -> DispatchResult {
+		let (pool, pool_address) = Self::owned_lbp(who, pool_id)?;
+		ensure!(!pool.is_trading(frame_system::Pallet::<T>::block_number()), Error::<T>::SaleInProgress);
+		for (id, amount) in pool.assets.iter().zip(amounts.iter()).filter(|(_, amount)| **amount > 0) {
+			T::MultiToken::transfer_to(&pool_address, who, id, amount)?;
+		}
+		Self::deposit_event(Event::<T>::LbpLiquidityRemoved { pool_id, amounts });
+		Ok(())
+	}
+
+	pub(crate) fn do_lbp_swap(
+		who: &T::AccountId,
+		pool_id: PoolId,
+		token_in: u64,
+		amount_in: u64,
+		min_amount_out: u64,
+	) -> Result<u64, DispatchError> {
+		let pool = LbpPools::<T>::get(pool_id).ok_or(Error::<T>::NoPool)?;
+		let pool_address = Self::pool_account(pool_id);
+		Self::ensure_pool_active(pool_id)?;
+		let now = frame_system::Pallet::<T>::block_number();
+		ensure!(pool.is_trading(now), Error::<T>::TradingClosed);
+		let i = pool.assets.iter().position(|id| *id == token_in).ok_or(Error::<T>::UnknownToken)?;
+		let token_out = pool.assets[1 - i];
+		ensure!(amount_in > 0, Error::<T>::ZeroAmount);
+		let balance_in = T::MultiToken::balances(&token_in, &pool_address);
+		let balance_out = T::MultiToken::balances(&token_out, &pool_address);
+		ensure!(amount_in <= balance_in / MAX_IN_RATIO_DIVISOR, Error::<T>::MaxInRatio);
+
+		let weights = pool.weights_at(now);
+		let amount_out = out_given_in(balance_in, weights[i], balance_out, weights[1 - i], amount_in, pool.swap_fee)
+			.ok_or(Error::<T>::Overflow)?;
+		ensure!(amount_out <= balance_out / MAX_OUT_RATIO_DIVISOR, Error::<T>::MaxOutRatio);
+		ensure!(amount_out > 0, Error::<T>::NoLiquidity);
+		ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+		let price = spot_price(balance_in, weights[i], balance_out, weights[1 - i]).ok_or(Error::<T>::Overflow)?;
+		let new_price = spot_price(balance_in + amount_in, weights[i], balance_out - amount_out, weights[1 - i])
+			.ok_or(Error::<T>::Overflow)?;
+		Self::check_swap_price_move(pool_id, token_in, token_out, price, new_price)?;
+
+		T::MultiToken::transfer_to(who, &pool_address, &token_in, &amount_in)?;
+		T::MultiToken::transfer_to(&pool_address, who, &token_out, &amount_out)?;
+		Self::deposit_event(Event::<T>::WeightedSwapped {
+			who: who.clone(),
+			pool_id,
+			token_in,
+			amount_in,
+			token_out,
+			amount_out,
+		});
+		Ok(amount_out)
+	}
+
+	/// Current weights of the LBP `pool_id`, in the order of its assets.
+	pub fn lbp_weights(pool_id: PoolId) -> Option<[u32; 2]> {
+		LbpPools::<T>::get(pool_id).map(|pool| pool.weights_at(frame_system::Pallet::<T>::block_number()))
+	}
+}
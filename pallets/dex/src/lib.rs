@@ -9,16 +9,95 @@ mod mock;
 #[cfg(test)]
 mod test;
 
+pub mod auction;
+pub mod commit_reveal;
+pub mod concentrated;
+pub mod farming;
+pub mod flash;
+pub mod lbp;
+pub mod migrations;
+pub mod oracle;
+pub mod orders;
+pub mod stable;
+pub mod swap;
+pub mod weighted;
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::traits::Contains;
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use sp_std::marker::PhantomData;
+
+/// Identifier of a pool, shared by all pool kinds.
+pub type PoolId = u64;
+
+/// LP shares of every constant-product pool that are locked forever on creation, so the
+/// share price can never be inflated from a dust-sized supply.
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+/// Identifier of a concentrated liquidity position.
+pub type PositionId = u64;
+
+/// Pricing curve a pool trades on.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum PoolKind {
+	/// Two assets on the `x * y = k` curve.
+	ConstantProduct,
+	/// Two or more pegged assets on the StableSwap invariant.
+	StableSwap,
+	/// Two or more assets with custom weights on the Balancer invariant.
+	Weighted,
+	/// Two assets with liquidity concentrated in price ranges.
+	Concentrated,
+	/// Two assets with weights shifting over a sale, liquidity from the creator only.
+	Lbp,
+}
+
+/// The escrow account of the pallet and the accounts of its pools. Multi-token lets them
+/// refund deposits regardless of freezes and transfer checks, which still apply to swaps.
+pub struct PalletAccounts<T>(PhantomData<T>);
+
+impl<T: Config> Contains<T::AccountId> for PalletAccounts<T> {
+	fn contains(who: &T::AccountId) -> bool {
+		*who == Pallet::<T>::escrow_account() || PoolIds::<T>::contains_key(who)
+	}
+}
+
 // pub use pallet_multi_token;
 #[frame_support::pallet]
 pub mod pallet {
-	use frame_support::pallet_prelude::*;
+	use frame_support::{
+		pallet_prelude::*,
+		traits::{Currency, ReservableCurrency},
+		PalletId,
+	};
 	use frame_system::pallet_prelude::*;
-	use sp_std::vec::Vec;
+	use sp_std::{vec, vec::Vec};
+	use sp_runtime::{
+		traits::{
+			AccountIdConversion, Hash as HashT, IntegerSquareRoot, Saturating, TrailingZeroInput, UniqueSaturatedInto,
+		},
+		FixedPointNumber, FixedU128, PerThing, Permill,
+	};
 	use pallet_multi_token::multitoken::MultiToken;
+	use crate::auction::SwapIntent;
+	use crate::concentrated::{ConcentratedPool, Position, TickInfo};
+	use crate::farming::{Farm, FarmId, Stake};
+	use crate::flash::FlashBorrower;
+	use crate::lbp::LbpPool;
+	use crate::oracle::{Observation, PriceOracle};
+	use crate::orders::{LimitOrder, OrderId};
+	use crate::stable::StablePool;
+	use crate::weighted::WeightedPool;
+	use crate::{PoolId, PoolKind, PositionId, MINIMUM_LIQUIDITY};
+	use sp_core::U256;
+
+	/// Version 1 keys pools by `PoolId` and maps pool accounts back to them in `PoolIds`.
+	pub(crate) const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
 
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	/// Configure the pallet by specifying the parameters and types on which it depends.
@@ -28,128 +107,1426 @@ pub mod pallet {
 		type MultiToken: MultiToken<Self::AccountId>;
 		#[pallet::constant]
         type Fee: Get<u64>;
+		/// How many per-block price observations are kept for TWAP queries.
+		#[pallet::constant]
+		type MaxObservations: Get<u32>;
+		/// Used to derive the accounts holding pool reserves.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+		/// Maximum number of assets in a multi-asset pool.
+		#[pallet::constant]
+		type MaxPoolAssets: Get<u32>;
+		/// Fee taken from the output of StableSwap trades.
+		#[pallet::constant]
+		type StableSwapFee: Get<Permill>;
+		/// Maximum number of initialized ticks in a concentrated liquidity pool.
+		#[pallet::constant]
+		type MaxTicks: Get<u32>;
+		/// Called with the reserves lent out by `flash_loan`.
+		type FlashBorrower: FlashBorrower<Self::AccountId>;
+		/// Native currency the pool creation deposit is reserved in.
+		type Currency: ReservableCurrency<Self::AccountId>;
+		/// Reserved from the creator of a pool until it is destroyed.
+		#[pallet::constant]
+		type PoolDeposit: Get<DepositBalanceOf<Self>>;
+		/// Origin allowed to pause and resume pools.
+		type AdminOrigin: EnsureOrigin<Self::Origin>;
+		/// Largest change of a pool price, relative to its price at the start of the block,
+		/// that swaps may cause within the block. Pools of more than two assets limit the
+		/// price of every pair traded on its own.
+		#[pallet::constant]
+		type MaxPriceMove: Get<Permill>;
+		/// Maximum number of open limit orders against a pool.
+		#[pallet::constant]
+		type MaxOrdersPerPool: Get<u32>;
+		/// Smallest input a limit order can be placed with, so that dust can't fill the book.
+		#[pallet::constant]
+		type MinLimitOrderAmount: Get<u64>;
+		/// Maximum number of blocks a limit order can stay open for.
+		#[pallet::constant]
+		type MaxOrderLifetime: Get<Self::BlockNumber>;
+		/// Maximum number of swap intents against a batch-auction pool in a block.
+		#[pallet::constant]
+		type MaxIntentsPerBatch: Get<u32>;
+		/// Maximum number of batch-auction pools cleared in a block.
+		#[pallet::constant]
+		type MaxAuctionsPerBlock: Get<u32>;
+		/// Reserved from traders while their swap commitment is unrevealed.
+		#[pallet::constant]
+		type CommitmentDeposit: Get<DepositBalanceOf<Self>>;
+		/// Number of blocks after its commitment a swap can be revealed in.
+		#[pallet::constant]
+		type RevealPeriod: Get<Self::BlockNumber>;
+		/// Maximum number of swap commitments made in a block.
+		#[pallet::constant]
+		type MaxCommitmentsPerBlock: Get<u32>;
+		/// Origin allowed to create farms, resolving to the account funding their rewards.
+		type FarmOrigin: EnsureOrigin<Self::Origin, Success = Self::AccountId>;
 	}
 
+	pub type DepositBalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
 	// Pallets use events to inform users when important changes are made.
 	// Event documentation should end with an array that provides descriptive names for parameters.
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
+		PoolCreated { pool_id: PoolId, kind: PoolKind, creator: T::AccountId, deposit: DepositBalanceOf<T> },
+		PoolDestroyed { pool_id: PoolId, creator: T::AccountId, deposit: DepositBalanceOf<T> },
+		PoolPaused { pool_id: PoolId },
+		PoolResumed { pool_id: PoolId },
+		StableSwapped { who: T::AccountId, pool_id: PoolId, token_in: u64, amount_in: u64, token_out: u64, amount_out: u64 },
+		StableLiquidityAdded { who: T::AccountId, pool_id: PoolId, amounts: Vec<u64>, liquidity: u64 },
+		StableLiquidityRemoved { who: T::AccountId, pool_id: PoolId, liquidity: u64, amounts: Vec<u64> },
+		Swapped { who: T::AccountId, to: T::AccountId, pool_id: PoolId, token_in: u64, amount_in: u64, token_out: u64, amount_out: u64 },
+		LiquidityAdded {
+			who: T::AccountId,
+			to: T::AccountId,
+			pool_id: PoolId,
+			first_token_amount: u64,
+			second_token_amount: u64,
+			liquidity: u64,
+		},
+		LiquidityRemoved {
+			who: T::AccountId,
+			to: T::AccountId,
+			pool_id: PoolId,
+			liquidity: u64,
+			first_token_amount: u64,
+			second_token_amount: u64,
+		},
+		WeightedSwapped { who: T::AccountId, pool_id: PoolId, token_in: u64, amount_in: u64, token_out: u64, amount_out: u64 },
+		WeightedPoolJoined { who: T::AccountId, pool_id: PoolId, amounts: Vec<u64>, liquidity: u64 },
+		WeightedPoolExited { who: T::AccountId, pool_id: PoolId, liquidity: u64, amounts: Vec<u64> },
+		PositionMinted {
+			who: T::AccountId,
+			pool_id: PoolId,
+			position_id: PositionId,
+			tick_lower: i32,
+			tick_upper: i32,
+			liquidity: u128,
+			first_amount: u64,
+			second_amount: u64,
+		},
+		PositionBurned { who: T::AccountId, position_id: PositionId, liquidity: u128, first_amount: u64, second_amount: u64 },
+		Collected { who: T::AccountId, position_id: PositionId, first_amount: u64, second_amount: u64 },
+		FlashLoan { borrower: T::AccountId, pool_id: PoolId, first_amount: u64, second_amount: u64 },
+		ConcentratedSwapped { who: T::AccountId, pool_id: PoolId, token_in: u64, amount_in: u64, token_out: u64, amount_out: u64 },
+		LimitOrderPlaced {
+			order_id: OrderId,
+			owner: T::AccountId,
+			pool_id: PoolId,
+			token_in: u64,
+			amount_in: u64,
+			min_amount_out: u64,
+			expires_at: T::BlockNumber,
+		},
+		LimitOrderFilled { order_id: OrderId, owner: T::AccountId, amount_out: u64 },
+		LimitOrderCancelled { order_id: OrderId, owner: T::AccountId },
+		LimitOrderExpired { order_id: OrderId, owner: T::AccountId },
+		BatchAuctionSet { pool_id: PoolId, enabled: bool },
+		SwapIntentSubmitted { who: T::AccountId, pool_id: PoolId, token_in: u64, amount_in: u64, min_amount_out: u64 },
+		SwapIntentFilled { who: T::AccountId, pool_id: PoolId, token_in: u64, amount_in: u64, token_out: u64, amount_out: u64 },
+		SwapIntentRefunded { who: T::AccountId, pool_id: PoolId, token_in: u64, amount_in: u64 },
+		/// `intents` were cleared at `price`, in second tokens per first token.
+		BatchCleared { pool_id: PoolId, price: FixedU128, intents: u32 },
+		SwapCommitted { who: T::AccountId, commitment: T::Hash },
+		SwapRevealed { who: T::AccountId, commitment: T::Hash },
+		CommitmentForfeited { who: T::AccountId, commitment: T::Hash, deposit: DepositBalanceOf<T> },
+		FarmCreated {
+			farm_id: FarmId,
+			pool_id: PoolId,
+			reward_token: u64,
+			reward_per_block: u64,
+			start: T::BlockNumber,
+			end: T::BlockNumber,
+		},
+		Staked { who: T::AccountId, farm_id: FarmId, amount: u64 },
+		Unstaked { who: T::AccountId, farm_id: FarmId, amount: u64 },
+		RewardsClaimed { who: T::AccountId, farm_id: FarmId, amount: u64 },
+		RewardsReclaimed { farm_id: FarmId, funder: T::AccountId, amount: u64 },
+		LbpLiquidityAdded { pool_id: PoolId, amounts: [u64; 2] },
+		LbpLiquidityRemoved { pool_id: PoolId, amounts: [u64; 2] },
 	}
 
 	#[pallet::error]
 	pub enum Error<T> {
 		WrongRatio,
 		NoLiquidity,
+		NoPool,
+		PoolExists,
+		UnknownToken,
+		DuplicateToken,
+		TooFewTokens,
+		TooManyTokens,
+		ShouldBeSameLength,
+		InvalidAmplification,
+		InvalidWeights,
+		InvalidFee,
+		MaxInRatio,
+		MaxOutRatio,
+		ZeroAmount,
+		SlippageExceeded,
+		Overflow,
+		InvalidTick,
+		TooManyTicks,
+		NoPosition,
+		NotPositionOwner,
+		PoolLocked,
+		FlashLoanNotRepaid,
+		InsufficientLiquidity,
+		PoolNotEmpty,
+		NotPoolCreator,
+		PoolPaused,
+		PriceMoveTooLarge,
+		TooManyOrders,
+		OrderTooSmall,
+		NoOrder,
+		NotOrderOwner,
+		OrderExpired,
+		OrderLifetimeTooLong,
+		BatchAuctionOnly,
+		NotBatchAuction,
+		TooManyIntents,
+		CommitmentExists,
+		NoCommitment,
+		RevealTooEarly,
+		CommitmentExpired,
+		TooManyCommitments,
+		NoFarm,
+		InvalidSchedule,
+		NotEnoughStaked,
+		NotFarmFunder,
+		FarmNotEnded,
+		TradingClosed,
+		SaleInProgress,
 	}
 
 	#[pallet::storage]
-	pub type Pool<T: Config> = StorageValue<_, T::AccountId, OptionQuery>;
+	pub type NextPoolId<T: Config> = StorageValue<_, PoolId, ValueQuery>;
+
+	#[pallet::storage]
+	pub type Pool<T: Config> = StorageMap<_, Blake2_128Concat, PoolId, T::AccountId, OptionQuery>;
+
+	/// Pool of each pool account.
+	#[pallet::storage]
+	pub type PoolIds<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, PoolId, OptionQuery>;
+
+	/// Creator of every pool and the deposit reserved from them.
+	#[pallet::storage]
+	pub type PoolDeposits<T: Config> =
+		StorageMap<_, Blake2_128Concat, PoolId, (T::AccountId, DepositBalanceOf<T>), OptionQuery>;
+
+	#[pallet::storage]
+	pub type PoolKinds<T: Config> = StorageMap<_, Blake2_128Concat, PoolId, PoolKind, OptionQuery>;
+
+	/// Constant-product pool of every token pair, keyed by the pair in ascending order.
+	#[pallet::storage]
+	pub type PairPool<T: Config> = StorageMap<_, Blake2_128Concat, (u64, u64), PoolId, OptionQuery>;
+
+	/// Pools where trading and deposits are suspended, withdrawals stay open.
+	#[pallet::storage]
+	pub type PausedPools<T: Config> = StorageMap<_, Blake2_128Concat, PoolId, (), OptionQuery>;
+
+	/// Price of the first token of a pair in a pool in the second one, before the first swap
+	/// of the pair in the block it was recorded in.
+	#[pallet::storage]
+	pub type BlockStartPrices<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		PoolId,
+		Blake2_128Concat,
+		(u64, u64),
+		(T::BlockNumber, FixedU128),
+		OptionQuery,
+	>;
+
+	/// Constant-product pools with a flash loan in progress.
+	#[pallet::storage]
+	pub type LockedPools<T: Config> = StorageMap<_, Blake2_128Concat, PoolId, (), OptionQuery>;
+
+	#[pallet::storage]
+	pub type FirstTokeId<T: Config> = StorageMap<_, Blake2_128Concat, PoolId, u64, ValueQuery>;
+
+	#[pallet::storage]
+	pub type SecondTokeId<T: Config> = StorageMap<_, Blake2_128Concat, PoolId, u64, ValueQuery>;
+
+	#[pallet::storage]
+	pub type StablePools<T: Config> = StorageMap<_, Blake2_128Concat, PoolId, StablePool<T>, OptionQuery>;
 
 	#[pallet::storage]
-	pub type FirstTokeId<T: Config> = StorageValue<_, u64, ValueQuery>;
+	pub type WeightedPools<T: Config> = StorageMap<_, Blake2_128Concat, PoolId, WeightedPool<T>, OptionQuery>;
 
 	#[pallet::storage]
-	pub type SecondTokeId<T: Config> = StorageValue<_, u64, ValueQuery>;
+	pub type LbpPools<T: Config> = StorageMap<_, Blake2_128Concat, PoolId, LbpPool<T>, OptionQuery>;
 
 	#[pallet::storage]
-	pub type LPTokenTotalSupply<T: Config> = StorageValue<_, u64, ValueQuery>;
+	pub type ConcentratedPools<T: Config> = StorageMap<_, Blake2_128Concat, PoolId, ConcentratedPool, OptionQuery>;
+
+	#[pallet::storage]
+	pub type Ticks<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, PoolId, Blake2_128Concat, i32, TickInfo, OptionQuery>;
+
+	/// Initialized ticks of every concentrated liquidity pool in ascending order.
+	#[pallet::storage]
+	pub type InitializedTicks<T: Config> =
+		StorageMap<_, Blake2_128Concat, PoolId, BoundedVec<i32, T::MaxTicks>, ValueQuery>;
+
+	#[pallet::storage]
+	pub type NextPositionId<T: Config> = StorageValue<_, PositionId, ValueQuery>;
+
+	#[pallet::storage]
+	pub type Positions<T: Config> = StorageMap<_, Blake2_128Concat, PositionId, Position<T>, OptionQuery>;
+
+	#[pallet::storage]
+	pub type LPTokenTotalSupply<T: Config> = StorageMap<_, Blake2_128Concat, PoolId, u64, ValueQuery>;
 
 	#[pallet::storage]
 	#[pallet::getter(fn balance_of)]
-	pub type BalanceOf<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u64, ValueQuery>;
+	pub type BalanceOf<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, PoolId, Blake2_128Concat, T::AccountId, u64, ValueQuery>;
+
+	/// Cumulative price snapshots of each constant-product pool, oldest first.
+	#[pallet::storage]
+	pub type PriceObservations<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		PoolId,
+		BoundedVec<Observation<T::BlockNumber>, T::MaxObservations>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	pub type NextOrderId<T: Config> = StorageValue<_, OrderId, ValueQuery>;
+
+	#[pallet::storage]
+	pub type Orders<T: Config> = StorageMap<_, Blake2_128Concat, OrderId, LimitOrder<T>, OptionQuery>;
+
+	/// Open limit orders against every constant-product pool, oldest first.
+	#[pallet::storage]
+	pub type OrderBooks<T: Config> =
+		StorageMap<_, Blake2_128Concat, PoolId, BoundedVec<OrderId, T::MaxOrdersPerPool>, ValueQuery>;
+
+	/// Pool whose order book was walked last; filling resumes after it in the next block.
+	#[pallet::storage]
+	pub type LimitOrderCursor<T: Config> = StorageValue<_, PoolId, OptionQuery>;
+
+	/// Constant-product pools that only trade through batch auctions.
+	#[pallet::storage]
+	pub type BatchAuctionPools<T: Config> = StorageMap<_, Blake2_128Concat, PoolId, (), OptionQuery>;
+
+	/// Swap intents against each batch-auction pool waiting to be cleared.
+	#[pallet::storage]
+	pub type SwapIntents<T: Config> =
+		StorageMap<_, Blake2_128Concat, PoolId, BoundedVec<SwapIntent<T>, T::MaxIntentsPerBatch>, ValueQuery>;
+
+	/// Batch-auction pool cleared last; clearing resumes after it in the next block.
+	#[pallet::storage]
+	pub type AuctionCursor<T: Config> = StorageValue<_, PoolId, OptionQuery>;
+
+	/// Unrevealed swap commitments with the block they were made in and the deposit reserved.
+	#[pallet::storage]
+	pub type Commitments<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Identity,
+		T::Hash,
+		(T::BlockNumber, DepositBalanceOf<T>),
+		OptionQuery,
+	>;
+
+	/// Commitments to forfeit at the start of a block unless revealed by then.
+	#[pallet::storage]
+	pub type CommitmentExpiries<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		BoundedVec<(T::AccountId, T::Hash), T::MaxCommitmentsPerBlock>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	pub type NextFarmId<T: Config> = StorageValue<_, FarmId, ValueQuery>;
+
+	#[pallet::storage]
+	pub type Farms<T: Config> = StorageMap<_, Blake2_128Concat, FarmId, Farm<T>, OptionQuery>;
+
+	/// LP shares every account has staked in a farm.
+	#[pallet::storage]
+	pub type Stakes<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, FarmId, Blake2_128Concat, T::AccountId, Stake, ValueQuery>;
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			Self::forfeit_expired_commitments(n).saturating_add(Self::clear_batch_auctions())
+		}
+
+		fn on_idle(_n: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			Self::fill_limit_orders(remaining_weight)
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			crate::migrations::migrate_to_v1::<T>()
+		}
+
+		fn integrity_test() {
+			// Swap outputs keep `100 - Fee` percent and quotes divide by it.
+			assert!(T::Fee::get() < 100, "`Fee` must be below 100 percent");
+		}
+	}
 
 	#[pallet:: call]
 	impl<T: Config> Pallet<T> {
 		#[pallet::weight(1000)]
 		pub fn init(
 			origin: OriginFor<T>,
-			pool_address: T::AccountId,
 			first_token_id: u64,
 			first_token_amount: u64,
 			second_token_id: u64,
 			second_token_amount: u64
 		) -> DispatchResult {
 			let sender = &ensure_signed(origin)?;
+			ensure!(first_token_id != second_token_id, Error::<T>::DuplicateToken);
+			let pair = Self::pair_key(first_token_id, second_token_id);
+			ensure!(!PairPool::<T>::contains_key(pair), Error::<T>::PoolExists);
+			let liquidity = (first_token_amount as u128 * second_token_amount as u128).integer_sqrt() as u64;
+			ensure!(liquidity > MINIMUM_LIQUIDITY, Error::<T>::InsufficientLiquidity);
 
+			let (pool_id, pool_address) = Self::register_pool(sender, PoolKind::ConstantProduct)?;
 			T::MultiToken::transfer_to(sender, &pool_address, &first_token_id, &first_token_amount)?;
 			T::MultiToken::transfer_to(sender, &pool_address, &second_token_id, &second_token_amount)?;
-			BalanceOf::<T>::set(sender, first_token_amount);
-			LPTokenTotalSupply::<T>::set(first_token_amount);
-			Pool::<T>::set(Some(pool_address));
-			FirstTokeId::<T>::set(first_token_id);
-			SecondTokeId::<T>::set(second_token_id);
+			BalanceOf::<T>::insert(pool_id, sender, liquidity - MINIMUM_LIQUIDITY);
+			LPTokenTotalSupply::<T>::insert(pool_id, liquidity);
+			PairPool::<T>::insert(pair, pool_id);
+			FirstTokeId::<T>::insert(pool_id, first_token_id);
+			SecondTokeId::<T>::insert(pool_id, second_token_id);
+			Self::reset_price_observations(pool_id, &pool_address);
 
 			Ok(())
 		}
-		
+
 		#[pallet::weight(1000)]
 		pub fn swap_token(
 			origin: OriginFor<T>,
+			pool_id: PoolId,
 			_token_id: u64,
 			_amount: u64
 		) -> DispatchResult {
 			let sender = &ensure_signed(origin)?;
-			let pool_address: T::AccountId = Pool::<T>::get().expect("Pool is not initialized");
-
-			if FirstTokeId::<T>::get() == _token_id {
-					let mut received_value = (T::MultiToken::balances(&SecondTokeId::<T>::get(), &pool_address) * &_amount)
-					/ (T::MultiToken::balances(&FirstTokeId::<T>::get(), &pool_address) + &_amount);
-					received_value = received_value * (100 - T::Fee::get()) / 100;
-					T::MultiToken::transfer_to(sender, &pool_address, &_token_id, &_amount)?;
-					T::MultiToken::transfer_to(&pool_address, sender, &SecondTokeId::<T>::get(), &received_value)?;
-				} else {
-					let mut received_value = (T::MultiToken::balances(&FirstTokeId::<T>::get(), &pool_address) * &_amount)
-					/ (T::MultiToken::balances(&SecondTokeId::<T>::get(), &pool_address) + &_amount);
-					received_value = received_value * (100 - T::Fee::get()) / 100;
-					T::MultiToken::transfer_to(sender, &pool_address, &_token_id, &_amount)?;
-					T::MultiToken::transfer_to(&pool_address, sender, &FirstTokeId::<T>::get(), &received_value)?;
-				}
-			
+			Self::do_swap(sender, sender, pool_id, _token_id, _amount)?;
+			Ok(())
+		}
+
+		/// Sells `amount` of `token_id` like `swap_token`, delivering the output to `to`.
+		#[pallet::weight(1000)]
+		pub fn swap_to(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			token_id: u64,
+			amount: u64,
+			to: T::AccountId
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_swap(sender, &to, pool_id, token_id, amount)?;
 			Ok(())
 		}
 
 		#[pallet::weight(1000)]
 		pub fn deposit(
 			origin: OriginFor<T>,
+			pool_id: PoolId,
 			_first_token_amount: u64,
 			_second_token_amount: u64
 		) -> DispatchResult {
 			let sender = &ensure_signed(origin)?;
-			let pool_address: &T::AccountId = &Pool::<T>::get().expect("Pool is not initialized");
-			let first_token_balance = &T::MultiToken::balances(&FirstTokeId::<T>::get(), &pool_address);
-			let second_token_balance = &T::MultiToken::balances(&SecondTokeId::<T>::get(), &pool_address);
-			let liquidity = _first_token_amount * LPTokenTotalSupply::<T>::get() / first_token_balance;
-			let required_second_token_amount = second_token_balance * _first_token_amount / first_token_balance;
-			ensure!(_second_token_amount >= required_second_token_amount, Error::<T>::WrongRatio);
-			T::MultiToken::transfer_to(sender, pool_address, &FirstTokeId::<T>::get(), &_first_token_amount)?;
-			T::MultiToken::transfer_to(sender, pool_address, &SecondTokeId::<T>::get(), &required_second_token_amount)?;
-			LPTokenTotalSupply::<T>::set(LPTokenTotalSupply::<T>::get() + &liquidity);
-			BalanceOf::<T>::mutate(sender, |x| *x += liquidity);
+			Self::do_add_liquidity(sender, sender, pool_id, _first_token_amount, _second_token_amount, _first_token_amount, 0)?;
+			Ok(())
+		}
+
+		/// Deposits both tokens in the pool ratio, taking no more than the desired amounts and
+		/// no less than the minimum ones. The LP minted to `to` is reported by `LiquidityAdded`.
+		#[pallet::weight(1000)]
+		pub fn add_liquidity(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			first_token_amount_desired: u64,
+			second_token_amount_desired: u64,
+			first_token_amount_min: u64,
+			second_token_amount_min: u64,
+			to: T::AccountId
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_add_liquidity(
+				sender,
+				&to,
+				pool_id,
+				first_token_amount_desired,
+				second_token_amount_desired,
+				first_token_amount_min,
+				second_token_amount_min,
+			)?;
 			Ok(())
 		}
 
 		#[pallet::weight(1000)]
 		pub fn withdraw(
-			origin: OriginFor<T>
+			origin: OriginFor<T>,
+			pool_id: PoolId
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			let lp_balance = BalanceOf::<T>::get(pool_id, sender);
+			Self::do_remove_liquidity(sender, sender, pool_id, lp_balance, 0, 0)?;
+			Ok(())
+		}
+
+		/// Burns `lp_amount` LP shares for a proportional part of both reserves, paid to `to`,
+		/// failing if that is less than `min_first_token_amount` or `min_second_token_amount`.
+		#[pallet::weight(1000)]
+		pub fn remove_liquidity(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			lp_amount: u64,
+			min_first_token_amount: u64,
+			min_second_token_amount: u64,
+			to: T::AccountId
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_remove_liquidity(sender, &to, pool_id, lp_amount, min_first_token_amount, min_second_token_amount)?;
+			Ok(())
+		}
+
+		/// Creates a StableSwap pool over `assets`, seeded with `amounts` of each of them.
+		#[pallet::weight(1000)]
+		pub fn create_stable_pool(
+			origin: OriginFor<T>,
+			assets: Vec<u64>,
+			amounts: Vec<u64>,
+			amplification: u64
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_create_stable_pool(sender, assets, amounts, amplification)
+		}
+
+		/// Adds any combination of the pool assets, minting at least `min_liquidity` LP shares.
+		#[pallet::weight(1000)]
+		pub fn add_stable_liquidity(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			amounts: Vec<u64>,
+			min_liquidity: u64
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_add_stable_liquidity(sender, pool_id, amounts, min_liquidity)
+		}
+
+		/// Burns `liquidity` LP shares for a proportional part of every pool asset.
+		#[pallet::weight(1000)]
+		pub fn remove_stable_liquidity(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			liquidity: u64,
+			min_amounts: Vec<u64>
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_remove_stable_liquidity(sender, pool_id, liquidity, min_amounts)
+		}
+
+		#[pallet::weight(1000)]
+		pub fn stable_swap(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			token_in: u64,
+			token_out: u64,
+			amount_in: u64,
+			min_amount_out: u64
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_stable_swap(sender, pool_id, token_in, token_out, amount_in, min_amount_out)?;
+			Ok(())
+		}
+
+		/// Creates a weighted pool over `assets` with denormalized `weights`, seeded with
+		/// `amounts` of each asset.
+		#[pallet::weight(1000)]
+		pub fn create_weighted_pool(
+			origin: OriginFor<T>,
+			assets: Vec<u64>,
+			weights: Vec<u32>,
+			amounts: Vec<u64>,
+			swap_fee: Permill
 		) -> DispatchResult {
 			let sender = &ensure_signed(origin)?;
-			let pool_address: &T::AccountId = &Pool::<T>::get().expect("Pool is not initialized");
-			let lp_balance = BalanceOf::<T>::get(sender);
-			ensure!(lp_balance >= 0, Error::<T>::NoLiquidity);
-			let first_token_amount = T::MultiToken::balances(&FirstTokeId::<T>::get(), pool_address) * lp_balance / LPTokenTotalSupply::<T>::get();
-			let second_token_amount = T::MultiToken::balances(&SecondTokeId::<T>::get(), pool_address) * lp_balance / LPTokenTotalSupply::<T>::get();
+			Self::do_create_weighted_pool(sender, assets, weights, amounts, swap_fee)
+		}
 
-			LPTokenTotalSupply::<T>::set(LPTokenTotalSupply::<T>::get() - lp_balance);
-			BalanceOf::<T>::set(sender, 0);
+		/// Mints `liquidity` LP shares for a proportional deposit of every pool asset.
+		#[pallet::weight(1000)]
+		pub fn join_weighted_pool(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			liquidity: u64,
+			max_amounts: Vec<u64>
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_join_weighted_pool(sender, pool_id, liquidity, max_amounts)
+		}
+
+		/// Burns `liquidity` LP shares for a proportional part of every pool asset.
+		#[pallet::weight(1000)]
+		pub fn exit_weighted_pool(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			liquidity: u64,
+			min_amounts: Vec<u64>
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_exit_weighted_pool(sender, pool_id, liquidity, min_amounts)
+		}
 
-			T::MultiToken::transfer_to(pool_address, sender, &FirstTokeId::<T>::get(), &first_token_amount)?;
-			T::MultiToken::transfer_to(pool_address, sender, &SecondTokeId::<T>::get(), &second_token_amount)?;
+		/// Deposits a single asset, minting at least `min_liquidity` LP shares.
+		#[pallet::weight(1000)]
+		pub fn join_weighted_pool_single(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			token_in: u64,
+			amount_in: u64,
+			min_liquidity: u64
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_join_weighted_pool_single(sender, pool_id, token_in, amount_in, min_liquidity)?;
+			Ok(())
+		}
+
+		/// Burns `liquidity` LP shares for at least `min_amount_out` of a single asset.
+		#[pallet::weight(1000)]
+		pub fn exit_weighted_pool_single(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			token_out: u64,
+			liquidity: u64,
+			min_amount_out: u64
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_exit_weighted_pool_single(sender, pool_id, token_out, liquidity, min_amount_out)?;
+			Ok(())
+		}
 
+		#[pallet::weight(1000)]
+		pub fn weighted_swap(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			token_in: u64,
+			token_out: u64,
+			amount_in: u64,
+			min_amount_out: u64
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_weighted_swap(sender, pool_id, token_in, token_out, amount_in, min_amount_out)?;
 			Ok(())
 		}
+
+		/// Creates a liquidity bootstrapping pool of two assets seeded with `amounts`, trading
+		/// from `start` until `end` while its weights move from `start_weights` to `end_weights`.
+		#[pallet::weight(1000)]
+		#[allow(clippy::too_many_arguments)]
+		pub fn create_lbp(
+			origin: OriginFor<T>,
+			assets: [u64; 2],
+			amounts: [u64; 2],
+			start_weights: [u32; 2],
+			end_weights: [u32; 2],
+			start: T::BlockNumber,
+			end: T::BlockNumber,
+			swap_fee: Permill
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_create_lbp(sender, assets, amounts, start_weights, end_weights, start, end, swap_fee)
+		}
+
+		#[pallet::weight(1000)]
+		pub fn add_lbp_liquidity(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			amounts: [u64; 2]
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_add_lbp_liquidity(sender, pool_id, amounts)
+		}
+
+		/// Withdraws `amounts` from an LBP before or after its sale.
+		#[pallet::weight(1000)]
+		pub fn remove_lbp_liquidity(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			amounts: [u64; 2]
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_remove_lbp_liquidity(sender, pool_id, amounts)
+		}
+
+		#[pallet::weight(1000)]
+		pub fn lbp_swap(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			token_in: u64,
+			amount_in: u64,
+			min_amount_out: u64
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_lbp_swap(sender, pool_id, token_in, amount_in, min_amount_out)?;
+			Ok(())
+		}
+
+		/// Suspends swaps, flash loans and deposits in a pool.
+		#[pallet::weight(1000)]
+		pub fn pause_pool(
+			origin: OriginFor<T>,
+			pool_id: PoolId
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(PoolKinds::<T>::contains_key(pool_id), Error::<T>::NoPool);
+			PausedPools::<T>::insert(pool_id, ());
+			Self::deposit_event(Event::<T>::PoolPaused { pool_id });
+			Ok(())
+		}
+
+		#[pallet::weight(1000)]
+		pub fn resume_pool(
+			origin: OriginFor<T>,
+			pool_id: PoolId
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(PausedPools::<T>::contains_key(pool_id), Error::<T>::NoPool);
+			PausedPools::<T>::remove(pool_id);
+			Self::deposit_event(Event::<T>::PoolResumed { pool_id });
+			Ok(())
+		}
+
+		/// Removes a pool nobody has liquidity in and returns the creation deposit. Only the
+		/// pool creator may call it and receives the reserves left behind. Open limit orders
+		/// and swap intents against the pool are refunded.
+		#[pallet::weight(1000)]
+		pub fn destroy_pool(
+			origin: OriginFor<T>,
+			pool_id: PoolId
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_destroy_pool(sender, pool_id)
+		}
+
+		/// Deposits `amount` of a single pool token by first swapping the part of it that leaves
+		/// both sides in the pool ratio, minting at least `min_liquidity` LP shares.
+		#[pallet::weight(1000)]
+		pub fn zap_in(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			token_id: u64,
+			amount: u64,
+			min_liquidity: u64
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_zap_in(sender, pool_id, token_id, amount, min_liquidity)?;
+			Ok(())
+		}
+
+		/// Lends reserves of a constant-product pool to the sender for the duration of the
+		/// `FlashBorrower` callback, which receives `data` and must pay the pool back with fees.
+		#[pallet::weight(1000)]
+		pub fn flash_loan(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			first_amount: u64,
+			second_amount: u64,
+			data: Vec<u8>
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_flash_loan(sender, pool_id, first_amount, second_amount, &data)
+		}
+
+		/// Creates an empty concentrated liquidity pool priced at `initial_tick`. Positions may
+		/// only be bounded by multiples of `tick_spacing`.
+		#[pallet::weight(1000)]
+		pub fn create_concentrated_pool(
+			origin: OriginFor<T>,
+			first_token_id: u64,
+			second_token_id: u64,
+			fee: Permill,
+			tick_spacing: u32,
+			initial_tick: i32
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_create_concentrated_pool(sender, first_token_id, second_token_id, fee, tick_spacing, initial_tick)
+		}
+
+		/// Opens a position of `liquidity` between two ticks, paying at most the given amounts.
+		#[pallet::weight(1000)]
+		pub fn mint_position(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			tick_lower: i32,
+			tick_upper: i32,
+			liquidity: u128,
+			max_first_amount: u64,
+			max_second_amount: u64
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_mint_position(sender, pool_id, tick_lower, tick_upper, liquidity, max_first_amount, max_second_amount)?;
+			Ok(())
+		}
+
+		/// Removes `liquidity` from a position, the released tokens become collectable.
+		#[pallet::weight(1000)]
+		pub fn burn_position(
+			origin: OriginFor<T>,
+			position_id: PositionId,
+			liquidity: u128,
+			min_first_amount: u64,
+			min_second_amount: u64
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_burn_position(sender, position_id, liquidity, min_first_amount, min_second_amount)
+		}
+
+		/// Pays out the earned fees and burned liquidity of a position.
+		#[pallet::weight(1000)]
+		pub fn collect(
+			origin: OriginFor<T>,
+			position_id: PositionId
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_collect(sender, position_id)?;
+			Ok(())
+		}
+
+		/// Charged for crossing up to `MaxTicks` ticks, refunded down to the ticks crossed.
+		#[pallet::weight(Pallet::<T>::concentrated_swap_weight(T::MaxTicks::get()))]
+		pub fn concentrated_swap(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			token_in: u64,
+			amount_in: u64,
+			min_amount_out: u64
+		) -> DispatchResultWithPostInfo {
+			let sender = &ensure_signed(origin)?;
+			let (_, ticks_crossed) = Self::do_concentrated_swap(sender, pool_id, token_in, amount_in, min_amount_out)?;
+			Ok(Some(Self::concentrated_swap_weight(ticks_crossed)).into())
+		}
+
+		/// Escrows `amount_in` to be swapped for at least `min_amount_out` once the pool pays
+		/// that much, before block `expires_at`.
+		#[pallet::weight(1000)]
+		pub fn place_limit_order(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			token_in: u64,
+			amount_in: u64,
+			min_amount_out: u64,
+			expires_at: T::BlockNumber
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_place_limit_order(sender, pool_id, token_in, amount_in, min_amount_out, expires_at)?;
+			Ok(())
+		}
+
+		/// Switches a constant-product pool between immediate swaps and batch auctions.
+		#[pallet::weight(1000)]
+		pub fn set_batch_auction(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			enabled: bool
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Self::constant_product_pool(pool_id)?;
+			if enabled {
+				BatchAuctionPools::<T>::insert(pool_id, ());
+			} else {
+				BatchAuctionPools::<T>::remove(pool_id);
+			}
+			Self::deposit_event(Event::<T>::BatchAuctionSet { pool_id, enabled });
+			Ok(())
+		}
+
+		/// Escrows `amount_in` to be swapped at the clearing price of the batch of this block,
+		/// or refunded if that pays less than `min_amount_out`.
+		#[pallet::weight(1000)]
+		pub fn submit_swap_intent(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			token_in: u64,
+			amount_in: u64,
+			min_amount_out: u64
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_submit_swap_intent(sender, pool_id, token_in, amount_in, min_amount_out)
+		}
+
+		/// Commits to a swap hashed with `swap_commitment`, to be revealed in a later block.
+		#[pallet::weight(1000)]
+		pub fn commit_swap(
+			origin: OriginFor<T>,
+			commitment: T::Hash
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_commit_swap(sender, commitment)
+		}
+
+		#[pallet::weight(1000)]
+		pub fn reveal_swap(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			token_id: u64,
+			amount: u64,
+			min_amount_out: u64,
+			salt: [u8; 32]
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_reveal_swap(sender, pool_id, token_id, amount, min_amount_out, salt)
+		}
+
+		#[pallet::weight(1000)]
+		pub fn cancel_limit_order(
+			origin: OriginFor<T>,
+			order_id: OrderId
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_cancel_limit_order(sender, order_id)
+		}
+
+		/// Pays `reward_per_block` of `reward_token` to the LP shares of `pool_id` staked from
+		/// `start` until `end`, funding the whole schedule up front.
+		#[pallet::weight(1000)]
+		pub fn create_farm(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			reward_token: u64,
+			reward_per_block: u64,
+			start: T::BlockNumber,
+			end: T::BlockNumber
+		) -> DispatchResult {
+			let funder = &T::FarmOrigin::ensure_origin(origin)?;
+			Self::do_create_farm(funder, pool_id, reward_token, reward_per_block, start, end)
+		}
+
+		/// Stakes `amount` LP shares in `farm_id`, paying out the rewards accrued so far.
+		#[pallet::weight(1000)]
+		pub fn stake(
+			origin: OriginFor<T>,
+			farm_id: FarmId,
+			amount: u64
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_stake(sender, farm_id, amount)
+		}
+
+		#[pallet::weight(1000)]
+		pub fn unstake(
+			origin: OriginFor<T>,
+			farm_id: FarmId,
+			amount: u64
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_unstake(sender, farm_id, amount)
+		}
+
+		#[pallet::weight(1000)]
+		pub fn claim(
+			origin: OriginFor<T>,
+			farm_id: FarmId
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_claim(sender, farm_id)
+		}
+
+		/// Returns the rewards of blocks nothing was staked in to the funder of an ended farm.
+		#[pallet::weight(1000)]
+		pub fn reclaim_rewards(
+			origin: OriginFor<T>,
+			farm_id: FarmId
+		) -> DispatchResult {
+			let sender = &ensure_signed(origin)?;
+			Self::do_reclaim_rewards(sender, farm_id)
+		}
 	}
 
-	
+	impl<T: Config> Pallet<T> {
+		/// Account escrowing open orders, swap intents, farm rewards and staked LP shares.
+		pub fn escrow_account() -> T::AccountId {
+			T::PalletId::get().into_account_truncating()
+		}
+
+		/// Account holding the reserves of `pool_id`.
+		pub fn pool_account(pool_id: PoolId) -> T::AccountId {
+			let entropy = T::Hashing::hash_of(&(T::PalletId::get(), pool_id));
+			Decode::decode(&mut TrailingZeroInput::new(entropy.as_ref()))
+				.expect("infinite length input; no invalid inputs for type; qed")
+		}
 
-}
\ No newline at end of file
+		/// Allocates the next pool id for a pool of `kind`, reserving the creation deposit.
+		pub(crate) fn register_pool(
+			creator: &T::AccountId,
+			kind: PoolKind,
+		) -> Result<(PoolId, T::AccountId), DispatchError> {
+			let deposit = T::PoolDeposit::get();
+			T::Currency::reserve(creator, deposit)?;
+			let pool_id = NextPoolId::<T>::get();
+			let pool_address = Self::pool_account(pool_id);
+			NextPoolId::<T>::put(pool_id + 1);
+			Pool::<T>::insert(pool_id, &pool_address);
+			PoolIds::<T>::insert(&pool_address, pool_id);
+			PoolKinds::<T>::insert(pool_id, kind);
+			PoolDeposits::<T>::insert(pool_id, (creator.clone(), deposit));
+			Self::deposit_event(Event::<T>::PoolCreated { pool_id, kind, creator: creator.clone(), deposit });
+			Ok((pool_id, pool_address))
+		}
+
+		/// Removes every trace of a pool without liquidity providers. Reserves left behind,
+		/// like those of the locked `MINIMUM_LIQUIDITY`, go to the creator.
+		pub(crate) fn do_destroy_pool(who: &T::AccountId, pool_id: PoolId) -> DispatchResult {
+			let kind = PoolKinds::<T>::get(pool_id).ok_or(Error::<T>::NoPool)?;
+			let (creator, deposit) = PoolDeposits::<T>::get(pool_id).ok_or(Error::<T>::NoPool)?;
+			ensure!(*who == creator, Error::<T>::NotPoolCreator);
+			ensure!(!LockedPools::<T>::contains_key(pool_id), Error::<T>::PoolLocked);
+			let total_supply = LPTokenTotalSupply::<T>::get(pool_id);
+
+			let assets = match kind {
+				PoolKind::ConstantProduct => {
+					ensure!(total_supply <= MINIMUM_LIQUIDITY, Error::<T>::PoolNotEmpty);
+					Self::refund_limit_orders(pool_id)?;
+					Self::refund_swap_intents(pool_id)?;
+					BatchAuctionPools::<T>::remove(pool_id);
+					let first_token_id = FirstTokeId::<T>::take(pool_id);
+					let second_token_id = SecondTokeId::<T>::take(pool_id);
+					PairPool::<T>::remove(Self::pair_key(first_token_id, second_token_id));
+					PriceObservations::<T>::remove(pool_id);
+					vec![first_token_id, second_token_id]
+				},
+				PoolKind::StableSwap => {
+					ensure!(total_supply == 0, Error::<T>::PoolNotEmpty);
+					StablePools::<T>::take(pool_id).map(|pool| pool.assets.into_inner()).unwrap_or_default()
+				},
+				PoolKind::Weighted => {
+					ensure!(total_supply == 0, Error::<T>::PoolNotEmpty);
+					WeightedPools::<T>::take(pool_id).map(|pool| pool.assets.into_inner()).unwrap_or_default()
+				},
+				PoolKind::Concentrated => {
+					let pool = ConcentratedPools::<T>::get(pool_id).ok_or(Error::<T>::NoPool)?;
+					ensure!(pool.positions == 0, Error::<T>::PoolNotEmpty);
+					ConcentratedPools::<T>::remove(pool_id);
+					let _ = Ticks::<T>::clear_prefix(pool_id, u32::MAX, None);
+					InitializedTicks::<T>::remove(pool_id);
+					vec![pool.first_token_id, pool.second_token_id]
+				},
+				PoolKind::Lbp => {
+					let pool = LbpPools::<T>::get(pool_id).ok_or(Error::<T>::NoPool)?;
+					let now = frame_system::Pallet::<T>::block_number();
+					ensure!(!pool.is_trading(now), Error::<T>::SaleInProgress);
+					LbpPools::<T>::remove(pool_id);
+					pool.assets.to_vec()
+				},
+			};
+
+			let pool_address = Self::pool_account(pool_id);
+			for id in assets.iter() {
+				let dust = T::MultiToken::balances(id, &pool_address);
+				if dust > 0 {
+					T::MultiToken::transfer_to(&pool_address, &creator, id, &dust)?;
+				}
+			}
+			LPTokenTotalSupply::<T>::remove(pool_id);
+			let _ = BalanceOf::<T>::clear_prefix(pool_id, u32::MAX, None);
+			if let Some(pool_address) = Pool::<T>::take(pool_id) {
+				PoolIds::<T>::remove(pool_address);
+			}
+			PoolKinds::<T>::remove(pool_id);
+			PoolDeposits::<T>::remove(pool_id);
+			PausedPools::<T>::remove(pool_id);
+			let _ = BlockStartPrices::<T>::clear_prefix(pool_id, u32::MAX, None);
+			T::Currency::unreserve(&creator, deposit);
+			Self::deposit_event(Event::<T>::PoolDestroyed { pool_id, creator, deposit });
+			Ok(())
+		}
+
+		pub(crate) fn ensure_pool_active(pool_id: PoolId) -> DispatchResult {
+			ensure!(!PausedPools::<T>::contains_key(pool_id), Error::<T>::PoolPaused);
+			Ok(())
+		}
+
+		/// Price of `pair` in `pool_id` at the start of the block. The first call in a block
+		/// records `price`, the current one, so it must come before the reserves change.
+		fn pair_block_start_price(pool_id: PoolId, pair: (u64, u64), price: FixedU128) -> FixedU128 {
+			let now = frame_system::Pallet::<T>::block_number();
+			match BlockStartPrices::<T>::get(pool_id, pair) {
+				Some((block_number, start_price)) if block_number == now => start_price,
+				_ => {
+					BlockStartPrices::<T>::insert(pool_id, pair, (now, price));
+					price
+				},
+			}
+		}
+
+		/// Rejects moving the price of `pair` in `pool_id` from `price` to `new_price` if that
+		/// leaves it more than `MaxPriceMove` away from its price at the start of the block.
+		fn check_pair_price_move(
+			pool_id: PoolId,
+			pair: (u64, u64),
+			price: FixedU128,
+			new_price: FixedU128,
+		) -> DispatchResult {
+			let start_price = Self::pair_block_start_price(pool_id, pair, price);
+			let change = if new_price > start_price { new_price - start_price } else { start_price - new_price };
+			ensure!(
+				change.into_inner() <= T::MaxPriceMove::get().mul_floor(start_price.into_inner()),
+				Error::<T>::PriceMoveTooLarge
+			);
+			Ok(())
+		}
+
+		/// Rejects a swap of `token_in` for `token_out` in `pool_id` that moves the price of
+		/// `token_in` in `token_out` from `price` to `new_price`, if that is too far from its
+		/// price at the start of the block. Swaps in both directions count against the same
+		/// start price.
+		pub(crate) fn check_swap_price_move(
+			pool_id: PoolId,
+			token_in: u64,
+			token_out: u64,
+			price: FixedU128,
+			new_price: FixedU128,
+		) -> DispatchResult {
+			if token_in < token_out {
+				Self::check_pair_price_move(pool_id, (token_in, token_out), price, new_price)
+			} else {
+				let invert = |price: FixedU128| price.reciprocal().unwrap_or_default();
+				Self::check_pair_price_move(pool_id, (token_out, token_in), invert(price), invert(new_price))
+			}
+		}
+
+		/// Price of the constant-product pool `pool_id` at the start of the block. It is
+		/// recorded on the first call in a block, which must come before the reserves change.
+		pub(crate) fn block_start_price(pool_id: PoolId, pool_address: &T::AccountId) -> FixedU128 {
+			let (price, _) = Self::spot_prices(pool_id, pool_address);
+			Self::pair_block_start_price(pool_id, Self::constant_product_pair(pool_id), price)
+		}
+
+		/// Rejects prices of the constant-product pool `pool_id` that moved more than
+		/// `MaxPriceMove` since the start of the block.
+		pub(crate) fn check_price_move(
+			pool_id: PoolId,
+			pool_address: &T::AccountId,
+			new_price: FixedU128,
+		) -> DispatchResult {
+			let (price, _) = Self::spot_prices(pool_id, pool_address);
+			Self::check_pair_price_move(pool_id, Self::constant_product_pair(pool_id), price, new_price)
+		}
+
+		/// Tokens of the constant-product pool `pool_id`, in the order its prices are kept in.
+		fn constant_product_pair(pool_id: PoolId) -> (u64, u64) {
+			(FirstTokeId::<T>::get(pool_id), SecondTokeId::<T>::get(pool_id))
+		}
+
+		/// Account of the constant-product pool `pool_id`, unless a flash loan is in progress.
+		pub(crate) fn constant_product_pool(pool_id: PoolId) -> Result<T::AccountId, DispatchError> {
+			ensure!(PoolKinds::<T>::get(pool_id) == Some(PoolKind::ConstantProduct), Error::<T>::NoPool);
+			ensure!(!LockedPools::<T>::contains_key(pool_id), Error::<T>::PoolLocked);
+			Pool::<T>::get(pool_id).ok_or_else(|| Error::<T>::NoPool.into())
+		}
+
+		/// Sells `amount` of `token_id` to a constant-product pool and returns the amount of the
+		/// other token paid to `to`. The fee is taken from the output.
+		pub(crate) fn do_swap(
+			who: &T::AccountId,
+			to: &T::AccountId,
+			pool_id: PoolId,
+			token_id: u64,
+			amount: u64,
+		) -> Result<u64, DispatchError> {
+			let pool_address: T::AccountId = Self::constant_product_pool(pool_id)?;
+			let first_token_id = FirstTokeId::<T>::get(pool_id);
+			let second_token_id = SecondTokeId::<T>::get(pool_id);
+			ensure!(token_id == first_token_id || token_id == second_token_id, Error::<T>::UnknownToken);
+			Self::ensure_pool_active(pool_id)?;
+			ensure!(!BatchAuctionPools::<T>::contains_key(pool_id), Error::<T>::BatchAuctionOnly);
+
+			let other_token_id = if token_id == first_token_id { second_token_id } else { first_token_id };
+			let balance = T::MultiToken::balances(&token_id, &pool_address) as u128;
+			let other_balance = T::MultiToken::balances(&other_token_id, &pool_address) as u128;
+			let mut received_value = other_balance * amount as u128 / (balance + amount as u128);
+			received_value = received_value * (100 - T::Fee::get() as u128) / 100;
+			let received_value = received_value as u64;
+			let (new_balance, new_other_balance) = (balance + amount as u128, other_balance - received_value as u128);
+			let new_price = if token_id == first_token_id {
+				FixedU128::checked_from_rational(new_other_balance, new_balance)
+			} else {
+				FixedU128::checked_from_rational(new_balance, new_other_balance)
+			};
+			Self::check_price_move(pool_id, &pool_address, new_price.unwrap_or_default())?;
+			T::MultiToken::transfer_to(who, &pool_address, &token_id, &amount)?;
+			T::MultiToken::transfer_to(&pool_address, to, &other_token_id, &received_value)?;
+			Self::update_price_observations(pool_id, &pool_address);
+			Self::deposit_event(Event::<T>::Swapped {
+				who: who.clone(),
+				to: to.clone(),
+				pool_id,
+				token_in: token_id,
+				amount_in: amount,
+				token_out: other_token_id,
+				amount_out: received_value,
+			});
+			Ok(received_value)
+		}
+
+		/// Part of a single-sided deposit of `amount` to swap first, so that the swap output
+		/// and the rest of `amount` are in the ratio of the reserves after the swap.
+		///
+		/// Solves `s² + (R·(2 - f) - a·f)·s - a·R = 0` for the reserve `R` of the deposited token
+		/// and the fee `f` charged on the swap output.
+		fn zap_swap_amount(reserve: u64, amount: u64) -> Result<u64, DispatchError> {
+			let fee = U256::from(T::Fee::get());
+			let (reserve, amount) = (U256::from(reserve), U256::from(amount));
+			// Coefficients scaled by 100 to keep the fee integral, the linear one is `b⁺ - b⁻`.
+			let (b_positive, b_negative) = (reserve * (U256::from(200) - fee), amount * fee);
+			let b = if b_positive > b_negative { b_positive - b_negative } else { b_negative - b_positive };
+			let discriminant = b
+				.checked_mul(b)
+				.and_then(|b_squared| b_squared.checked_add(U256::from(40_000) * amount * reserve))
+				.ok_or(Error::<T>::Overflow)?;
+			let root = discriminant.integer_sqrt();
+			let swap_amount = if b_positive > b_negative { (root - b) / 200 } else { (root + b) / 200 };
+			swap_amount.try_into().map_err(|_| Error::<T>::Overflow.into())
+		}
+
+		pub(crate) fn do_zap_in(
+			who: &T::AccountId,
+			pool_id: PoolId,
+			token_id: u64,
+			amount: u64,
+			min_liquidity: u64,
+		) -> Result<u64, DispatchError> {
+			let pool_address = Self::constant_product_pool(pool_id)?;
+			let first_token_id = FirstTokeId::<T>::get(pool_id);
+			ensure!(token_id == first_token_id || token_id == SecondTokeId::<T>::get(pool_id), Error::<T>::UnknownToken);
+			let swap_amount = Self::zap_swap_amount(T::MultiToken::balances(&token_id, &pool_address), amount)?;
+			ensure!(swap_amount > 0, Error::<T>::ZeroAmount);
+			let received = Self::do_swap(who, who, pool_id, token_id, swap_amount)?;
+			let liquidity = if token_id == first_token_id {
+				Self::do_add_liquidity(who, who, pool_id, amount - swap_amount, received, 0, 0)?
+			} else {
+				Self::do_add_liquidity(who, who, pool_id, received, amount - swap_amount, 0, 0)?
+			};
+			ensure!(liquidity >= min_liquidity, Error::<T>::SlippageExceeded);
+			Ok(liquidity)
+		}
+
+		/// Deposits the largest amounts in the pool ratio that fit the desired ones and returns
+		/// the LP shares minted for them to `to`.
+		pub(crate) fn do_add_liquidity(
+			who: &T::AccountId,
+			to: &T::AccountId,
+			pool_id: PoolId,
+			first_token_amount_desired: u64,
+			second_token_amount_desired: u64,
+			first_token_amount_min: u64,
+			second_token_amount_min: u64,
+		) -> Result<u64, DispatchError> {
+			let pool_address = &Self::constant_product_pool(pool_id)?;
+			Self::ensure_pool_active(pool_id)?;
+			let first_token_id = FirstTokeId::<T>::get(pool_id);
+			let second_token_id = SecondTokeId::<T>::get(pool_id);
+			let first_token_balance = T::MultiToken::balances(&first_token_id, pool_address);
+			let second_token_balance = T::MultiToken::balances(&second_token_id, pool_address);
+			ensure!(first_token_balance > 0 && second_token_balance > 0, Error::<T>::NoLiquidity);
+			let quote = |amount: u64, reserve_in: u64, reserve_out: u64| {
+				(amount as u128 * reserve_out as u128 / reserve_in as u128) as u64
+			};
+
+			let second_token_amount_optimal =
+				quote(first_token_amount_desired, first_token_balance, second_token_balance);
+			let (first_token_amount, second_token_amount) = if second_token_amount_optimal <= second_token_amount_desired {
+				ensure!(second_token_amount_optimal >= second_token_amount_min, Error::<T>::WrongRatio);
+				(first_token_amount_desired, second_token_amount_optimal)
+			} else {
+				let first_token_amount_optimal =
+					quote(second_token_amount_desired, second_token_balance, first_token_balance);
+				ensure!(first_token_amount_optimal >= first_token_amount_min, Error::<T>::WrongRatio);
+				(first_token_amount_optimal, second_token_amount_desired)
+			};
+
+			let total_supply = LPTokenTotalSupply::<T>::get(pool_id);
+			let liquidity = quote(first_token_amount, first_token_balance, total_supply)
+				.min(quote(second_token_amount, second_token_balance, total_supply));
+			ensure!(liquidity > 0, Error::<T>::InsufficientLiquidity);
+			T::MultiToken::transfer_to(who, pool_address, &first_token_id, &first_token_amount)?;
+			T::MultiToken::transfer_to(who, pool_address, &second_token_id, &second_token_amount)?;
+			LPTokenTotalSupply::<T>::insert(pool_id, total_supply + liquidity);
+			BalanceOf::<T>::mutate(pool_id, to, |x| *x += liquidity);
+			Self::update_price_observations(pool_id, pool_address);
+			Self::deposit_event(Event::<T>::LiquidityAdded {
+				who: who.clone(),
+				to: to.clone(),
+				pool_id,
+				first_token_amount,
+				second_token_amount,
+				liquidity,
+			});
+			Ok(liquidity)
+		}
+
+		/// Burns `liquidity` shares of a constant-product pool for their part of the reserves,
+		/// which are paid to `to`.
+		pub(crate) fn do_remove_liquidity(
+			who: &T::AccountId,
+			to: &T::AccountId,
+			pool_id: PoolId,
+			liquidity: u64,
+			min_first_token_amount: u64,
+			min_second_token_amount: u64,
+		) -> Result<(u64, u64), DispatchError> {
+			let pool_address = &Self::constant_product_pool(pool_id)?;
+			ensure!(liquidity > 0, Error::<T>::NoLiquidity);
+			let lp_balance = BalanceOf::<T>::get(pool_id, who);
+			ensure!(liquidity <= lp_balance, Error::<T>::NoLiquidity);
+			let first_token_id = FirstTokeId::<T>::get(pool_id);
+			let second_token_id = SecondTokeId::<T>::get(pool_id);
+			let total_supply = LPTokenTotalSupply::<T>::get(pool_id);
+			let share_of = |balance: u64| (balance as u128 * liquidity as u128 / total_supply as u128) as u64;
+			let first_token_amount = share_of(T::MultiToken::balances(&first_token_id, pool_address));
+			let second_token_amount = share_of(T::MultiToken::balances(&second_token_id, pool_address));
+			ensure!(
+				first_token_amount >= min_first_token_amount && second_token_amount >= min_second_token_amount,
+				Error::<T>::SlippageExceeded
+			);
+
+			LPTokenTotalSupply::<T>::insert(pool_id, total_supply - liquidity);
+			BalanceOf::<T>::insert(pool_id, who, lp_balance - liquidity);
+
+			T::MultiToken::transfer_to(pool_address, to, &first_token_id, &first_token_amount)?;
+			T::MultiToken::transfer_to(pool_address, to, &second_token_id, &second_token_amount)?;
+			Self::update_price_observations(pool_id, pool_address);
+			Self::deposit_event(Event::<T>::LiquidityRemoved {
+				who: who.clone(),
+				to: to.clone(),
+				pool_id,
+				liquidity,
+				first_token_amount,
+				second_token_amount,
+			});
+			Ok((first_token_amount, second_token_amount))
+		}
+
+		pub(crate) fn pair_key(first_token_id: u64, second_token_id: u64) -> (u64, u64) {
+			if first_token_id < second_token_id {
+				(first_token_id, second_token_id)
+			} else {
+				(second_token_id, first_token_id)
+			}
+		}
+
+		/// Spot prices of the first token in the second one and vice versa.
+		pub(crate) fn spot_prices(pool_id: PoolId, pool_address: &T::AccountId) -> (FixedU128, FixedU128) {
+			let (first_token_balance, second_token_balance) = Self::pool_reserves(pool_id, pool_address);
+			(
+				FixedU128::checked_from_rational(second_token_balance, first_token_balance).unwrap_or_default(),
+				FixedU128::checked_from_rational(first_token_balance, second_token_balance).unwrap_or_default(),
+			)
+		}
+
+		/// Current balances of the two tokens of a constant-product pool.
+		fn pool_reserves(pool_id: PoolId, pool_address: &T::AccountId) -> (u64, u64) {
+			(
+				T::MultiToken::balances(&FirstTokeId::<T>::get(pool_id), pool_address),
+				T::MultiToken::balances(&SecondTokeId::<T>::get(pool_id), pool_address),
+			)
+		}
+
+		/// Starts a fresh price history at the current block and reserves.
+		pub(crate) fn reset_price_observations(pool_id: PoolId, pool_address: &T::AccountId) {
+			let (first_reserve, second_reserve) = Self::pool_reserves(pool_id, pool_address);
+			let mut observations = BoundedVec::default();
+			let _ = observations.try_push(Observation {
+				block_number: frame_system::Pallet::<T>::block_number(),
+				first_price_cumulative: 0,
+				second_price_cumulative: 0,
+				first_reserve,
+				second_reserve,
+			});
+			PriceObservations::<T>::insert(pool_id, observations);
+		}
+
+		/// Accumulates the prices of the reserves recorded last over the blocks since, then
+		/// records the current reserves. Must be called after every change of the reserves; only
+		/// the first call in a block adds an observation.
+		pub(crate) fn update_price_observations(pool_id: PoolId, pool_address: &T::AccountId) {
+			let now = frame_system::Pallet::<T>::block_number();
+			let (first_reserve, second_reserve) = Self::pool_reserves(pool_id, pool_address);
+			PriceObservations::<T>::mutate(pool_id, |observations| {
+				let last = match observations.last() {
+					Some(last) => *last,
+					None => return,
+				};
+				if last.block_number < now {
+					let elapsed: u128 = now.saturating_sub(last.block_number).unique_saturated_into();
+					let (first_price, second_price) = last.prices();
+					if observations.len() as u32 >= T::MaxObservations::get() {
+						observations.remove(0);
+					}
+					let _ = observations.try_push(Observation {
+						block_number: now,
+						first_price_cumulative: last
+							.first_price_cumulative
+							.wrapping_add(first_price.into_inner().wrapping_mul(elapsed)),
+						second_price_cumulative: last
+							.second_price_cumulative
+							.wrapping_add(second_price.into_inner().wrapping_mul(elapsed)),
+						first_reserve,
+						second_reserve,
+					});
+				} else if let Some(latest) = observations.last_mut() {
+					latest.first_reserve = first_reserve;
+					latest.second_reserve = second_reserve;
+				}
+			});
+		}
+
+		/// Time-weighted average prices of the first and the second token of `pool_id` over the
+		/// last `window` blocks, measured against the newest observation at least `window` blocks old.
+		pub fn time_weighted_prices(pool_id: PoolId, window: T::BlockNumber) -> Option<(FixedU128, FixedU128)> {
+			Self::constant_product_pool(pool_id).ok()?;
+			let observations = PriceObservations::<T>::get(pool_id);
+			let latest = observations.last()?;
+			let now = frame_system::Pallet::<T>::block_number();
+			let (first_price, second_price) = latest.prices();
+			let since_latest: u128 = now.saturating_sub(latest.block_number).unique_saturated_into();
+			let first_price_cumulative = latest
+				.first_price_cumulative
+				.wrapping_add(first_price.into_inner().wrapping_mul(since_latest));
+			let second_price_cumulative = latest
+				.second_price_cumulative
+				.wrapping_add(second_price.into_inner().wrapping_mul(since_latest));
+
+			let target = now.saturating_sub(window);
+			let past = observations.iter().rev().find(|o| o.block_number <= target)?;
+			let elapsed: u128 = now.saturating_sub(past.block_number).unique_saturated_into();
+			if elapsed == 0 {
+				return None
+			}
+			Some((
+				FixedU128::from_inner(first_price_cumulative.wrapping_sub(past.first_price_cumulative) / elapsed),
+				FixedU128::from_inner(second_price_cumulative.wrapping_sub(past.second_price_cumulative) / elapsed),
+			))
+		}
+	}
+
+	impl<T: Config> PriceOracle<T::BlockNumber> for Pallet<T> {
+		fn twap(base_token_id: u64, quote_token_id: u64, window: T::BlockNumber) -> Option<FixedU128> {
+			let pool_id = PairPool::<T>::get(Self::pair_key(base_token_id, quote_token_id))?;
+			let (first_price, second_price) = Self::time_weighted_prices(pool_id, window)?;
+			if base_token_id == FirstTokeId::<T>::get(pool_id) {
+				Some(first_price)
+			} else {
+				Some(second_price)
+			}
+		}
+	}
+}
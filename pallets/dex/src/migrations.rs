@@ -0,0 +1,66 @@
+use crate::{
+	BalanceOf, Config, FirstTokeId, LPTokenTotalSupply, NextPoolId, PairPool, Pallet, Pool, PoolIds, PoolKind,
+	PoolKinds, SecondTokeId, STORAGE_VERSION,
+};
+use frame_support::{
+	traits::{Get, GetStorageVersion},
+	weights::Weight,
+};
+use sp_std::vec::Vec;
+
+/// Storage layout of the single pool the pallet held before pools had ids.
+pub(crate) mod v0 {
+	use crate::{Config, Pallet};
+	use frame_support::{storage_alias, Blake2_128Concat, ValueQuery};
+
+	#[storage_alias]
+	pub(crate) type Pool<T: Config> = StorageValue<Pallet<T>, <T as frame_system::Config>::AccountId>;
+
+	#[storage_alias]
+	pub(crate) type FirstTokeId<T: Config> = StorageValue<Pallet<T>, u64, ValueQuery>;
+
+	#[storage_alias]
+	pub(crate) type SecondTokeId<T: Config> = StorageValue<Pallet<T>, u64, ValueQuery>;
+
+	#[storage_alias]
+	pub(crate) type LPTokenTotalSupply<T: Config> = StorageValue<Pallet<T>, u64, ValueQuery>;
+
+	#[storage_alias]
+	pub(crate) type BalanceOf<T: Config> =
+		StorageMap<Pallet<T>, Blake2_128Concat, <T as frame_system::Config>::AccountId, u64, ValueQuery>;
+}
+
+/// Moves the pool of version 0 to pool id 0 and maps its account back to it in `PoolIds`.
+/// The old values sit on the bare prefixes of the new maps, so they are taken out before
+/// anything is written under those prefixes.
+pub fn migrate_to_v1<T: Config>() -> Weight {
+	if Pallet::<T>::on_chain_storage_version() >= STORAGE_VERSION {
+		return 0
+	}
+
+	let pool_address = v0::Pool::<T>::take();
+	let first_token_id = v0::FirstTokeId::<T>::take();
+	let second_token_id = v0::SecondTokeId::<T>::take();
+	let total_supply = v0::LPTokenTotalSupply::<T>::take();
+	let balances: Vec<_> = v0::BalanceOf::<T>::drain().collect();
+	let accounts = balances.len() as u64;
+
+	let pool_id = 0;
+	if let Some(pool_address) = pool_address {
+		Pool::<T>::insert(pool_id, &pool_address);
+		PoolIds::<T>::insert(&pool_address, pool_id);
+		PoolKinds::<T>::insert(pool_id, PoolKind::ConstantProduct);
+		PairPool::<T>::insert(Pallet::<T>::pair_key(first_token_id, second_token_id), pool_id);
+		FirstTokeId::<T>::insert(pool_id, first_token_id);
+		SecondTokeId::<T>::insert(pool_id, second_token_id);
+		LPTokenTotalSupply::<T>::insert(pool_id, total_supply);
+		for (who, balance) in balances {
+			BalanceOf::<T>::insert(pool_id, who, balance);
+		}
+		Pallet::<T>::reset_price_observations(pool_id, &pool_address);
+		NextPoolId::<T>::put(pool_id + 1);
+	}
+
+	STORAGE_VERSION.put::<Pallet<T>>();
+	T::DbWeight::get().reads_writes(accounts + 5, 2 * accounts + 15)
+}
@@ -1,10 +1,14 @@
 use crate as pallet_dex;
-use frame_support::traits::{ConstU16, ConstU64};
+use crate::flash::FlashBorrower;
+use codec::Decode;
+use frame_benchmarking::{account, whitelisted_caller};
+use frame_support::{pallet_prelude::DispatchResult, traits::{ConstU16, ConstU64}, PalletId};
 use frame_system as system;
 use sp_core::H256;
 use sp_runtime::{
 	testing::Header,
 	traits::{BlakeTwo256, IdentityLookup},
+	DispatchError, Permill,
 };
 use pallet_multi_token;
 
@@ -14,6 +18,27 @@ type Block = frame_system::mocking::MockBlock<Test>;
 
 frame_support::parameter_types! {
 	pub const Fee: u64 = 3;
+	pub const MaxObservations: u32 = 16;
+	pub const DexPalletId: PalletId = PalletId(*b"py/dexpl");
+	pub const MaxPoolAssets: u32 = 8;
+	pub const StableSwapFee: Permill = Permill::from_parts(400);
+	pub const MaxTicks: u32 = 64;
+	pub const PoolDeposit: u64 = 10;
+	pub static MaxPriceMove: Permill = Permill::from_percent(100);
+	pub const MaxOrdersPerPool: u32 = 4;
+	pub const MinLimitOrderAmount: u64 = 1_000;
+	pub const MaxOrderLifetime: u64 = 100;
+	pub const MaxIntentsPerBatch: u32 = 8;
+	pub const MaxAuctionsPerBlock: u32 = 1;
+	pub const CommitmentDeposit: u64 = 5;
+	pub const RevealPeriod: u64 = 3;
+	pub const MaxCommitmentsPerBlock: u32 = 8;
+	pub const NativeTokenId: u64 = u64::MAX;
+	pub const MultiTokenPalletId: PalletId = PalletId(*b"py/mtokn");
+}
+
+frame_support::ord_parameter_types! {
+	pub const FarmAdmin: u64 = 7;
 }
 
 
@@ -25,6 +50,7 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system,
+		Balances: pallet_balances,
         MultiToken: pallet_multi_token,
 		Dex: pallet_dex,
 	}
@@ -48,7 +74,7 @@ impl system::Config for Test {
 	type BlockHashCount = ConstU64<250>;
 	type Version = ();
 	type PalletInfo = PalletInfo;
-	type AccountData = ();
+	type AccountData = pallet_balances::AccountData<u64>;
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 	type SystemWeightInfo = ();
@@ -62,9 +88,30 @@ impl system::Config for Test {
 // 	type WeightInfo = pallet_multi_token::weights::SubstrateWeight<Runtime>;
 // }
 
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU64<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
 impl pallet_multi_token::Config for Test {
 	type Event = Event;
 	type WeightInfo = pallet_multi_token::weights::SubstrateWeight<Test>;
+	type Currency = Balances;
+	type NativeTokenId = NativeTokenId;
+	type PalletId = MultiTokenPalletId;
+	type FirstIssuedTokenId = ConstU64<100>;
+	type AssetDeposit = ConstU64<10>;
+	type OnTransfer = pallet_multi_token::AllowList<Test>;
+	type ExemptAccounts = crate::PalletAccounts<Test>;
+	type OnReceived = ();
+	type StringLimit = frame_support::traits::ConstU32<64>;
 }
 
 
@@ -72,9 +119,63 @@ impl pallet_dex::Config for Test {
 	type Event = Event;
     type Fee = Fee;
     type MultiToken = MultiToken;
+	type MaxObservations = MaxObservations;
+	type PalletId = DexPalletId;
+	type MaxPoolAssets = MaxPoolAssets;
+	type StableSwapFee = StableSwapFee;
+	type MaxTicks = MaxTicks;
+	type FlashBorrower = MockBorrower;
+	type Currency = Balances;
+	type PoolDeposit = PoolDeposit;
+	type AdminOrigin = frame_system::EnsureRoot<u64>;
+	type MaxPriceMove = MaxPriceMove;
+	type MaxOrdersPerPool = MaxOrdersPerPool;
+	type MinLimitOrderAmount = MinLimitOrderAmount;
+	type MaxOrderLifetime = MaxOrderLifetime;
+	type MaxIntentsPerBatch = MaxIntentsPerBatch;
+	type MaxAuctionsPerBlock = MaxAuctionsPerBlock;
+	type CommitmentDeposit = CommitmentDeposit;
+	type RevealPeriod = RevealPeriod;
+	type MaxCommitmentsPerBlock = MaxCommitmentsPerBlock;
+	type FarmOrigin = frame_system::EnsureSignedBy<FarmAdmin, u64>;
+}
+
+/// Pays back the amounts SCALE-encoded in `data` as `(first_repayment, second_repayment, reenter)`,
+/// swapping in the same pool first if `reenter` is set.
+pub struct MockBorrower;
+
+impl FlashBorrower<u64> for MockBorrower {
+	fn on_flash_loan(
+		borrower: &u64,
+		pool_account: &u64,
+		first_token_id: u64,
+		_first_amount: u64,
+		second_token_id: u64,
+		_second_amount: u64,
+		data: &[u8],
+	) -> DispatchResult {
+		let (first_repayment, second_repayment, reenter) = <(u64, u64, bool)>::decode(&mut &data[..])
+			.map_err(|_| DispatchError::Other("unexpected flash loan data"))?;
+		if reenter {
+			Dex::swap_token(Origin::signed(*borrower), 0, first_token_id, 1)?;
+		}
+		if first_repayment > 0 {
+			MultiToken::transfer_to(Origin::signed(*borrower), *pool_account, first_token_id, first_repayment)?;
+		}
+		if second_repayment > 0 {
+			MultiToken::transfer_to(Origin::signed(*borrower), *pool_account, second_token_id, second_repayment)?;
+		}
+		Ok(())
+	}
 }
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	let mut storage = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(whitelisted_caller(), 1_000), (account("recepient", 0, 1), 1_000)],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+	storage.into()
 }
\ No newline at end of file
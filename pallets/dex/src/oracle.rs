@@ -0,0 +1,38 @@
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_runtime::{FixedPointNumber, FixedU128, RuntimeDebug};
+
+/// Source of manipulation-resistant prices for other pallets.
+pub trait PriceOracle<BlockNumber> {
+	/// Time-weighted average price of `base_token_id` expressed in `quote_token_id` over the last
+	/// `window` blocks. Returns `None` if no pool trades the pair or its history is too short.
+	fn twap(base_token_id: u64, quote_token_id: u64, window: BlockNumber) -> Option<FixedU128>;
+}
+
+/// Snapshot of the cumulative pool prices, taken on the first change of the pool reserves in
+/// a block.
+///
+/// Cumulative prices are sums of `FixedU128` inner values multiplied by the number of blocks
+/// they were in effect and are allowed to wrap, so only differences between snapshots matter.
+/// As in Uniswap v2, prices come from the reserves the pool recorded itself after its last
+/// swap or liquidity change, never from its live balances, so tokens merely sent to the pool
+/// can't move them.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Observation<BlockNumber> {
+	pub block_number: BlockNumber,
+	pub first_price_cumulative: u128,
+	pub second_price_cumulative: u128,
+	/// Reserves after the last change in `block_number`, in effect until the next observation.
+	pub first_reserve: u64,
+	pub second_reserve: u64,
+}
+
+impl<BlockNumber> Observation<BlockNumber> {
+	/// Prices of the first token in the second one and vice versa at the recorded reserves.
+	pub fn prices(&self) -> (FixedU128, FixedU128) {
+		(
+			FixedU128::checked_from_rational(self.second_reserve, self.first_reserve).unwrap_or_default(),
+			FixedU128::checked_from_rational(self.first_reserve, self.second_reserve).unwrap_or_default(),
+		)
+	}
+}
@@ -0,0 +1,197 @@
+//! Limit orders filled against constant-product pools.
+//!
+//! Placing an order moves its input, at least `MinLimitOrderAmount`, into the escrow account of
+//! the pallet, for at most `MaxOrderLifetime` blocks. Each block, the weight left in `on_idle`
+//! is spent walking the order books, oldest order first, starting after the pool walked last
+//! so every pool gets its turn. An order is swapped as a whole once the pool pays at least its
+//! minimum output for it, and is refunded once it expires. Orders nobody got to in a block are
+//! tried again in a later one.
+
+use crate::{
+	Config, Error, Event, FirstTokeId, LimitOrderCursor, NextOrderId, OrderBooks, Orders, Pallet, PoolId, SecondTokeId,
+};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{
+	ensure,
+	pallet_prelude::{DispatchError, DispatchResult, Weight},
+	storage::{with_transaction, TransactionOutcome},
+	traits::Get,
+};
+use pallet_multi_token::multitoken::MultiToken;
+use scale_info::TypeInfo;
+use sp_runtime::{traits::Saturating, RuntimeDebug};
+
+/// Identifier of a limit order.
+pub type OrderId = u64;
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct LimitOrder<T: Config> {
+	pub owner: T::AccountId,
+	pub pool_id: PoolId,
+	pub token_in: u64,
+	pub amount_in: u64,
+	/// Output below which the order is not filled, `amount_in` times the limit price.
+	pub min_amount_out: u64,
+	/// First block the order can no longer be filled in.
+	pub expires_at: T::BlockNumber,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Upper bound of the weight spent on a single order by `fill_limit_orders`.
+	fn limit_order_weight() -> Weight {
+		T::DbWeight::get().reads_writes(12, 8)
+	}
+
+	pub(crate) fn do_place_limit_order(
+		who: &T::AccountId,
+		pool_id: PoolId,
+		token_in: u64,
+		amount_in: u64,
+		min_amount_out: u64,
+		expires_at: T::BlockNumber,
+	) -> Result<OrderId, DispatchError> {
+		Self::constant_product_pool(pool_id)?;
+		ensure!(
+			token_in == FirstTokeId::<T>::get(pool_id) || token_in == SecondTokeId::<T>::get(pool_id),
+			Error::<T>::UnknownToken
+		);
+		ensure!(amount_in > 0, Error::<T>::ZeroAmount);
+		ensure!(amount_in >= T::MinLimitOrderAmount::get(), Error::<T>::OrderTooSmall);
+		let now = frame_system::Pallet::<T>::block_number();
+		ensure!(expires_at > now, Error::<T>::OrderExpired);
+		ensure!(expires_at <= now.saturating_add(T::MaxOrderLifetime::get()), Error::<T>::OrderLifetimeTooLong);
+
+		let order_id = NextOrderId::<T>::get();
+		OrderBooks::<T>::try_mutate(pool_id, |book| book.try_push(order_id))
+			.map_err(|_| Error::<T>::TooManyOrders)?;
+		T::MultiToken::transfer_to(who, &Self::escrow_account(), &token_in, &amount_in)?;
+		NextOrderId::<T>::put(order_id + 1);
+		Orders::<T>::insert(
+			order_id,
+			LimitOrder { owner: who.clone(), pool_id, token_in, amount_in, min_amount_out, expires_at },
+		);
+		Self::deposit_event(Event::<T>::LimitOrderPlaced {
+			order_id,
+			owner: who.clone(),
+			pool_id,
+			token_in,
+			amount_in,
+			min_amount_out,
+			expires_at,
+		});
+		Ok(order_id)
+	}
+
+	pub(crate) fn do_cancel_limit_order(who: &T::AccountId, order_id: OrderId) -> DispatchResult {
+		let order = Orders::<T>::get(order_id).ok_or(Error::<T>::NoOrder)?;
+		ensure!(order.owner == *who, Error::<T>::NotOrderOwner);
+		T::MultiToken::refund(&Self::escrow_account(), who, &order.token_in, &order.amount_in)?;
+		Orders::<T>::remove(order_id);
+		OrderBooks::<T>::mutate_exists(order.pool_id, |book| {
+			if let Some(orders) = book {
+				orders.retain(|id| *id != order_id);
+				if orders.is_empty() {
+					*book = None;
+				}
+			}
+		});
+		Self::deposit_event(Event::<T>::LimitOrderCancelled { order_id, owner: order.owner });
+		Ok(())
+	}
+
+	/// Refunds and removes every open order against `pool_id`.
+	pub(crate) fn refund_limit_orders(pool_id: PoolId) -> DispatchResult {
+		for order_id in OrderBooks::<T>::take(pool_id) {
+			if let Some(order) = Orders::<T>::take(order_id) {
+				T::MultiToken::refund(&Self::escrow_account(), &order.owner, &order.token_in, &order.amount_in)?;
+				Self::deposit_event(Event::<T>::LimitOrderCancelled { order_id, owner: order.owner });
+			}
+		}
+		Ok(())
+	}
+
+	/// Swaps the input of `order` to its owner, unless the pool pays less than its minimum.
+	fn fill_limit_order(order: &LimitOrder<T>) -> Result<u64, DispatchError> {
+		with_transaction(|| {
+			let filled = Self::do_swap(&Self::escrow_account(), &order.owner, order.pool_id, order.token_in, order.amount_in)
+				.and_then(|amount_out| {
+					ensure!(amount_out >= order.min_amount_out, Error::<T>::SlippageExceeded);
+					Ok(amount_out)
+				});
+			match filled {
+				Ok(amount_out) => TransactionOutcome::Commit(Ok(amount_out)),
+				Err(e) => TransactionOutcome::Rollback(Err(e)),
+			}
+		})
+	}
+
+	/// Fills and expires the orders `remaining_weight` suffices for, walking the order books
+	/// from the one after `LimitOrderCursor` round to it, returns the weight used.
+	pub(crate) fn fill_limit_orders(remaining_weight: Weight) -> Weight {
+		let now = frame_system::Pallet::<T>::block_number();
+		let order_weight = Self::limit_order_weight();
+		// The cursor, then for every book its key, the book itself and the cursor update.
+		let book_weight = T::DbWeight::get().reads_writes(2, 2);
+		let mut consumed = T::DbWeight::get().reads(1);
+		if consumed.saturating_add(book_weight).saturating_add(order_weight) > remaining_weight {
+			return 0
+		}
+
+		let cursor = LimitOrderCursor::<T>::get().map(OrderBooks::<T>::hashed_key_for);
+		let after_cursor = cursor.clone().map(OrderBooks::<T>::iter_keys_from).into_iter().flatten();
+		let up_to_cursor = OrderBooks::<T>::iter_keys().take_while(|pool_id| match &cursor {
+			Some(cursor) => OrderBooks::<T>::hashed_key_for(pool_id) <= *cursor,
+			None => true,
+		});
+		for pool_id in after_cursor.chain(up_to_cursor) {
+			if consumed.saturating_add(book_weight).saturating_add(order_weight) > remaining_weight {
+				break
+			}
+			consumed = consumed.saturating_add(book_weight);
+			LimitOrderCursor::<T>::put(pool_id);
+			let mut book = OrderBooks::<T>::get(pool_id);
+			book.retain(|order_id| {
+				if consumed.saturating_add(order_weight) > remaining_weight {
+					return true
+				}
+				consumed = consumed.saturating_add(order_weight);
+				let order = match Orders::<T>::get(order_id) {
+					Some(order) => order,
+					None => return false,
+				};
+
+				if order.expires_at <= now {
+					if T::MultiToken::refund(&Self::escrow_account(), &order.owner, &order.token_in, &order.amount_in)
+						.is_err()
+					{
+						return true
+					}
+					Orders::<T>::remove(order_id);
+					Self::deposit_event(Event::<T>::LimitOrderExpired { order_id: *order_id, owner: order.owner });
+					return false
+				}
+
+				match Self::fill_limit_order(&order) {
+					Ok(amount_out) => {
+						Orders::<T>::remove(order_id);
+						Self::deposit_event(Event::<T>::LimitOrderFilled {
+							order_id: *order_id,
+							owner: order.owner,
+							amount_out,
+						});
+						false
+					},
+					Err(_) => true,
+				}
+			});
+			if book.is_empty() {
+				OrderBooks::<T>::remove(pool_id);
+			} else {
+				OrderBooks::<T>::insert(pool_id, book);
+			}
+		}
+		consumed
+	}
+}
@@ -0,0 +1,321 @@
+//! StableSwap (Curve-style) invariant for pools of pegged assets.
+//!
+//! The invariant is `A·n^n·Σx + D = A·D·n^n + D^(n+1) / (n^n·Πx)`, solved for `D` and for a
+//! single balance with Newton's method on integers, exactly as the reference Vyper
+//! implementation does, so results are deterministic and reproducible off-chain.
+
+use crate::{
+	BalanceOf, Config, Error, Event, LPTokenTotalSupply, Pallet, PoolId, PoolKind, StablePools,
+};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{ensure, pallet_prelude::DispatchResult, traits::Get, BoundedVec};
+use pallet_multi_token::multitoken::MultiToken;
+use scale_info::TypeInfo;
+use sp_core::U256;
+use sp_runtime::{DispatchError, FixedPointNumber, FixedU128, PerThing, RuntimeDebug};
+use sp_std::vec::Vec;
+
+/// Newton iterations before giving up on convergence.
+const MAX_ITERATIONS: u32 = 255;
+
+/// Largest accepted amplification coefficient.
+pub const MAX_AMPLIFICATION: u64 = 1_000_000;
+
+/// Parameters of a StableSwap pool. Reserves live on the pool account.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct StablePool<T: Config> {
+	pub assets: BoundedVec<u64, T::MaxPoolAssets>,
+	pub amplification: u64,
+}
+
+fn converged(current: U256, previous: U256) -> bool {
+	if current > previous {
+		current - previous <= U256::one()
+	} else {
+		previous - current <= U256::one()
+	}
+}
+
+/// Computes the invariant `D` of `balances` for the amplification coefficient `amplification`.
+///
+/// Returns `None` if a balance is zero, the arithmetic overflows or Newton's method does not
+/// converge.
+pub fn compute_d(balances: &[u128], amplification: u64) -> Option<u128> {
+	let n = U256::from(balances.len());
+	let sum = balances.iter().try_fold(U256::zero(), |acc, b| acc.checked_add(U256::from(*b)))?;
+	if sum.is_zero() {
+		return Some(0)
+	}
+	let ann = U256::from(amplification).checked_mul(n)?;
+	let mut d = sum;
+	for _ in 0..MAX_ITERATIONS {
+		let mut d_p = d;
+		for balance in balances {
+			let denominator = U256::from(*balance).checked_mul(n)?;
+			if denominator.is_zero() {
+				return None
+			}
+			d_p = d_p.checked_mul(d)?.checked_div(denominator)?;
+		}
+		let previous = d;
+		let numerator = ann.checked_mul(sum)?.checked_add(d_p.checked_mul(n)?)?.checked_mul(d)?;
+		let denominator = ann
+			.checked_sub(U256::one())?
+			.checked_mul(d)?
+			.checked_add(n.checked_add(U256::one())?.checked_mul(d_p)?)?;
+		d = numerator.checked_div(denominator)?;
+		if converged(d, previous) {
+			return d.try_into().ok()
+		}
+	}
+	None
+}
+
+/// Price of asset `i` in asset `j` at `balances`, before fees: the ratio of the derivatives of
+/// the invariant in the balances of `i` and `j`.
+pub fn spot_price(i: usize, j: usize, balances: &[u128], amplification: u64) -> Option<FixedU128> {
+	if i >= balances.len() || j >= balances.len() {
+		return None
+	}
+	let n = U256::from(balances.len());
+	let d = U256::from(compute_d(balances, amplification)?);
+	let ann = U256::from(amplification).checked_mul(n)?;
+	let mut d_p = d;
+	for balance in balances {
+		let denominator = U256::from(*balance).checked_mul(n)?;
+		if denominator.is_zero() {
+			return None
+		}
+		d_p = d_p.checked_mul(d)?.checked_div(denominator)?;
+	}
+	let (x_i, x_j) = (U256::from(balances[i]), U256::from(balances[j]));
+	let numerator = x_j.checked_mul(ann.checked_mul(x_i)?.checked_add(d_p)?)?;
+	let denominator = x_i.checked_mul(ann.checked_mul(x_j)?.checked_add(d_p)?)?;
+	let price = numerator.checked_mul(U256::from(FixedU128::DIV))?.checked_div(denominator)?;
+	Some(FixedU128::from_inner(price.try_into().ok()?))
+}
+
+/// Computes the balance of asset `j` that keeps the invariant of `balances` once the balance
+/// of asset `i` becomes `new_balance_i`.
+pub fn compute_y(
+	i: usize,
+	j: usize,
+	new_balance_i: u128,
+	balances: &[u128],
+	amplification: u64,
+) -> Option<u128> {
+	if i == j || i >= balances.len() || j >= balances.len() {
+		return None
+	}
+	let n = U256::from(balances.len());
+	let d = U256::from(compute_d(balances, amplification)?);
+	let ann = U256::from(amplification).checked_mul(n)?;
+	let mut c = d;
+	let mut sum = U256::zero();
+	for (k, balance) in balances.iter().enumerate() {
+		let x = if k == i {
+			new_balance_i
+		} else if k == j {
+			continue
+		} else {
+			*balance
+		};
+		let denominator = U256::from(x).checked_mul(n)?;
+		if denominator.is_zero() {
+			return None
+		}
+		sum = sum.checked_add(U256::from(x))?;
+		c = c.checked_mul(d)?.checked_div(denominator)?;
+	}
+	c = c.checked_mul(d)?.checked_div(ann.checked_mul(n)?)?;
+	let b = sum.checked_add(d.checked_div(ann)?)?;
+	let mut y = d;
+	for _ in 0..MAX_ITERATIONS {
+		let previous = y;
+		let numerator = y.checked_mul(y)?.checked_add(c)?;
+		let denominator = y.checked_mul(U256::from(2))?.checked_add(b)?.checked_sub(d)?;
+		y = numerator.checked_div(denominator)?;
+		if converged(y, previous) {
+			return y.try_into().ok()
+		}
+	}
+	None
+}
+
+impl<T: Config> Pallet<T> {
+	/// Current reserves of a StableSwap pool, in the order of its assets.
+	fn stable_balances(pool: &StablePool<T>, pool_address: &T::AccountId) -> Vec<u128> {
+		pool.assets
+			.iter()
+			.map(|id| T::MultiToken::balances(id, pool_address) as u128)
+			.collect()
+	}
+
+	pub(crate) fn do_create_stable_pool(
+		creator: &T::AccountId,
+		assets: Vec<u64>,
+		amounts: Vec<u64>,
+		amplification: u64,
+	) -> DispatchResult {
+		ensure!(assets.len() == amounts.len(), Error::<T>::ShouldBeSameLength);
+		ensure!(assets.len() >= 2, Error::<T>::TooFewTokens);
+		ensure!(
+			(1..=MAX_AMPLIFICATION).contains(&amplification),
+			Error::<T>::InvalidAmplification
+		);
+		for (index, id) in assets.iter().enumerate() {
+			ensure!(!assets[..index].contains(id), Error::<T>::DuplicateToken);
+		}
+		ensure!(amounts.iter().all(|amount| *amount > 0), Error::<T>::ZeroAmount);
+		let assets: BoundedVec<u64, T::MaxPoolAssets> =
+			assets.try_into().map_err(|_| Error::<T>::TooManyTokens)?;
+
+		let balances: Vec<u128> = amounts.iter().map(|amount| *amount as u128).collect();
+		let d = compute_d(&balances, amplification).ok_or(Error::<T>::Overflow)?;
+		let liquidity: u64 = d.try_into().map_err(|_| Error::<T>::Overflow)?;
+
+		let (pool_id, pool_address) = Self::register_pool(creator, PoolKind::StableSwap)?;
+		for (id, amount) in assets.iter().zip(amounts.iter()) {
+			T::MultiToken::transfer_to(creator, &pool_address, id, amount)?;
+		}
+		StablePools::<T>::insert(pool_id, StablePool { assets, amplification });
+		LPTokenTotalSupply::<T>::insert(pool_id, liquidity);
+		BalanceOf::<T>::insert(pool_id, creator, liquidity);
+		Ok(())
+	}
+
+	pub(crate) fn do_add_stable_liquidity(
+		who: &T::AccountId,
+		pool_id: PoolId,
+		amounts: Vec<u64>,
+		min_liquidity: u64,
+	) -> DispatchResult {
+		let pool = StablePools::<T>::get(pool_id).ok_or(Error::<T>::NoPool)?;
+		Self::ensure_pool_active(pool_id)?;
+		let pool_address = Self::pool_account(pool_id);
+		ensure!(amounts.len() == pool.assets.len(), Error::<T>::ShouldBeSameLength);
+
+		let old_balances = Self::stable_balances(&pool, &pool_address);
+		let new_balances: Vec<u128> = old_balances
+			.iter()
+			.zip(amounts.iter())
+			.map(|(balance, amount)| balance + *amount as u128)
+			.collect();
+		let d0 = compute_d(&old_balances, pool.amplification).ok_or(Error::<T>::Overflow)?;
+		let d1 = compute_d(&new_balances, pool.amplification).ok_or(Error::<T>::Overflow)?;
+		ensure!(d0 > 0, Error::<T>::NoLiquidity);
+		ensure!(d1 > d0, Error::<T>::ZeroAmount);
+
+		// Deposits that shift the pool away from its current composition pay the swap fee on the
+		// imbalance, otherwise adding one asset and removing all of them would be a free swap.
+		let n = old_balances.len() as u128;
+		let fee_parts = T::StableSwapFee::get().deconstruct() as u128;
+		let balances_after_fee: Vec<u128> = old_balances
+			.iter()
+			.zip(new_balances.iter())
+			.map(|(old, new)| {
+				let ideal = U256::from(d1) * U256::from(*old) / U256::from(d0);
+				let ideal = ideal.try_into().unwrap_or(u128::MAX);
+				let difference = if ideal > *new { ideal - new } else { new - ideal };
+				let fee = difference.saturating_mul(fee_parts).saturating_mul(n) /
+					(4 * (n - 1) * 1_000_000);
+				new.saturating_sub(fee)
+			})
+			.collect();
+		let d2 = compute_d(&balances_after_fee, pool.amplification).ok_or(Error::<T>::Overflow)?;
+
+		let total_supply = LPTokenTotalSupply::<T>::get(pool_id);
+		let liquidity: u64 = (U256::from(total_supply) * U256::from(d2.saturating_sub(d0)) /
+			U256::from(d0))
+		.try_into()
+		.map_err(|_| Error::<T>::Overflow)?;
+		ensure!(liquidity > 0, Error::<T>::ZeroAmount);
+		ensure!(liquidity >= min_liquidity, Error::<T>::SlippageExceeded);
+
+		for (id, amount) in pool.assets.iter().zip(amounts.iter()) {
+			if *amount > 0 {
+				T::MultiToken::transfer_to(who, &pool_address, id, amount)?;
+			}
+		}
+		LPTokenTotalSupply::<T>::insert(pool_id, total_supply + liquidity);
+		BalanceOf::<T>::mutate(pool_id, who, |x| *x += liquidity);
+		Self::deposit_event(Event::<T>::StableLiquidityAdded { who: who.clone(), pool_id, amounts, liquidity });
+		Ok(())
+	}
+
+	pub(crate) fn do_remove_stable_liquidity(
+		who: &T::AccountId,
+		pool_id: PoolId,
+		liquidity: u64,
+		min_amounts: Vec<u64>,
+	) -> DispatchResult {
+		let pool = StablePools::<T>::get(pool_id).ok_or(Error::<T>::NoPool)?;
+		let pool_address = Self::pool_account(pool_id);
+		ensure!(min_amounts.len() == pool.assets.len(), Error::<T>::ShouldBeSameLength);
+		ensure!(liquidity > 0, Error::<T>::ZeroAmount);
+		ensure!(BalanceOf::<T>::get(pool_id, who) >= liquidity, Error::<T>::NoLiquidity);
+
+		let total_supply = LPTokenTotalSupply::<T>::get(pool_id);
+		let balances = Self::stable_balances(&pool, &pool_address);
+		let mut amounts = Vec::with_capacity(pool.assets.len());
+		for ((id, balance), min_amount) in
+			pool.assets.iter().zip(balances.iter()).zip(min_amounts.iter())
+		{
+			let amount = (balance * liquidity as u128 / total_supply as u128) as u64;
+			ensure!(amount >= *min_amount, Error::<T>::SlippageExceeded);
+			T::MultiToken::transfer_to(&pool_address, who, id, &amount)?;
+			amounts.push(amount);
+		}
+		LPTokenTotalSupply::<T>::insert(pool_id, total_supply - liquidity);
+		BalanceOf::<T>::mutate(pool_id, who, |x| *x -= liquidity);
+		Self::deposit_event(Event::<T>::StableLiquidityRemoved { who: who.clone(), pool_id, liquidity, amounts });
+		Ok(())
+	}
+
+	pub(crate) fn do_stable_swap(
+		who: &T::AccountId,
+		pool_id: PoolId,
+		token_in: u64,
+		token_out: u64,
+		amount_in: u64,
+		min_amount_out: u64,
+	) -> Result<u64, DispatchError> {
+		let pool = StablePools::<T>::get(pool_id).ok_or(Error::<T>::NoPool)?;
+		Self::ensure_pool_active(pool_id)?;
+		let pool_address = Self::pool_account(pool_id);
+		let i = pool.assets.iter().position(|id| *id == token_in).ok_or(Error::<T>::UnknownToken)?;
+		let j = pool.assets.iter().position(|id| *id == token_out).ok_or(Error::<T>::UnknownToken)?;
+		ensure!(i != j, Error::<T>::DuplicateToken);
+		ensure!(amount_in > 0, Error::<T>::ZeroAmount);
+
+		let balances = Self::stable_balances(&pool, &pool_address);
+		let y = compute_y(i, j, balances[i] + amount_in as u128, &balances, pool.amplification)
+			.ok_or(Error::<T>::Overflow)?;
+		// One unit is kept in the pool to absorb rounding in the Newton iteration.
+		let dy = balances[j].checked_sub(y).and_then(|dy| dy.checked_sub(1)).unwrap_or_default();
+		let dy: u64 = dy.try_into().map_err(|_| Error::<T>::Overflow)?;
+		let amount_out = dy - T::StableSwapFee::get().mul_ceil(dy);
+		ensure!(amount_out > 0, Error::<T>::NoLiquidity);
+		ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+		let price = spot_price(i, j, &balances, pool.amplification).ok_or(Error::<T>::Overflow)?;
+		let mut new_balances = balances;
+		new_balances[i] += amount_in as u128;
+		new_balances[j] -= amount_out as u128;
+		let new_price = spot_price(i, j, &new_balances, pool.amplification).ok_or(Error::<T>::Overflow)?;
+		Self::check_swap_price_move(pool_id, token_in, token_out, price, new_price)?;
+
+		T::MultiToken::transfer_to(who, &pool_address, &token_in, &amount_in)?;
+		T::MultiToken::transfer_to(&pool_address, who, &token_out, &amount_out)?;
+		Self::deposit_event(Event::<T>::StableSwapped {
+			who: who.clone(),
+			pool_id,
+			token_in,
+			amount_in,
+			token_out,
+			amount_out,
+		});
+		Ok(amount_out)
+	}
+}
@@ -0,0 +1,51 @@
+//! Token swaps for other pallets, routed through the constant-product pool of a pair.
+
+use crate::{Config, Error, PairPool, Pallet, Pool};
+use frame_support::traits::Get;
+use pallet_multi_token::multitoken::MultiToken;
+use sp_runtime::DispatchError;
+
+/// Swaps between token pairs, by whatever pool trades them.
+pub trait Swap<AccountId> {
+	/// Smallest amount of `token_in` paying at least `amount_out` of `token_out`, at current
+	/// reserves. `None` without a pool or with too little of `token_out` in it.
+	fn quote_amount_in(token_in: u64, token_out: u64, amount_out: u64) -> Option<u64>;
+
+	/// Sells `amount_in` of `token_in` from `who` for `token_out` paid to `to`, returning the
+	/// amount paid.
+	fn swap_exact_in(
+		who: &AccountId,
+		to: &AccountId,
+		token_in: u64,
+		token_out: u64,
+		amount_in: u64,
+	) -> Result<u64, DispatchError>;
+}
+
+impl<T: Config> Swap<T::AccountId> for Pallet<T> {
+	fn quote_amount_in(token_in: u64, token_out: u64, amount_out: u64) -> Option<u64> {
+		let pool_id = PairPool::<T>::get(Self::pair_key(token_in, token_out))?;
+		let pool_address = Pool::<T>::get(pool_id)?;
+		let reserve_in = T::MultiToken::balances(&token_in, &pool_address) as u128;
+		let reserve_out = T::MultiToken::balances(&token_out, &pool_address) as u128;
+		// Output before the fee, then the input paying it on the `x * y = k` curve.
+		let keep = 100 - T::Fee::get() as u128;
+		let gross_out = (amount_out as u128 * 100 + keep - 1) / keep;
+		if gross_out >= reserve_out {
+			return None
+		}
+		let amount_in = (gross_out * reserve_in + reserve_out - gross_out - 1) / (reserve_out - gross_out);
+		amount_in.try_into().ok()
+	}
+
+	fn swap_exact_in(
+		who: &T::AccountId,
+		to: &T::AccountId,
+		token_in: u64,
+		token_out: u64,
+		amount_in: u64,
+	) -> Result<u64, DispatchError> {
+		let pool_id = PairPool::<T>::get(Self::pair_key(token_in, token_out)).ok_or(Error::<T>::NoPool)?;
+		Self::do_swap(who, to, pool_id, token_in, amount_in)
+	}
+}
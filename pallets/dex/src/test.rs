@@ -1,17 +1,25 @@
-use frame_support::{ assert_ok, assert_noop};
+use codec::Encode;
+use frame_support::{ assert_ok, assert_noop, traits::{GetStorageVersion, Hooks, StorageVersion}};
 use frame_benchmarking::{account, whitelisted_caller};
-use crate::{mock::*, Error};
+use crate::{mock::*, BalanceOf, ConcentratedPools, Error, LPTokenTotalSupply, NextPoolId, OrderBooks, Orders, PairPool, Pool, PoolIds, Positions, MINIMUM_LIQUIDITY, STORAGE_VERSION};
+use crate::auction::clear_batch;
+use crate::migrations::{migrate_to_v1, v0};
+use crate::concentrated::{compute_swap_step, sqrt_price_at_tick, tick_at_sqrt_price, MAX_TICK, MIN_TICK};
+use crate::oracle::PriceOracle;
+use crate::stable::{compute_d, compute_y};
+use crate::weighted::{out_given_in, pool_out_given_single_in, single_out_given_pool_in};
 use frame_system;
+use sp_runtime::{FixedPointNumber, FixedU128, Permill};
 
 type AccountId = <Test as frame_system::Config>::AccountId;
 
 #[test]
 fn should_init() {
 	new_test_ext().execute_with(|| {
-        let pool_address: AccountId = account("pool", 0, 1);
+        let pool_address: AccountId = Dex::pool_account(0);
         MultiToken::mint(Origin::signed(whitelisted_caller()), 0, 9_000_000);
         MultiToken::mint(Origin::signed(whitelisted_caller()), 1, 9_000_000);
-		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), pool_address, 0,  9_000_000, 1, 9_000_000));
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0,  9_000_000, 1, 9_000_000));
 		assert_eq!(MultiToken::balances(0, pool_address), Some(9_000_000));
 		assert_eq!(MultiToken::balances(1, pool_address), Some(9_000_000));
 	});
@@ -21,14 +29,13 @@ fn should_init() {
 #[test]
 fn should_swap() {
 	new_test_ext().execute_with(|| {
-        let pool_address: AccountId = account("pool", 0, 1);
 		let recepient: AccountId = account("recepient", 0, 1);
         MultiToken::mint(Origin::signed(whitelisted_caller()), 0, 9_000_000);
         MultiToken::mint(Origin::signed(whitelisted_caller()), 1, 9_000_000);
 		MultiToken::mint(Origin::signed(recepient), 0, 100_000);
 
-		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), pool_address, 0,  9_000_000, 1, 9_000_000));
-		assert_ok!(Dex::swap_token(Origin::signed(recepient), 0, 100_000));
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0,  9_000_000, 1, 9_000_000));
+		assert_ok!(Dex::swap_token(Origin::signed(recepient), 0, 0, 100_000));
 		assert_eq!(MultiToken::balances(1, recepient), Some((100_000 * 9_000_000) / (9_000_000 + 100_000) * 97 / 100));
 	});
 }
@@ -36,15 +43,14 @@ fn should_swap() {
 #[test]
 fn should_deposit() {
 	new_test_ext().execute_with(|| {
-        let pool_address: AccountId = account("pool", 0, 1);
 		let recepient: AccountId = account("recepient", 0, 1);
         MultiToken::mint(Origin::signed(whitelisted_caller()), 0, 9_000_000);
         MultiToken::mint(Origin::signed(whitelisted_caller()), 1, 9_000_000);
 		MultiToken::mint(Origin::signed(recepient), 0, 100_000);
 		MultiToken::mint(Origin::signed(recepient), 1, 100_000);
 
-		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), pool_address, 0,  9_000_000, 1, 9_000_000));
-		assert_ok!(Dex::deposit(Origin::signed(recepient), 100_000, 100_000));
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0,  9_000_000, 1, 9_000_000));
+		assert_ok!(Dex::deposit(Origin::signed(recepient), 0, 100_000, 100_000));
 		assert_eq!(MultiToken::balances(0, recepient), Some(9_100_000));
 		assert_eq!(MultiToken::balances(1, recepient), Some(9_100_000));
 	});
@@ -53,7 +59,6 @@ fn should_deposit() {
 #[test]
 fn should_withdraw_with_additional_liquidity() {
 	new_test_ext().execute_with(|| {
-        let pool_address: AccountId = account("pool", 0, 1);
 		let recepient: AccountId = account("recepient", 0, 1);
 		let second_recepient: AccountId = account("recepient2", 0, 1);
 
@@ -61,20 +66,1310 @@ fn should_withdraw_with_additional_liquidity() {
 		MultiToken::mint_batch(Origin::signed(recepient), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
 		MultiToken::mint_batch(Origin::signed(second_recepient), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
 		let initial_liquidity: u64 = 1_000_000 * 1_000_000;
-		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), pool_address, 0,  9_000_000, 1, 9_000_000));
-		assert_ok!(Dex::deposit(Origin::signed(recepient), 1_000_000, 1_000_000));
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0,  9_000_000, 1, 9_000_000));
+		assert_ok!(Dex::deposit(Origin::signed(recepient), 0, 1_000_000, 1_000_000));
 
-		assert_ok!(Dex::swap_token(Origin::signed(second_recepient), 0, 100_000));
-		assert_ok!(Dex::swap_token(Origin::signed(second_recepient), 1, 100_000));
-		assert_ok!(Dex::swap_token(Origin::signed(second_recepient), 0, 100_000));
-		assert_ok!(Dex::swap_token(Origin::signed(second_recepient), 1, 100_000));
-		assert_ok!(Dex::swap_token(Origin::signed(second_recepient), 0, 100_000));
-		assert_ok!(Dex::swap_token(Origin::signed(second_recepient), 1, 100_000));
+		assert_ok!(Dex::swap_token(Origin::signed(second_recepient), 0, 0, 100_000));
+		assert_ok!(Dex::swap_token(Origin::signed(second_recepient), 0, 1, 100_000));
+		assert_ok!(Dex::swap_token(Origin::signed(second_recepient), 0, 0, 100_000));
+		assert_ok!(Dex::swap_token(Origin::signed(second_recepient), 0, 1, 100_000));
+		assert_ok!(Dex::swap_token(Origin::signed(second_recepient), 0, 0, 100_000));
+		assert_ok!(Dex::swap_token(Origin::signed(second_recepient), 0, 1, 100_000));
 
-		assert_ok!(Dex::withdraw(Origin::signed(recepient)));
+		assert_ok!(Dex::withdraw(Origin::signed(recepient), 0));
 		let first_token_balance = MultiToken::balances(0, recepient).unwrap();
 		let second_token_balance = MultiToken::balances(1, recepient).unwrap();
 		assert!(first_token_balance * second_token_balance > initial_liquidity)
 	});
 }
 
+#[test]
+fn should_track_time_weighted_price() {
+	new_test_ext().execute_with(|| {
+		let recepient: AccountId = account("recepient", 0, 1);
+        MultiToken::mint(Origin::signed(whitelisted_caller()), 0, 1_000_000);
+        MultiToken::mint(Origin::signed(whitelisted_caller()), 1, 1_000_000);
+		MultiToken::mint(Origin::signed(recepient), 0, 1_000_000);
+
+		System::set_block_number(1);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0,  1_000_000, 1, 1_000_000));
+		System::set_block_number(11);
+		// Leaves 2_000_000 of token 0 and 515_000 of token 1 in the pool.
+		assert_ok!(Dex::swap_token(Origin::signed(recepient), 0, 0, 1_000_000));
+		System::set_block_number(21);
+
+		assert_eq!(Dex::twap(0, 1, 10), Some(FixedU128::from_inner(257_500_000_000_000_000)));
+		assert_eq!(Dex::twap(0, 1, 20), Some(FixedU128::from_inner(628_750_000_000_000_000)));
+		assert_eq!(Dex::twap(0, 1, 30), None);
+		assert_eq!(Dex::twap(0, 2, 20), None);
+	});
+}
+
+#[test]
+fn should_ignore_donations_for_twap() {
+	new_test_ext().execute_with(|| {
+		let recepient: AccountId = account("recepient", 0, 1);
+        MultiToken::mint(Origin::signed(whitelisted_caller()), 0, 1_000_000);
+        MultiToken::mint(Origin::signed(whitelisted_caller()), 1, 1_000_000);
+		MultiToken::mint(Origin::signed(recepient), 0, 1_000_000);
+
+		System::set_block_number(1);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0,  1_000_000, 1, 1_000_000));
+		System::set_block_number(11);
+		// Tokens sent to the pool without a swap don't change the reserves the oracle prices.
+		assert_ok!(MultiToken::transfer_to(Origin::signed(recepient), Dex::pool_account(0), 0, 1_000_000));
+
+		assert_eq!(Dex::twap(0, 1, 10), Some(FixedU128::from_inner(1_000_000_000_000_000_000)));
+	});
+}
+
+#[test]
+fn should_ignore_swaps_within_the_same_block_for_twap() {
+	new_test_ext().execute_with(|| {
+		let recepient: AccountId = account("recepient", 0, 1);
+        MultiToken::mint(Origin::signed(whitelisted_caller()), 0, 1_000_000);
+        MultiToken::mint(Origin::signed(whitelisted_caller()), 1, 1_000_000);
+		MultiToken::mint(Origin::signed(recepient), 0, 1_000_000);
+
+		System::set_block_number(1);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0,  1_000_000, 1, 1_000_000));
+		System::set_block_number(11);
+		// A swap that is reverted later in the same block does not show up in the average.
+		assert_ok!(Dex::swap_token(Origin::signed(recepient), 0, 0, 1_000_000));
+		let received = MultiToken::balances(1, recepient).unwrap();
+		assert_ok!(Dex::swap_token(Origin::signed(recepient), 0, 1, received));
+
+		assert_eq!(Dex::twap(0, 1, 10), Some(FixedU128::from_inner(1_000_000_000_000_000_000)));
+	});
+}
+
+#[test]
+fn should_match_stable_swap_reference_vectors() {
+	// Values produced by the reference Curve `get_D`/`get_y` implementation.
+	assert_eq!(compute_d(&[1_000_000, 1_000_000], 100), Some(2_000_000));
+	assert_eq!(compute_d(&[1_000_000, 3_000_000], 100), Some(3_993_431));
+	assert_eq!(compute_d(&[1_000_000, 3_000_000], 1), Some(3_717_778));
+	assert_eq!(compute_d(&[5_000_000_000, 4_000_000_000, 6_000_000_000], 200), Some(14_998_963_801));
+	assert_eq!(
+		compute_d(&[1_000_000_000_000_000_000, 2_000_000_000_000_000_000, 1_000_000_000_000_000_000, 3_000_000_000_000_000_000], 1000),
+		Some(6_999_016_161_537_321_714)
+	);
+	assert_eq!(compute_d(&[0, 0], 100), Some(0));
+	assert_eq!(compute_d(&[1_000_000, 0], 100), None);
+
+	let balances = [1_000_000, 1_000_000];
+	assert_eq!(compute_y(0, 1, 1_100_000, &balances, 100).map(|y| balances[1] - y - 1), Some(99_900));
+	let balances = [5_000_000_000, 4_000_000_000, 6_000_000_000];
+	assert_eq!(compute_y(1, 2, 5_000_000_000, &balances, 200).map(|y| balances[2] - y - 1), Some(1_001_036_198));
+	assert_eq!(compute_y(1, 1, 5_000_000_000, &balances, 200), None);
+}
+
+#[test]
+fn should_swap_in_stable_pool() {
+	new_test_ext().execute_with(|| {
+		let recepient: AccountId = account("recepient", 0, 1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(recepient), 0, 100_000);
+
+		assert_ok!(Dex::create_stable_pool(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec(), 100));
+		assert_eq!(Dex::balance_of(0, whitelisted_caller::<AccountId>()), 2_000_000);
+
+		assert_noop!(
+			Dex::stable_swap(Origin::signed(recepient), 0, 0, 1, 100_000, 99_861),
+			Error::<Test>::SlippageExceeded
+		);
+		assert_ok!(Dex::stable_swap(Origin::signed(recepient), 0, 0, 1, 100_000, 99_860));
+		// 99_900 out of the invariant minus the 0.04% fee, where x * y = k would give 90_909.
+		assert_eq!(MultiToken::balances(1, recepient), Some(99_860));
+		assert_eq!(MultiToken::balances(0, Dex::pool_account(0)), Some(1_100_000));
+	});
+}
+
+#[test]
+fn should_charge_imbalanced_stable_deposits() {
+	new_test_ext().execute_with(|| {
+		let recepient: AccountId = account("recepient", 0, 1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(recepient), 0, 100_000);
+
+		assert_ok!(Dex::create_stable_pool(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec(), 100));
+		System::set_block_number(1);
+		assert_ok!(Dex::add_stable_liquidity(Origin::signed(recepient), 0, [100_000, 0].to_vec(), 0));
+		let liquidity = Dex::balance_of(0, recepient);
+		assert_eq!(liquidity, 99_957);
+		System::assert_last_event(
+			crate::Event::<Test>::StableLiquidityAdded { who: recepient, pool_id: 0, amounts: [100_000, 0].to_vec(), liquidity }.into(),
+		);
+
+		assert_ok!(Dex::remove_stable_liquidity(Origin::signed(recepient), 0, liquidity, [0, 0].to_vec()));
+		System::assert_last_event(
+			crate::Event::<Test>::StableLiquidityRemoved { who: recepient, pool_id: 0, liquidity, amounts: [52_359, 47_599].to_vec() }
+				.into(),
+		);
+		let first_token_balance = MultiToken::balances(0, recepient).unwrap();
+		let second_token_balance = MultiToken::balances(1, recepient).unwrap();
+		assert!(first_token_balance + second_token_balance < 100_000);
+		assert_eq!(Dex::balance_of(0, recepient), 0);
+	});
+}
+
+#[test]
+fn should_revert_invalid_stable_pools() {
+	new_test_ext().execute_with(|| {
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		assert_noop!(
+			Dex::create_stable_pool(Origin::signed(whitelisted_caller()), [0].to_vec(), [1_000_000].to_vec(), 100),
+			Error::<Test>::TooFewTokens
+		);
+		assert_noop!(
+			Dex::create_stable_pool(Origin::signed(whitelisted_caller()), [0, 0].to_vec(), [1_000, 1_000].to_vec(), 100),
+			Error::<Test>::DuplicateToken
+		);
+		assert_noop!(
+			Dex::create_stable_pool(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000, 1_000].to_vec(), 0),
+			Error::<Test>::InvalidAmplification
+		);
+		assert_noop!(
+			Dex::create_stable_pool(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000].to_vec(), 100),
+			Error::<Test>::ShouldBeSameLength
+		);
+	});
+}
+
+#[test]
+fn should_price_weighted_swaps() {
+	let within_one = |actual: Option<u64>, expected: u64| assert!(actual.unwrap().abs_diff(expected) <= 1, "{:?} != {}", actual, expected);
+	// Equal weights reduce to the constant product formula.
+	assert_eq!(out_given_in(1_000_000, 50, 1_000_000, 50, 10_000, Permill::zero()), Some(9_900));
+	// 1_000_000 * (1 - (1_000_000 / 1_010_000) ^ (w_in / w_out)) for 80/20, 20/80 and 60/40 pools.
+	within_one(out_given_in(1_000_000, 80, 1_000_000, 20, 10_000, Permill::zero()), 39_019);
+	within_one(out_given_in(1_000_000, 20, 1_000_000, 80, 10_000, Permill::zero()), 2_484);
+	within_one(out_given_in(1_000_000, 60, 1_000_000, 40, 10_000, Permill::zero()), 14_814);
+
+	// A single-sided join followed by a single-sided exit returns the deposit minus fees.
+	let fee = Permill::from_parts(3_000);
+	let liquidity = pool_out_given_single_in(1_000_000, 50, 100, 1_000_000_000_000, 10_000, fee).unwrap();
+	let amount_out = single_out_given_pool_in(1_010_000, 50, 100, 1_000_000_000_000 + liquidity, liquidity, fee).unwrap();
+	assert!(amount_out < 10_000 && amount_out > 9_950);
+}
+
+#[test]
+fn should_swap_between_any_weighted_pool_assets() {
+	new_test_ext().execute_with(|| {
+		let recepient: AccountId = account("recepient", 0, 1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1, 2].to_vec(), [1_000_000, 500_000, 500_000].to_vec());
+		MultiToken::mint_batch(Origin::signed(recepient), [0, 1].to_vec(), [10_000, 10_000].to_vec());
+
+		assert_ok!(Dex::create_weighted_pool(
+			Origin::signed(whitelisted_caller()),
+			[0, 1, 2].to_vec(),
+			[50, 25, 25].to_vec(),
+			[1_000_000, 500_000, 500_000].to_vec(),
+			Permill::from_parts(3_000)
+		));
+		assert_ok!(Dex::weighted_swap(Origin::signed(recepient), 0, 1, 2, 10_000, 0));
+		// 500_000 * 9_970 / 509_970 after the 0.3% fee on the input.
+		let received = MultiToken::balances(2, recepient).unwrap();
+		assert!(received.abs_diff(9_774) <= 1);
+
+		assert_ok!(Dex::weighted_swap(Origin::signed(recepient), 0, 0, 1, 10_000, 0));
+		assert!(MultiToken::balances(1, recepient).unwrap() > 0);
+
+		assert_noop!(
+			Dex::weighted_swap(Origin::signed(whitelisted_caller()), 0, 2, 0, 500_000, 0),
+			Error::<Test>::MaxInRatio
+		);
+		assert_noop!(
+			Dex::weighted_swap(Origin::signed(recepient), 0, 1, 3, 1_000, 0),
+			Error::<Test>::UnknownToken
+		);
+	});
+}
+
+#[test]
+fn should_join_and_exit_weighted_pool_with_single_asset() {
+	new_test_ext().execute_with(|| {
+		let recepient: AccountId = account("recepient", 0, 1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 250_000].to_vec());
+		MultiToken::mint(Origin::signed(recepient), 0, 10_000);
+
+		assert_ok!(Dex::create_weighted_pool(
+			Origin::signed(whitelisted_caller()),
+			[0, 1].to_vec(),
+			[80, 20].to_vec(),
+			[1_000_000, 250_000].to_vec(),
+			Permill::from_parts(3_000)
+		));
+		System::set_block_number(1);
+		assert_ok!(Dex::join_weighted_pool_single(Origin::signed(recepient), 0, 0, 10_000, 0));
+		let liquidity = Dex::balance_of(0, recepient);
+		assert!(liquidity > 0);
+		System::assert_last_event(
+			crate::Event::<Test>::WeightedPoolJoined { who: recepient, pool_id: 0, amounts: [10_000, 0].to_vec(), liquidity }.into(),
+		);
+
+		assert_ok!(Dex::exit_weighted_pool_single(Origin::signed(recepient), 0, 0, liquidity, 0));
+		let returned = MultiToken::balances(0, recepient).unwrap();
+		assert!(returned < 10_000 && returned > 9_950);
+		System::assert_last_event(
+			crate::Event::<Test>::WeightedPoolExited { who: recepient, pool_id: 0, liquidity, amounts: [returned, 0].to_vec() }.into(),
+		);
+		assert_eq!(Dex::balance_of(0, recepient), 0);
+	});
+}
+
+#[test]
+fn should_revert_invalid_weighted_pools() {
+	new_test_ext().execute_with(|| {
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		assert_noop!(
+			Dex::create_weighted_pool(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [0, 100].to_vec(), [1_000, 1_000].to_vec(), Permill::zero()),
+			Error::<Test>::InvalidWeights
+		);
+		assert_noop!(
+			Dex::create_weighted_pool(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [50, 50].to_vec(), [1_000, 1_000].to_vec(), Permill::from_percent(20)),
+			Error::<Test>::InvalidFee
+		);
+	});
+}
+
+#[test]
+fn should_map_ticks_to_sqrt_prices() {
+	assert_eq!(sqrt_price_at_tick(0), Some(FixedU128::saturating_from_integer(1)));
+	assert_eq!(sqrt_price_at_tick(MAX_TICK + 1), None);
+	assert_eq!(sqrt_price_at_tick(MIN_TICK - 1), None);
+	// 1.0001 ^ (20_000 / 2) = 2.718145926825...
+	let e = sqrt_price_at_tick(20_000).unwrap().into_inner();
+	assert!(e.abs_diff(2_718_145_926_825_224_864) < 1_000_000_000);
+	for tick in [MIN_TICK, -20_001, -1, 0, 1, 887, 20_000, MAX_TICK] {
+		assert_eq!(tick_at_sqrt_price(sqrt_price_at_tick(tick).unwrap()), Some(tick));
+	}
+	let between = sqrt_price_at_tick(11).unwrap() + FixedU128::from_inner(1);
+	assert_eq!(tick_at_sqrt_price(between), Some(11));
+}
+
+#[test]
+fn should_compute_concentrated_swap_steps() {
+	let one = FixedU128::saturating_from_integer(1);
+	let far = sqrt_price_at_tick(MIN_TICK).unwrap();
+	// Virtual reserves of 1_000_000 each: 1_000_000 * 1_000 / 1_001_000 out.
+	assert_eq!(
+		compute_swap_step(one, far, 1_000_000, 1_000, Permill::zero(), true),
+		Some((FixedU128::from_inner(999_000_999_000_999_001), 1_000, 999, 0))
+	);
+	let (_, amount_in, _, fee) = compute_swap_step(one, far, 1_000_000, 1_000, Permill::from_parts(3_000), true).unwrap();
+	assert_eq!((amount_in, fee), (997, 3));
+
+	// Without liquidity the target is reached for free.
+	let target = sqrt_price_at_tick(-10).unwrap();
+	assert_eq!(compute_swap_step(one, target, 0, 1_000, Permill::zero(), true), Some((target, 0, 0, 0)));
+}
+
+#[test]
+fn should_earn_and_collect_concentrated_liquidity_fees() {
+	new_test_ext().execute_with(|| {
+		let provider: AccountId = whitelisted_caller();
+		let trader: AccountId = account("recepient", 0, 1);
+		MultiToken::mint_batch(Origin::signed(provider), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(trader), 0, 10_000);
+		let pool_address: AccountId = Dex::pool_account(0);
+
+		assert_ok!(Dex::create_concentrated_pool(Origin::signed(provider), 0, 1, Permill::from_parts(3_000), 10, 0));
+		assert_ok!(Dex::mint_position(Origin::signed(provider), 0, -1_000, 1_000, 10_000_000, 1_000_000, 1_000_000));
+		let first_reserve = MultiToken::balances(0, pool_address).unwrap();
+		let second_reserve = MultiToken::balances(1, pool_address).unwrap();
+		// Symmetric range around the current price: 10_000_000 * (1 - 1.0001 ^ -500) of each token.
+		assert!(first_reserve.abs_diff(487_682) <= 1 && first_reserve.abs_diff(second_reserve) <= 1);
+
+		assert_ok!(Dex::concentrated_swap(Origin::signed(trader), 0, 0, 10_000, 0));
+		let received = MultiToken::balances(1, trader).unwrap();
+		assert!(received > 9_900 && received < 9_970);
+
+		let first_before = MultiToken::balances(0, provider).unwrap();
+		assert_ok!(Dex::collect(Origin::signed(provider), 0));
+		let fees = MultiToken::balances(0, provider).unwrap() - first_before;
+		assert!(fees == 29 || fees == 30);
+
+		assert_ok!(Dex::burn_position(Origin::signed(provider), 0, 10_000_000, 0, 0));
+		assert!(Positions::<Test>::contains_key(0));
+		assert_ok!(Dex::collect(Origin::signed(provider), 0));
+		assert!(!Positions::<Test>::contains_key(0));
+		assert_eq!(ConcentratedPools::<Test>::get(0).unwrap().liquidity, 0);
+		// Only rounding dust stays in the pool.
+		assert!(MultiToken::balances(0, pool_address).unwrap() <= 3);
+		assert!(MultiToken::balances(1, pool_address).unwrap() <= 3);
+	});
+}
+
+#[test]
+fn should_share_fees_between_ranges_crossed_by_a_swap() {
+	new_test_ext().execute_with(|| {
+		let wide: AccountId = whitelisted_caller();
+		let narrow: AccountId = account("narrow", 0, 2);
+		let trader: AccountId = account("recepient", 0, 1);
+		MultiToken::mint_batch(Origin::signed(wide), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(narrow), 0, 1_000_000);
+		MultiToken::mint(Origin::signed(trader), 1, 100_000);
+
+		assert_ok!(Dex::create_concentrated_pool(Origin::signed(wide), 0, 1, Permill::from_parts(3_000), 10, 0));
+		assert_ok!(Dex::mint_position(Origin::signed(wide), 0, -1_000, 1_000, 10_000_000, 1_000_000, 1_000_000));
+		// Above the current price only the first token is needed.
+		assert_ok!(Dex::mint_position(Origin::signed(narrow), 0, 100, 200, 10_000_000, 1_000_000, 0));
+
+		// Only crossing into the narrow range at tick 100 is charged.
+		let info = Dex::concentrated_swap(Origin::signed(trader), 0, 1, 100_000, 0).unwrap();
+		assert_eq!(info.actual_weight, Some(Dex::concentrated_swap_weight(1)));
+		let pool = ConcentratedPools::<Test>::get(0).unwrap();
+		assert!(pool.tick > 100 && pool.tick < 200);
+		assert_eq!(pool.liquidity, 20_000_000);
+
+		let narrow_before = MultiToken::balances(1, narrow).unwrap_or(0);
+		assert_ok!(Dex::collect(Origin::signed(narrow), 1));
+		let narrow_fees = MultiToken::balances(1, narrow).unwrap() - narrow_before;
+		let wide_before = MultiToken::balances(1, wide).unwrap();
+		assert_ok!(Dex::collect(Origin::signed(wide), 0));
+		let wide_fees = MultiToken::balances(1, wide).unwrap() - wide_before;
+		// The wide range earned alone until the price entered the narrow one, then split evenly.
+		assert!(narrow_fees > 0 && wide_fees > narrow_fees);
+		assert!(wide_fees + narrow_fees <= 301 && wide_fees + narrow_fees >= 296);
+	});
+}
+
+#[test]
+fn should_revert_invalid_positions() {
+	new_test_ext().execute_with(|| {
+		let provider: AccountId = whitelisted_caller();
+		let stranger: AccountId = account("recepient", 0, 1);
+		MultiToken::mint_batch(Origin::signed(provider), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		assert_ok!(Dex::create_concentrated_pool(Origin::signed(provider), 0, 1, Permill::from_parts(3_000), 10, 0));
+
+		assert_noop!(
+			Dex::mint_position(Origin::signed(provider), 0, -15, 10, 1_000, 1_000, 1_000),
+			Error::<Test>::InvalidTick
+		);
+		assert_noop!(
+			Dex::mint_position(Origin::signed(provider), 0, 10, -10, 1_000, 1_000, 1_000),
+			Error::<Test>::InvalidTick
+		);
+		assert_noop!(
+			Dex::mint_position(Origin::signed(provider), 0, -10, 10, 10_000_000, 1, 1),
+			Error::<Test>::SlippageExceeded
+		);
+		assert_ok!(Dex::mint_position(Origin::signed(provider), 0, -10, 10, 1_000_000, 1_000, 1_000));
+		assert_noop!(Dex::burn_position(Origin::signed(stranger), 0, 1, 0, 0), Error::<Test>::NotPositionOwner);
+		assert_noop!(Dex::burn_position(Origin::signed(provider), 0, 1_000_001, 0, 0), Error::<Test>::NoLiquidity);
+		assert_noop!(Dex::collect(Origin::signed(provider), 1), Error::<Test>::NoPosition);
+	});
+}
+
+#[test]
+fn should_repay_flash_loan_with_fee() {
+	new_test_ext().execute_with(|| {
+		let borrower: AccountId = account("recepient", 0, 1);
+		let pool_address: AccountId = Dex::pool_account(0);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(borrower), 0, 10_000);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000_000, 1, 1_000_000));
+
+		// (1_003_092 * 100 - 103_092 * 3) * 1_000_000 * 100 < 1_000_000 ^ 2 * 100 ^ 2
+		assert_noop!(
+			Dex::flash_loan(Origin::signed(borrower), 0, 100_000, 0, (103_092u64, 0u64, false).encode()),
+			Error::<Test>::FlashLoanNotRepaid
+		);
+		assert_ok!(Dex::flash_loan(Origin::signed(borrower), 0, 100_000, 0, (103_093u64, 0u64, false).encode()));
+		assert_eq!(MultiToken::balances(0, pool_address), Some(1_003_093));
+		assert_eq!(MultiToken::balances(0, borrower), Some(10_000 - 3_093));
+	});
+}
+
+#[test]
+fn should_repay_flash_swap_in_other_token() {
+	new_test_ext().execute_with(|| {
+		let borrower: AccountId = account("recepient", 0, 1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(borrower), 0, 20_000);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000_000, 1, 1_000_000));
+
+		assert_noop!(
+			Dex::flash_loan(Origin::signed(borrower), 0, 0, 10_000, (10_413u64, 0u64, false).encode()),
+			Error::<Test>::FlashLoanNotRepaid
+		);
+		assert_ok!(Dex::flash_loan(Origin::signed(borrower), 0, 0, 10_000, (10_414u64, 0u64, false).encode()));
+		assert_eq!(MultiToken::balances(1, borrower), Some(10_000));
+	});
+}
+
+#[test]
+fn should_lock_pool_during_flash_loan() {
+	new_test_ext().execute_with(|| {
+		let borrower: AccountId = account("recepient", 0, 1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(borrower), 0, 10_000);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000_000, 1, 1_000_000));
+
+		assert_noop!(
+			Dex::flash_loan(Origin::signed(borrower), 0, 100_000, 0, (103_093u64, 0u64, true).encode()),
+			Error::<Test>::PoolLocked
+		);
+		assert_noop!(
+			Dex::flash_loan(Origin::signed(borrower), 0, 1_000_000, 0, (0u64, 0u64, false).encode()),
+			Error::<Test>::NoLiquidity
+		);
+	});
+}
+
+#[test]
+fn should_mint_geometric_mean_and_lock_minimum_liquidity() {
+	new_test_ext().execute_with(|| {
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [4_000_000, 1_000_000].to_vec());
+		assert_noop!(
+			Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000, 1, 1_000),
+			Error::<Test>::InsufficientLiquidity
+		);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 4_000_000, 1, 1_000_000));
+		assert_eq!(LPTokenTotalSupply::<Test>::get(0), 2_000_000);
+		assert_eq!(Dex::balance_of(0, whitelisted_caller::<AccountId>()), 2_000_000 - MINIMUM_LIQUIDITY);
+	});
+}
+
+#[test]
+fn should_make_first_depositor_inflation_attack_unprofitable() {
+	new_test_ext().execute_with(|| {
+		let attacker: AccountId = whitelisted_caller();
+		let victim: AccountId = account("recepient", 0, 1);
+		let pool_address: AccountId = Dex::pool_account(0);
+		MultiToken::mint_batch(Origin::signed(attacker), [0, 1].to_vec(), [1_001_001, 1_001_001].to_vec());
+		MultiToken::mint_batch(Origin::signed(victim), [0, 1].to_vec(), [500_000, 500_000].to_vec());
+
+		// The attacker keeps a single share and donates to the pool to inflate its price.
+		assert_ok!(Dex::init(Origin::signed(attacker), 0, 1_001, 1, 1_001));
+		assert_eq!(Dex::balance_of(0, attacker), 1);
+		assert_ok!(MultiToken::transfer_to(Origin::signed(attacker), pool_address, 0, 1_000_000));
+		assert_ok!(MultiToken::transfer_to(Origin::signed(attacker), pool_address, 1, 1_000_000));
+
+		// A deposit worth less than a share is refused instead of being swallowed.
+		assert_noop!(Dex::deposit(Origin::signed(victim), 0, 500, 500), Error::<Test>::InsufficientLiquidity);
+
+		assert_ok!(Dex::deposit(Origin::signed(victim), 0, 500_000, 500_000));
+		assert_ok!(Dex::withdraw(Origin::signed(attacker), 0));
+		assert_ok!(Dex::withdraw(Origin::signed(victim), 0));
+
+		// Most of the donation went to the locked shares: the attacker lost it, the victim
+		// lost less than a share to rounding.
+		assert_eq!(MultiToken::balances(0, attacker), Some(1_000));
+		assert_eq!(MultiToken::balances(1, attacker), Some(1_000));
+		assert_eq!(MultiToken::balances(0, victim), Some(499_333));
+		assert_eq!(MultiToken::balances(1, victim), Some(499_333));
+	});
+}
+
+#[test]
+fn should_never_return_more_for_partial_withdrawals() {
+	let setup = || {
+		let recepient: AccountId = account("recepient", 0, 1);
+		let trader: AccountId = account("recepient2", 0, 1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [9_000_000, 7_000_003].to_vec());
+		MultiToken::mint_batch(Origin::signed(recepient), [0, 1].to_vec(), [1_000_001, 1_000_001].to_vec());
+		MultiToken::mint(Origin::signed(trader), 0, 123_457);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 9_000_000, 1, 7_000_003));
+		assert_ok!(Dex::deposit(Origin::signed(recepient), 0, 1_000_001, 1_000_001));
+		assert_ok!(Dex::swap_token(Origin::signed(trader), 0, 0, 123_457));
+		recepient
+	};
+
+	let full = new_test_ext().execute_with(|| {
+		let recepient = setup();
+		assert_ok!(Dex::withdraw(Origin::signed(recepient), 0));
+		(MultiToken::balances(0, recepient).unwrap(), MultiToken::balances(1, recepient).unwrap())
+	});
+	let partial = new_test_ext().execute_with(|| {
+		let recepient = setup();
+		let chunk = Dex::balance_of(0, recepient) / 7;
+		while Dex::balance_of(0, recepient) > 0 {
+			let lp_amount = chunk.min(Dex::balance_of(0, recepient));
+			assert_ok!(Dex::remove_liquidity(Origin::signed(recepient), 0, lp_amount, 0, 0, recepient));
+		}
+		(MultiToken::balances(0, recepient).unwrap(), MultiToken::balances(1, recepient).unwrap())
+	});
+	assert!(partial.0 <= full.0 && partial.1 <= full.1);
+	assert!(partial.0 + 8 >= full.0 && partial.1 + 8 >= full.1);
+}
+
+#[test]
+fn should_remove_part_of_liquidity() {
+	new_test_ext().execute_with(|| {
+		let creator: AccountId = whitelisted_caller();
+		MultiToken::mint_batch(Origin::signed(creator), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		assert_ok!(Dex::init(Origin::signed(creator), 0, 1_000_000, 1, 1_000_000));
+
+		assert_noop!(
+			Dex::remove_liquidity(Origin::signed(creator), 0, 499_500, 499_501, 0, creator),
+			Error::<Test>::SlippageExceeded
+		);
+		assert_ok!(Dex::remove_liquidity(Origin::signed(creator), 0, 499_500, 499_500, 499_500, creator));
+		assert_eq!(Dex::balance_of(0, creator), 499_500);
+		assert_eq!(MultiToken::balances(0, creator), Some(499_500));
+		assert_noop!(Dex::remove_liquidity(Origin::signed(creator), 0, 499_501, 0, 0, creator), Error::<Test>::NoLiquidity);
+	});
+}
+
+#[test]
+fn should_add_liquidity_on_the_limiting_side() {
+	new_test_ext().execute_with(|| {
+		let recepient: AccountId = account("recepient", 0, 1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 2_000_000].to_vec());
+		MultiToken::mint_batch(Origin::signed(recepient), [0, 1].to_vec(), [100_000, 100_000].to_vec());
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000_000, 1, 2_000_000));
+
+		// The second token limits the deposit to 50_000 of the first one.
+		assert_noop!(
+			Dex::add_liquidity(Origin::signed(recepient), 0, 100_000, 100_000, 60_000, 0, recepient),
+			Error::<Test>::WrongRatio
+		);
+		assert_ok!(Dex::add_liquidity(Origin::signed(recepient), 0, 100_000, 100_000, 50_000, 100_000, recepient));
+		assert_eq!(MultiToken::balances(0, recepient), Some(50_000));
+		assert_eq!(MultiToken::balances(1, recepient), Some(0));
+		// 1_414_213 total supply * 5%
+		assert_eq!(Dex::balance_of(0, recepient), 70_710);
+
+		// Here the first token limits it and only 20_000 of the second one is taken.
+		let second_recepient: AccountId = account("recepient2", 0, 1);
+		MultiToken::mint_batch(Origin::signed(second_recepient), [0, 1].to_vec(), [10_000, 100_000].to_vec());
+		assert_ok!(Dex::add_liquidity(Origin::signed(second_recepient), 0, 10_000, 100_000, 0, 0, second_recepient));
+		assert_eq!(MultiToken::balances(0, second_recepient), Some(0));
+		assert_eq!(MultiToken::balances(1, second_recepient), Some(80_000));
+	});
+}
+
+#[test]
+fn should_zap_single_token_into_pool() {
+	new_test_ext().execute_with(|| {
+		let recepient: AccountId = account("recepient", 0, 1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(recepient), 0, 100_000);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000_000, 1, 1_000_000));
+
+		assert_noop!(Dex::zap_in(Origin::signed(recepient), 0, 0, 100_000, 48_028), Error::<Test>::SlippageExceeded);
+		// Swaps 49_588 for 45_827 of the second token and deposits it with 50_409 of the first.
+		assert_ok!(Dex::zap_in(Origin::signed(recepient), 0, 0, 100_000, 48_027));
+		assert_eq!(Dex::balance_of(0, recepient), 48_027);
+		assert_eq!(MultiToken::balances(0, recepient), Some(3));
+		assert_eq!(MultiToken::balances(1, recepient), Some(0));
+		assert_noop!(Dex::zap_in(Origin::signed(recepient), 0, 2, 3, 0), Error::<Test>::UnknownToken);
+	});
+}
+
+#[test]
+fn should_route_outputs_to_recipient() {
+	new_test_ext().execute_with(|| {
+		let payer: AccountId = account("recepient", 0, 1);
+		let merchant: AccountId = account("recepient2", 0, 1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint_batch(Origin::signed(payer), [0, 1].to_vec(), [200_000, 100_000].to_vec());
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000_000, 1, 1_000_000));
+
+		assert_ok!(Dex::swap_to(Origin::signed(payer), 0, 0, 100_000, merchant));
+		assert_eq!(MultiToken::balances(0, payer), Some(100_000));
+		assert_eq!(MultiToken::balances(1, payer), Some(100_000));
+		assert_eq!(MultiToken::balances(1, merchant), Some(1_000_000 * 100_000 / 1_100_000 * 97 / 100));
+
+		let liquidity_before = Dex::balance_of(0, whitelisted_caller::<AccountId>());
+		assert_ok!(Dex::add_liquidity(Origin::signed(payer), 0, 100_000, 100_000, 0, 0, merchant));
+		assert_eq!(Dex::balance_of(0, payer), 0);
+		let liquidity = Dex::balance_of(0, merchant);
+		assert!(liquidity > 0);
+		assert_eq!(Dex::balance_of(0, whitelisted_caller::<AccountId>()), liquidity_before);
+
+		// Shares are burned from the signer, the reserves go to the recipient.
+		assert_noop!(
+			Dex::remove_liquidity(Origin::signed(payer), 0, liquidity, 0, 0, merchant),
+			Error::<Test>::NoLiquidity
+		);
+		let merchant_first_before = MultiToken::balances(0, merchant).unwrap_or(0);
+		assert_ok!(Dex::remove_liquidity(Origin::signed(merchant), 0, liquidity, 0, 0, payer));
+		assert_eq!(Dex::balance_of(0, merchant), 0);
+		assert_eq!(MultiToken::balances(0, merchant).unwrap_or(0), merchant_first_before);
+		assert!(MultiToken::balances(0, payer).unwrap() > 0);
+	});
+}
+
+#[test]
+fn should_reserve_deposit_until_pool_is_destroyed() {
+	new_test_ext().execute_with(|| {
+		let creator: AccountId = whitelisted_caller();
+		let provider: AccountId = account("recepient", 0, 1);
+		let pool_address: AccountId = Dex::pool_account(0);
+		MultiToken::mint_batch(Origin::signed(creator), [0, 1].to_vec(), [2_000_000, 2_000_000].to_vec());
+		MultiToken::mint_batch(Origin::signed(provider), [0, 1].to_vec(), [100_000, 100_000].to_vec());
+
+		assert_ok!(Dex::init(Origin::signed(creator), 0, 1_000_000, 1, 1_000_000));
+		assert_eq!(Balances::reserved_balance(creator), 10);
+		assert_eq!(Balances::free_balance(creator), 990);
+		assert_ok!(Dex::deposit(Origin::signed(provider), 0, 100_000, 100_000));
+
+		assert_ok!(Dex::withdraw(Origin::signed(creator), 0));
+		assert_noop!(Dex::destroy_pool(Origin::signed(creator), 0), Error::<Test>::PoolNotEmpty);
+		assert_ok!(Dex::withdraw(Origin::signed(provider), 0));
+		assert_noop!(Dex::destroy_pool(Origin::signed(provider), 0), Error::<Test>::NotPoolCreator);
+
+		assert_ok!(Dex::destroy_pool(Origin::signed(creator), 0));
+		assert_eq!(Balances::reserved_balance(creator), 0);
+		assert_eq!(Balances::free_balance(creator), 1_000);
+		assert!(!Pool::<Test>::contains_key(0));
+		assert_eq!(PairPool::<Test>::get((0, 1)), None);
+		assert_eq!(LPTokenTotalSupply::<Test>::get(0), 0);
+		// The reserves of the locked minimum liquidity went back to the creator.
+		assert_eq!(MultiToken::balances(0, pool_address), Some(0));
+		assert_eq!(MultiToken::balances(0, creator), Some(2_000_000));
+		assert_noop!(Dex::destroy_pool(Origin::signed(creator), 0), Error::<Test>::NoPool);
+
+		// The pair can be listed again.
+		assert_ok!(Dex::init(Origin::signed(creator), 0, 1_000_000, 1, 1_000_000));
+		assert_eq!(PairPool::<Test>::get((0, 1)), Some(1));
+	});
+}
+
+#[test]
+fn should_refund_orders_and_intents_of_destroyed_pool() {
+	new_test_ext().execute_with(|| {
+		let creator: AccountId = whitelisted_caller();
+		let trader: AccountId = account("recepient", 0, 1);
+		System::set_block_number(1);
+		MultiToken::mint_batch(Origin::signed(creator), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint_batch(Origin::signed(trader), [0, 1].to_vec(), [10_000, 10_000].to_vec());
+		assert_ok!(Dex::init(Origin::signed(creator), 0, 1_000_000, 1, 1_000_000));
+
+		assert_ok!(Dex::place_limit_order(Origin::signed(trader), 0, 0, 10_000, 20_000, 100));
+		assert_ok!(Dex::set_batch_auction(Origin::root(), 0, true));
+		assert_ok!(Dex::submit_swap_intent(Origin::signed(trader), 0, 1, 10_000, 0));
+		assert_ok!(Dex::pause_pool(Origin::root(), 0));
+		assert_ok!(Dex::withdraw(Origin::signed(creator), 0));
+
+		assert_ok!(Dex::destroy_pool(Origin::signed(creator), 0));
+		assert_eq!(MultiToken::balances(0, trader), Some(10_000));
+		assert_eq!(MultiToken::balances(1, trader), Some(10_000));
+		assert_eq!(MultiToken::balances(0, Dex::escrow_account()), Some(0));
+		assert_eq!(MultiToken::balances(1, Dex::escrow_account()), Some(0));
+		assert!(!Orders::<Test>::contains_key(0));
+		assert!(!OrderBooks::<Test>::contains_key(0));
+		assert!(!crate::SwapIntents::<Test>::contains_key(0));
+		assert!(!crate::BatchAuctionPools::<Test>::contains_key(0));
+		assert!(!crate::PausedPools::<Test>::contains_key(0));
+		assert!(crate::BlockStartPrices::<Test>::iter_prefix(0).next().is_none());
+
+		// Relisting the pair gives a pool that is neither paused nor in batch mode.
+		MultiToken::mint_batch(Origin::signed(creator), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		assert_ok!(Dex::init(Origin::signed(creator), 0, 1_000_000, 1, 1_000_000));
+		assert_ok!(Dex::swap_token(Origin::signed(trader), 1, 0, 1_000));
+	});
+}
+
+#[test]
+fn should_require_pool_deposit() {
+	new_test_ext().execute_with(|| {
+		let poor: AccountId = account("poor", 0, 3);
+		MultiToken::mint_batch(Origin::signed(poor), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		assert_noop!(
+			Dex::init(Origin::signed(poor), 0, 1_000_000, 1, 1_000_000),
+			pallet_balances::Error::<Test>::InsufficientBalance
+		);
+	});
+}
+
+#[test]
+fn should_destroy_concentrated_pool_once_positions_are_closed() {
+	new_test_ext().execute_with(|| {
+		let provider: AccountId = whitelisted_caller();
+		MultiToken::mint_batch(Origin::signed(provider), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		assert_ok!(Dex::create_concentrated_pool(Origin::signed(provider), 0, 1, Permill::from_parts(3_000), 10, 0));
+		assert_ok!(Dex::mint_position(Origin::signed(provider), 0, -100, 100, 1_000_000, 1_000_000, 1_000_000));
+
+		assert_ok!(Dex::burn_position(Origin::signed(provider), 0, 1_000_000, 0, 0));
+		assert_noop!(Dex::destroy_pool(Origin::signed(provider), 0), Error::<Test>::PoolNotEmpty);
+		assert_ok!(Dex::collect(Origin::signed(provider), 0));
+		assert_ok!(Dex::destroy_pool(Origin::signed(provider), 0));
+		assert!(!ConcentratedPools::<Test>::contains_key(0));
+		assert_eq!(MultiToken::balances(0, provider), Some(1_000_000));
+		assert_eq!(MultiToken::balances(1, provider), Some(1_000_000));
+	});
+}
+
+#[test]
+fn should_limit_price_move_within_a_block() {
+	new_test_ext().execute_with(|| {
+		let trader: AccountId = account("recepient", 0, 1);
+		MaxPriceMove::set(Permill::from_percent(10));
+		System::set_block_number(1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(trader), 0, 200_000);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000_000, 1, 1_000_000));
+
+		assert_noop!(Dex::swap_token(Origin::signed(trader), 0, 0, 100_000), Error::<Test>::PriceMoveTooLarge);
+		// Moves add up against the price the block started with.
+		assert_ok!(Dex::swap_token(Origin::signed(trader), 0, 0, 30_000));
+		assert_noop!(Dex::swap_token(Origin::signed(trader), 0, 0, 30_000), Error::<Test>::PriceMoveTooLarge);
+
+		System::set_block_number(2);
+		assert_ok!(Dex::swap_token(Origin::signed(trader), 0, 0, 30_000));
+		assert_eq!(MultiToken::balances(0, trader), Some(140_000));
+	});
+}
+
+#[test]
+fn should_limit_price_move_of_flash_swaps_and_batches() {
+	new_test_ext().execute_with(|| {
+		let trader: AccountId = account("recepient", 0, 1);
+		MaxPriceMove::set(Permill::from_percent(1));
+		System::set_block_number(1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(trader), 0, 30_000);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000_000, 1, 1_000_000));
+
+		// Repaying a loan of the second token in the first one moves the price by 2%.
+		assert_noop!(
+			Dex::flash_loan(Origin::signed(trader), 0, 0, 10_000, (10_414u64, 0u64, false).encode()),
+			Error::<Test>::PriceMoveTooLarge
+		);
+
+		assert_ok!(Dex::set_batch_auction(Origin::root(), 0, true));
+		assert_ok!(Dex::submit_swap_intent(Origin::signed(trader), 0, 0, 10_000, 0));
+		System::set_block_number(2);
+		Dex::on_initialize(2);
+		assert_eq!(MultiToken::balances(0, trader), Some(30_000));
+		assert_eq!(MultiToken::balances(1, trader), None);
+		assert_eq!(MultiToken::balances(0, Dex::escrow_account()), Some(0));
+	});
+}
+
+#[test]
+fn should_limit_price_move_of_weighted_pools() {
+	new_test_ext().execute_with(|| {
+		let trader: AccountId = account("recepient", 0, 1);
+		MaxPriceMove::set(Permill::from_percent(1));
+		System::set_block_number(1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint_batch(Origin::signed(trader), [0, 1].to_vec(), [20_000, 20_000].to_vec());
+		assert_ok!(Dex::create_weighted_pool(
+			Origin::signed(whitelisted_caller()),
+			[0, 1].to_vec(),
+			[50, 50].to_vec(),
+			[1_000_000, 1_000_000].to_vec(),
+			Permill::zero()
+		));
+
+		// Selling 10_000 of the first token lowers its price by 2%.
+		assert_noop!(
+			Dex::weighted_swap(Origin::signed(trader), 0, 0, 1, 10_000, 0),
+			Error::<Test>::PriceMoveTooLarge
+		);
+		assert_ok!(Dex::weighted_swap(Origin::signed(trader), 0, 0, 1, 4_000, 0));
+		assert_noop!(
+			Dex::weighted_swap(Origin::signed(trader), 0, 0, 1, 4_000, 0),
+			Error::<Test>::PriceMoveTooLarge
+		);
+		// Swaps back towards the price at the start of the block are checked against it as well.
+		assert_ok!(Dex::weighted_swap(Origin::signed(trader), 0, 1, 0, 8_000, 0));
+
+		System::set_block_number(2);
+		assert_ok!(Dex::weighted_swap(Origin::signed(trader), 0, 1, 0, 4_000, 0));
+	});
+}
+
+#[test]
+fn should_pause_and_resume_pool() {
+	new_test_ext().execute_with(|| {
+		let provider: AccountId = whitelisted_caller();
+		let trader: AccountId = account("recepient", 0, 1);
+		MultiToken::mint_batch(Origin::signed(provider), [0, 1].to_vec(), [1_100_000, 1_100_000].to_vec());
+		MultiToken::mint(Origin::signed(trader), 0, 10_000);
+		assert_ok!(Dex::init(Origin::signed(provider), 0, 1_000_000, 1, 1_000_000));
+
+		assert_noop!(Dex::pause_pool(Origin::signed(provider), 0), sp_runtime::DispatchError::BadOrigin);
+		assert_noop!(Dex::pause_pool(Origin::root(), 1), Error::<Test>::NoPool);
+		assert_ok!(Dex::pause_pool(Origin::root(), 0));
+
+		assert_noop!(Dex::swap_token(Origin::signed(trader), 0, 0, 10_000), Error::<Test>::PoolPaused);
+		assert_noop!(Dex::deposit(Origin::signed(provider), 0, 100_000, 100_000), Error::<Test>::PoolPaused);
+		assert_noop!(Dex::zap_in(Origin::signed(trader), 0, 0, 10_000, 0), Error::<Test>::PoolPaused);
+		// Liquidity can always be taken out.
+		let liquidity = Dex::balance_of(0, provider);
+		assert_ok!(Dex::remove_liquidity(Origin::signed(provider), 0, liquidity / 2, 0, 0, provider));
+
+		assert_noop!(Dex::resume_pool(Origin::signed(provider), 0), sp_runtime::DispatchError::BadOrigin);
+		assert_ok!(Dex::resume_pool(Origin::root(), 0));
+		assert_noop!(Dex::resume_pool(Origin::root(), 0), Error::<Test>::NoPool);
+		assert_ok!(Dex::swap_token(Origin::signed(trader), 0, 0, 10_000));
+	});
+}
+
+#[test]
+fn should_fill_limit_order_once_price_is_reached() {
+	new_test_ext().execute_with(|| {
+		let owner: AccountId = account("recepient", 0, 1);
+		let trader: AccountId = account("recepient2", 0, 1);
+		System::set_block_number(1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(owner), 0, 10_000);
+		MultiToken::mint(Origin::signed(trader), 1, 100_000);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000_000, 1, 1_000_000));
+
+		// Sell 10_000 of the first token at a price of at least one.
+		assert_ok!(Dex::place_limit_order(Origin::signed(owner), 0, 0, 10_000, 10_000, 100));
+		assert_eq!(MultiToken::balances(0, owner), Some(0));
+		assert_eq!(MultiToken::balances(0, Dex::escrow_account()), Some(10_000));
+
+		Dex::on_idle(1, u64::MAX);
+		assert!(Orders::<Test>::contains_key(0));
+		assert_eq!(MultiToken::balances(0, Dex::escrow_account()), Some(10_000));
+
+		assert_ok!(Dex::swap_token(Origin::signed(trader), 0, 1, 100_000));
+		System::set_block_number(2);
+		Dex::on_idle(2, u64::MAX);
+		assert!(!Orders::<Test>::contains_key(0));
+		assert!(!OrderBooks::<Test>::contains_key(0));
+		assert_eq!(MultiToken::balances(0, Dex::escrow_account()), Some(0));
+		assert_eq!(MultiToken::balances(1, owner), Some(1_100_000 * 10_000 / 921_819 * 97 / 100));
+	});
+}
+
+#[test]
+fn should_walk_order_books_round_from_the_cursor() {
+	new_test_ext().execute_with(|| {
+		let owner: AccountId = account("recepient", 0, 1);
+		System::set_block_number(1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1, 2, 3].to_vec(), [1_000_000; 4].to_vec());
+		MultiToken::mint_batch(Origin::signed(owner), [0, 2].to_vec(), [10_000, 10_000].to_vec());
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000_000, 1, 1_000_000));
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 2, 1_000_000, 3, 1_000_000));
+		assert_ok!(Dex::place_limit_order(Origin::signed(owner), 0, 0, 10_000, 1, 100));
+		assert_ok!(Dex::place_limit_order(Origin::signed(owner), 1, 2, 10_000, 1, 100));
+
+		// Whichever book comes first in storage was walked last, so the other one goes first.
+		let (first, second) = if OrderBooks::<Test>::hashed_key_for(0) < OrderBooks::<Test>::hashed_key_for(1) {
+			(0, 1)
+		} else {
+			(1, 0)
+		};
+		crate::LimitOrderCursor::<Test>::put(first);
+		Dex::on_idle(1, u64::MAX);
+		let filled: Vec<_> = System::events()
+			.into_iter()
+			.filter_map(|record| match record.event {
+				Event::Dex(crate::Event::LimitOrderFilled { order_id, .. }) => Some(order_id),
+				_ => None,
+			})
+			.collect();
+		assert_eq!(filled, [second, first].to_vec());
+		assert_eq!(crate::LimitOrderCursor::<Test>::get(), Some(first));
+	});
+}
+
+#[test]
+fn should_cancel_and_expire_limit_orders() {
+	new_test_ext().execute_with(|| {
+		let owner: AccountId = account("recepient", 0, 1);
+		let other: AccountId = account("recepient2", 0, 1);
+		System::set_block_number(1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(owner), 0, 50_000);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000_000, 1, 1_000_000));
+
+		assert_noop!(Dex::place_limit_order(Origin::signed(owner), 0, 0, 10_000, 20_000, 1), Error::<Test>::OrderExpired);
+		assert_noop!(Dex::place_limit_order(Origin::signed(owner), 0, 2, 10_000, 20_000, 5), Error::<Test>::UnknownToken);
+		assert_noop!(Dex::place_limit_order(Origin::signed(owner), 0, 0, 0, 20_000, 5), Error::<Test>::ZeroAmount);
+		assert_noop!(Dex::place_limit_order(Origin::signed(owner), 0, 0, 999, 2_000, 5), Error::<Test>::OrderTooSmall);
+		assert_noop!(
+			Dex::place_limit_order(Origin::signed(owner), 0, 0, 10_000, 20_000, 102),
+			Error::<Test>::OrderLifetimeTooLong
+		);
+		for _ in 0..4 {
+			assert_ok!(Dex::place_limit_order(Origin::signed(owner), 0, 0, 10_000, 20_000, 5));
+		}
+		assert_noop!(Dex::place_limit_order(Origin::signed(owner), 0, 0, 10_000, 20_000, 5), Error::<Test>::TooManyOrders);
+		assert_eq!(MultiToken::balances(0, owner), Some(10_000));
+
+		assert_noop!(Dex::cancel_limit_order(Origin::signed(other), 1), Error::<Test>::NotOrderOwner);
+		assert_ok!(Dex::cancel_limit_order(Origin::signed(owner), 1));
+		assert_noop!(Dex::cancel_limit_order(Origin::signed(owner), 1), Error::<Test>::NoOrder);
+		assert_eq!(MultiToken::balances(0, owner), Some(20_000));
+		assert_eq!(OrderBooks::<Test>::get(0).into_inner(), [0, 2, 3].to_vec());
+
+		System::set_block_number(4);
+		Dex::on_idle(4, u64::MAX);
+		assert_eq!(OrderBooks::<Test>::get(0).len(), 3);
+		System::set_block_number(5);
+		Dex::on_idle(5, u64::MAX);
+		assert!(!OrderBooks::<Test>::contains_key(0));
+		assert_eq!(MultiToken::balances(0, owner), Some(50_000));
+		assert_eq!(MultiToken::balances(0, Dex::escrow_account()), Some(0));
+	});
+}
+
+#[test]
+fn should_refund_escrow_of_frozen_asset() {
+	new_test_ext().execute_with(|| {
+		let owner: AccountId = account("recepient", 0, 1);
+		System::set_block_number(1);
+		assert_ok!(MultiToken::create_asset(Origin::signed(whitelisted_caller()), 105));
+		assert_ok!(MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [105, 1].to_vec(), [1_010_000, 1_000_000].to_vec()));
+		assert_ok!(MultiToken::transfer_to(Origin::signed(whitelisted_caller()), owner, 105, 10_000));
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 105, 1_000_000, 1, 1_000_000));
+		assert_ok!(Dex::place_limit_order(Origin::signed(owner), 0, 105, 10_000, 20_000, 5));
+
+		assert_ok!(MultiToken::freeze_asset(Origin::signed(whitelisted_caller()), 105));
+		assert_ok!(Dex::cancel_limit_order(Origin::signed(owner), 0));
+		assert_eq!(MultiToken::balances(105, owner), Some(10_000));
+		assert_noop!(
+			MultiToken::transfer_to(Origin::signed(owner), whitelisted_caller(), 105, 1),
+			pallet_multi_token::Error::<Test>::AssetFrozen
+		);
+	});
+}
+
+#[test]
+fn should_clear_batch_at_uniform_price() {
+	// Net flow into the pool in the first token.
+	let (price, amounts_out) = clear_batch(1_000_000, 1_000_000, 3, &[(true, 10_000), (false, 5_000)]).unwrap();
+	assert_eq!(amounts_out, [9_653, 5_179].to_vec());
+	assert_eq!(price, FixedU128::from_rational(9_653, 10_000));
+	// Same in the other direction.
+	let (price, amounts_out) = clear_batch(1_000_000, 1_000_000, 3, &[(false, 10_000), (true, 5_000)]).unwrap();
+	assert_eq!(amounts_out, [9_653, 5_179].to_vec());
+	assert_eq!(price, FixedU128::from_rational(9_653, 10_000).reciprocal().unwrap());
+	// Opposite intents inside the spread of the pool are matched with each other only.
+	let (price, amounts_out) = clear_batch(1_000_000, 1_000_000, 3, &[(true, 10_000), (false, 10_000)]).unwrap();
+	assert_eq!(amounts_out, [10_000, 10_000].to_vec());
+	assert_eq!(price, FixedU128::one());
+	assert!(clear_batch(1_000_000, 1_000_000, 3, &[]).is_none());
+}
+
+#[test]
+fn should_settle_swap_intents_in_next_block() {
+	new_test_ext().execute_with(|| {
+		let (first_seller, second_seller, greedy_seller): (AccountId, AccountId, AccountId) =
+			(account("recepient", 0, 1), account("recepient2", 0, 1), account("recepient3", 0, 1));
+		let pool_address: AccountId = Dex::pool_account(0);
+		System::set_block_number(1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(first_seller), 0, 10_000);
+		MultiToken::mint(Origin::signed(second_seller), 1, 5_000);
+		MultiToken::mint(Origin::signed(greedy_seller), 0, 10_000);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000_000, 1, 1_000_000));
+
+		assert_noop!(Dex::submit_swap_intent(Origin::signed(first_seller), 0, 0, 10_000, 0), Error::<Test>::NotBatchAuction);
+		assert_noop!(Dex::set_batch_auction(Origin::signed(first_seller), 0, true), sp_runtime::DispatchError::BadOrigin);
+		assert_ok!(Dex::set_batch_auction(Origin::root(), 0, true));
+		assert_noop!(Dex::swap_token(Origin::signed(first_seller), 0, 0, 10_000), Error::<Test>::BatchAuctionOnly);
+		assert_noop!(
+			Dex::flash_loan(Origin::signed(first_seller), 0, 10_000, 0, (10_310u64, 0u64, false).encode()),
+			Error::<Test>::BatchAuctionOnly
+		);
+
+		assert_ok!(Dex::submit_swap_intent(Origin::signed(first_seller), 0, 0, 10_000, 9_000));
+		assert_ok!(Dex::submit_swap_intent(Origin::signed(second_seller), 0, 1, 5_000, 5_000));
+		// Asks for more than the pool could ever pay, so it is left out of the batch.
+		assert_ok!(Dex::submit_swap_intent(Origin::signed(greedy_seller), 0, 0, 10_000, 20_000));
+		assert_eq!(MultiToken::balances(0, Dex::escrow_account()), Some(20_000));
+
+		System::set_block_number(2);
+		Dex::on_initialize(2);
+		assert_eq!(MultiToken::balances(1, first_seller), Some(9_653));
+		assert_eq!(MultiToken::balances(0, second_seller), Some(5_179));
+		assert_eq!(MultiToken::balances(0, greedy_seller), Some(10_000));
+		assert_eq!(MultiToken::balances(0, Dex::escrow_account()), Some(0));
+		assert_eq!(MultiToken::balances(0, pool_address), Some(1_000_000 + 10_000 - 5_179));
+		assert_eq!(MultiToken::balances(1, pool_address), Some(1_000_000 + 5_000 - 9_653));
+		assert!(!crate::SwapIntents::<Test>::contains_key(0));
+
+		assert_ok!(Dex::set_batch_auction(Origin::root(), 0, false));
+		assert_ok!(Dex::swap_token(Origin::signed(greedy_seller), 0, 0, 10_000));
+	});
+}
+
+#[test]
+fn should_carry_batch_auctions_over_to_next_block() {
+	new_test_ext().execute_with(|| {
+		let seller: AccountId = account("recepient", 0, 1);
+		System::set_block_number(1);
+		MultiToken::mint_batch(
+			Origin::signed(whitelisted_caller()),
+			[0, 1, 2, 3].to_vec(),
+			[1_000_000, 1_000_000, 1_000_000, 1_000_000].to_vec(),
+		);
+		MultiToken::mint_batch(Origin::signed(seller), [0, 2].to_vec(), [20_000, 20_000].to_vec());
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000_000, 1, 1_000_000));
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 2, 1_000_000, 3, 1_000_000));
+		assert_ok!(Dex::set_batch_auction(Origin::root(), 0, true));
+		assert_ok!(Dex::set_batch_auction(Origin::root(), 1, true));
+		assert_ok!(Dex::submit_swap_intent(Origin::signed(seller), 0, 0, 10_000, 0));
+		assert_ok!(Dex::submit_swap_intent(Origin::signed(seller), 1, 2, 10_000, 0));
+
+		// The mock clears one pool a block, the other one waits.
+		System::set_block_number(2);
+		Dex::on_initialize(2);
+		let cleared = crate::AuctionCursor::<Test>::get().unwrap();
+		let waiting = 1 - cleared;
+		assert!(!crate::SwapIntents::<Test>::contains_key(cleared));
+		assert!(crate::SwapIntents::<Test>::contains_key(waiting));
+		assert_eq!(MultiToken::balances(cleared * 2 + 1, seller), Some(9_603));
+
+		// New intents against the pool just cleared don't hold the waiting one up.
+		assert_ok!(Dex::submit_swap_intent(Origin::signed(seller), cleared, cleared * 2, 10_000, 0));
+		System::set_block_number(3);
+		Dex::on_initialize(3);
+		assert_eq!(crate::AuctionCursor::<Test>::get(), Some(waiting));
+		assert!(!crate::SwapIntents::<Test>::contains_key(waiting));
+		assert!(crate::SwapIntents::<Test>::contains_key(cleared));
+		assert_eq!(MultiToken::balances(waiting * 2 + 1, seller), Some(9_603));
+	});
+}
+
+#[test]
+fn should_execute_revealed_swap_commitment() {
+	new_test_ext().execute_with(|| {
+		let trader: AccountId = account("recepient", 0, 1);
+		let salt = [7u8; 32];
+		System::set_block_number(1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(trader), 0, 10_000);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000_000, 1, 1_000_000));
+
+		let commitment = Dex::swap_commitment(0, 0, 10_000, 9_000, salt);
+		assert_ok!(Dex::commit_swap(Origin::signed(trader), commitment));
+		assert_noop!(Dex::commit_swap(Origin::signed(trader), commitment), Error::<Test>::CommitmentExists);
+		assert_eq!(Balances::reserved_balance(trader), CommitmentDeposit::get());
+		assert_noop!(Dex::reveal_swap(Origin::signed(trader), 0, 0, 10_000, 9_000, salt), Error::<Test>::RevealTooEarly);
+
+		System::set_block_number(2);
+		assert_noop!(Dex::reveal_swap(Origin::signed(trader), 0, 0, 10_000, 8_000, salt), Error::<Test>::NoCommitment);
+		assert_noop!(
+			Dex::reveal_swap(Origin::signed(whitelisted_caller()), 0, 0, 10_000, 9_000, salt),
+			Error::<Test>::NoCommitment
+		);
+		assert_ok!(Dex::reveal_swap(Origin::signed(trader), 0, 0, 10_000, 9_000, salt));
+		assert_eq!(MultiToken::balances(1, trader), Some(1_000_000 * 10_000 / 1_010_000 * 97 / 100));
+		assert_eq!(Balances::reserved_balance(trader), 0);
+		assert_eq!(Balances::free_balance(trader), 1_000);
+
+		// Revealed commitments are not forfeited when their period ends.
+		System::set_block_number(5);
+		Dex::on_initialize(5);
+		assert_eq!(Balances::free_balance(trader), 1_000);
+	});
+}
+
+#[test]
+fn should_forfeit_unrevealed_commitment_deposit() {
+	new_test_ext().execute_with(|| {
+		let trader: AccountId = account("recepient", 0, 1);
+		let salt = [7u8; 32];
+		System::set_block_number(1);
+		MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(trader), 0, 10_000);
+		assert_ok!(Dex::init(Origin::signed(whitelisted_caller()), 0, 1_000_000, 1, 1_000_000));
+
+		let commitment = Dex::swap_commitment(0, 0, 10_000, 9_000, salt);
+		assert_ok!(Dex::commit_swap(Origin::signed(trader), commitment));
+		System::set_block_number(1 + RevealPeriod::get() + 1);
+		assert_noop!(
+			Dex::reveal_swap(Origin::signed(trader), 0, 0, 10_000, 9_000, salt),
+			Error::<Test>::CommitmentExpired
+		);
+
+		Dex::on_initialize(1 + RevealPeriod::get() + 1);
+		assert_eq!(Balances::reserved_balance(trader), 0);
+		assert_eq!(Balances::free_balance(trader), 1_000 - CommitmentDeposit::get());
+		assert_noop!(Dex::reveal_swap(Origin::signed(trader), 0, 0, 10_000, 9_000, salt), Error::<Test>::NoCommitment);
+		assert_eq!(MultiToken::balances(0, trader), Some(10_000));
+	});
+}
+
+#[test]
+fn should_share_farm_rewards_pro_rata_to_stake() {
+	new_test_ext().execute_with(|| {
+		let early: AccountId = whitelisted_caller();
+		let late: AccountId = account("recepient", 0, 1);
+		let escrow = Dex::escrow_account();
+		System::set_block_number(1);
+		MultiToken::mint_batch(Origin::signed(early), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint_batch(Origin::signed(late), [0, 1].to_vec(), [999_000, 999_000].to_vec());
+		MultiToken::mint(Origin::signed(FarmAdmin::get()), 2, 1_000);
+		assert_ok!(Dex::init(Origin::signed(early), 0, 1_000_000, 1, 1_000_000));
+		assert_ok!(Dex::deposit(Origin::signed(late), 0, 999_000, 999_000));
+
+		assert_noop!(Dex::create_farm(Origin::signed(early), 0, 2, 100, 2, 12), sp_runtime::DispatchError::BadOrigin);
+		assert_noop!(
+			Dex::create_farm(Origin::signed(FarmAdmin::get()), 0, 2, 100, 12, 2),
+			Error::<Test>::InvalidSchedule
+		);
+		assert_ok!(Dex::create_farm(Origin::signed(FarmAdmin::get()), 0, 2, 100, 2, 12));
+		assert_eq!(MultiToken::balances(2, escrow), Some(1_000));
+
+		assert_ok!(Dex::stake(Origin::signed(early), 0, 999_000));
+		assert_eq!(BalanceOf::<Test>::get(0, early), 0);
+		assert_eq!(BalanceOf::<Test>::get(0, escrow), 999_000);
+
+		// Alone in the farm for blocks 2 to 7, then sharing it equally until 12.
+		System::set_block_number(7);
+		assert_ok!(Dex::stake(Origin::signed(late), 0, 999_000));
+		System::set_block_number(20);
+		assert_eq!(Dex::pending_rewards(0, &early), 749);
+		assert_eq!(Dex::pending_rewards(0, &late), 250);
+
+		assert_ok!(Dex::claim(Origin::signed(early), 0));
+		assert_eq!(MultiToken::balances(2, early), Some(749));
+		assert_eq!(Dex::pending_rewards(0, &early), 0);
+		assert_noop!(Dex::unstake(Origin::signed(late), 0, 999_001), Error::<Test>::NotEnoughStaked);
+		assert_ok!(Dex::unstake(Origin::signed(late), 0, 999_000));
+		assert_eq!(MultiToken::balances(2, late), Some(250));
+		assert_eq!(BalanceOf::<Test>::get(0, late), 999_000);
+		assert_noop!(Dex::claim(Origin::signed(late), 1), Error::<Test>::NoFarm);
+	});
+}
+
+#[test]
+fn should_return_unallocated_farm_rewards_to_funder() {
+	new_test_ext().execute_with(|| {
+		let staker: AccountId = whitelisted_caller();
+		let funder = FarmAdmin::get();
+		System::set_block_number(1);
+		MultiToken::mint_batch(Origin::signed(staker), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		MultiToken::mint(Origin::signed(funder), 2, 1_000);
+		assert_ok!(Dex::init(Origin::signed(staker), 0, 1_000_000, 1, 1_000_000));
+		assert_ok!(Dex::create_farm(Origin::signed(funder), 0, 2, 100, 2, 12));
+
+		// Nothing staked for blocks 2 to 7.
+		System::set_block_number(7);
+		assert_ok!(Dex::stake(Origin::signed(staker), 0, 999_000));
+		assert_noop!(Dex::reclaim_rewards(Origin::signed(funder), 0), Error::<Test>::FarmNotEnded);
+
+		System::set_block_number(12);
+		assert_noop!(Dex::reclaim_rewards(Origin::signed(staker), 0), Error::<Test>::NotFarmFunder);
+		assert_ok!(Dex::reclaim_rewards(Origin::signed(funder), 0));
+		assert_eq!(MultiToken::balances(2, funder), Some(500));
+		assert_ok!(Dex::reclaim_rewards(Origin::signed(funder), 0));
+		assert_eq!(MultiToken::balances(2, funder), Some(500));
+
+		assert_ok!(Dex::unstake(Origin::signed(staker), 0, 999_000));
+		// Rounding of the reward per share leaves a unit behind.
+		assert_eq!(MultiToken::balances(2, staker), Some(499));
+		assert_eq!(MultiToken::balances(2, Dex::escrow_account()), Some(1));
+	});
+}
+
+#[test]
+fn should_trade_lbp_only_in_its_sale_as_weights_shift() {
+	new_test_ext().execute_with(|| {
+		let owner: AccountId = whitelisted_caller();
+		let buyer: AccountId = account("recepient", 0, 1);
+		System::set_block_number(1);
+		MultiToken::mint_batch(Origin::signed(owner), [0, 1].to_vec(), [1_000_000, 100_000].to_vec());
+		MultiToken::mint(Origin::signed(buyer), 1, 10_000);
+
+		assert_noop!(
+			Dex::create_lbp(Origin::signed(owner), [0, 1], [1_000_000, 100_000], [90, 10], [50, 50], 20, 10, Permill::zero()),
+			Error::<Test>::InvalidSchedule
+		);
+		assert_ok!(Dex::create_lbp(
+			Origin::signed(owner),
+			[0, 1],
+			[1_000_000, 100_000],
+			[90, 10],
+			[50, 50],
+			10,
+			20,
+			Permill::zero()
+		));
+		assert_noop!(Dex::lbp_swap(Origin::signed(buyer), 0, 1, 1_000, 0), Error::<Test>::TradingClosed);
+		assert_noop!(Dex::add_lbp_liquidity(Origin::signed(buyer), 0, [1, 1]), Error::<Test>::NotPoolCreator);
+
+		System::set_block_number(10);
+		assert_eq!(Dex::lbp_weights(0), Some([90, 10]));
+		assert_ok!(Dex::lbp_swap(Origin::signed(buyer), 0, 1, 1_000, 0));
+		let early_out = MultiToken::balances(0, buyer).unwrap();
+		assert_noop!(Dex::remove_lbp_liquidity(Origin::signed(owner), 0, [1, 1]), Error::<Test>::SaleInProgress);
+
+		// The weight of the sold token falls, and its price with it.
+		System::set_block_number(15);
+		assert_eq!(Dex::lbp_weights(0), Some([70, 30]));
+		System::set_block_number(19);
+		assert_ok!(Dex::lbp_swap(Origin::signed(buyer), 0, 1, 1_000, 0));
+		let late_out = MultiToken::balances(0, buyer).unwrap() - early_out;
+		assert!(late_out > early_out * 4);
+
+		System::set_block_number(20);
+		assert_eq!(Dex::lbp_weights(0), Some([50, 50]));
+		assert_noop!(Dex::lbp_swap(Origin::signed(buyer), 0, 1, 1_000, 0), Error::<Test>::TradingClosed);
+		assert_ok!(Dex::remove_lbp_liquidity(Origin::signed(owner), 0, [0, 102_000]));
+		assert_eq!(MultiToken::balances(1, owner), Some(102_000));
+	});
+}
+
+#[test]
+fn should_migrate_the_pool_of_version_0() {
+	new_test_ext().execute_with(|| {
+		let provider: AccountId = whitelisted_caller();
+		let trader: AccountId = account("recepient", 0, 1);
+		let pool_address: AccountId = 42;
+		MultiToken::mint_batch(Origin::signed(provider), [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec());
+		assert_ok!(MultiToken::transfer_batch_to(Origin::signed(provider), pool_address, [0, 1].to_vec(), [1_000_000, 1_000_000].to_vec()));
+		MultiToken::mint(Origin::signed(trader), 0, 10_000);
+
+		// The single pool of version 0, as `init` used to store it.
+		StorageVersion::new(0).put::<Dex>();
+		v0::Pool::<Test>::put(pool_address);
+		v0::FirstTokeId::<Test>::put(0);
+		v0::SecondTokeId::<Test>::put(1);
+		v0::LPTokenTotalSupply::<Test>::put(1_000_000);
+		v0::BalanceOf::<Test>::insert(provider, 1_000_000);
+
+		migrate_to_v1::<Test>();
+		assert_eq!(Dex::on_chain_storage_version(), STORAGE_VERSION);
+		assert!(!v0::Pool::<Test>::exists());
+		assert!(!v0::LPTokenTotalSupply::<Test>::exists());
+		assert!(!v0::BalanceOf::<Test>::contains_key(provider));
+		assert_eq!(Pool::<Test>::get(0), Some(pool_address));
+		assert_eq!(PoolIds::<Test>::get(pool_address), Some(0));
+		assert_eq!(PairPool::<Test>::get((0, 1)), Some(0));
+		assert_eq!(LPTokenTotalSupply::<Test>::get(0), 1_000_000);
+		assert_eq!(Dex::balance_of(0, provider), 1_000_000);
+		assert_eq!(NextPoolId::<Test>::get(), 1);
+
+		// The migrated pool trades and pays out its providers.
+		assert_ok!(Dex::swap_token(Origin::signed(trader), 0, 0, 10_000));
+		assert!(MultiToken::balances(1, trader).unwrap() > 0);
+		assert_ok!(Dex::withdraw(Origin::signed(provider), 0));
+		assert_eq!(Dex::balance_of(0, provider), 0);
+	});
+}
+
+#[test]
+fn should_not_sell_frozen_or_restricted_tokens_out_of_pools() {
+	new_test_ext().execute_with(|| {
+		let issuer: AccountId = whitelisted_caller();
+		let buyer: AccountId = account("recepient", 0, 1);
+		assert_ok!(MultiToken::create_asset(Origin::signed(issuer), 105));
+		assert_ok!(MultiToken::mint(Origin::signed(issuer), 105, 1_000_000));
+		MultiToken::mint(Origin::signed(issuer), 0, 1_000_000);
+		MultiToken::mint(Origin::signed(buyer), 0, 10_000);
+		assert_ok!(Dex::init(Origin::signed(issuer), 0, 1_000_000, 105, 1_000_000));
+
+		assert_ok!(MultiToken::freeze_asset(Origin::signed(issuer), 105));
+		assert_noop!(
+			Dex::swap_token(Origin::signed(buyer), 0, 0, 1_000),
+			pallet_multi_token::Error::<Test>::AssetFrozen
+		);
+		assert_ok!(MultiToken::thaw_asset(Origin::signed(issuer), 105));
+
+		// Allow-listing the pool lets listed accounts trade with it, not everyone.
+		assert_ok!(MultiToken::set_restricted(Origin::signed(issuer), 105, true));
+		assert_ok!(MultiToken::set_allowed(Origin::signed(issuer), 105, Dex::pool_account(0), true));
+		assert_noop!(
+			Dex::swap_token(Origin::signed(buyer), 0, 0, 1_000),
+			pallet_multi_token::Error::<Test>::NotAllowListed
+		);
+		assert_ok!(MultiToken::set_allowed(Origin::signed(issuer), 105, buyer, true));
+		assert_ok!(Dex::swap_token(Origin::signed(buyer), 0, 0, 1_000));
+		assert!(MultiToken::balances(105, buyer).unwrap() > 0);
+	});
+}
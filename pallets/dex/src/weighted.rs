@@ -0,0 +1,399 @@
+//! Weighted (Balancer-style) pools of two or more assets.
+//!
+//! The pool keeps `Π B_i ^ w_i` constant, where `w_i` are the normalized asset weights. The
+//! fixed-point power used by the pricing formulas follows Balancer's `bpow`: the integer part
+//! of the exponent is applied by repeated multiplication and the fractional part through a
+//! binomial series.
+
+use crate::{
+	BalanceOf, Config, Error, Event, LPTokenTotalSupply, Pallet, PoolId, PoolKind, WeightedPools,
+};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{ensure, pallet_prelude::DispatchResult, BoundedVec};
+use pallet_multi_token::multitoken::MultiToken;
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{CheckedAdd, CheckedDiv, CheckedMul, Saturating},
+	DispatchError, FixedPointNumber, FixedU128, PerThing, Permill, RuntimeDebug,
+};
+use sp_std::{vec, vec::Vec};
+
+/// LP shares minted to the creator of a weighted pool.
+pub const INITIAL_SUPPLY: u64 = 1_000_000_000_000;
+
+/// Highest swap fee a weighted pool may charge.
+pub const MAX_SWAP_FEE: Permill = Permill::from_percent(10);
+
+/// Smallest share of the total weight a single asset may have, in percent.
+pub const MIN_WEIGHT_PERCENT: u32 = 1;
+
+/// Trades may not add more than half of the input reserve...
+pub(crate) const MAX_IN_RATIO_DIVISOR: u64 = 2;
+
+/// ...nor take more than a third of the output reserve.
+pub(crate) const MAX_OUT_RATIO_DIVISOR: u64 = 3;
+
+/// The binomial series stops once its terms are below `10^-10`.
+const POW_PRECISION: u128 = 100_000_000;
+
+const MAX_POW_ITERATIONS: u128 = 1_000;
+
+/// Parameters of a weighted pool. Reserves live on the pool account.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct WeightedPool<T: Config> {
+	pub assets: BoundedVec<u64, T::MaxPoolAssets>,
+	/// Denormalized weights, in the order of `assets`.
+	pub weights: BoundedVec<u32, T::MaxPoolAssets>,
+	pub swap_fee: Permill,
+}
+
+fn one() -> FixedU128 {
+	FixedU128::from_inner(FixedU128::DIV)
+}
+
+fn sub_sign(a: FixedU128, b: FixedU128) -> (FixedU128, bool) {
+	if a >= b {
+		(a.saturating_sub(b), false)
+	} else {
+		(b.saturating_sub(a), true)
+	}
+}
+
+fn fee_fraction(fee: Permill) -> Option<FixedU128> {
+	FixedU128::checked_from_rational(fee.deconstruct(), Permill::ACCURACY)
+}
+
+/// `base ^ exponent` for a base below two, which is all the pricing formulas need.
+pub fn pow(base: FixedU128, exponent: FixedU128) -> Option<FixedU128> {
+	let whole = exponent.trunc();
+	let remain = exponent.saturating_sub(whole);
+	let mut result = one();
+	for _ in 0..whole.into_inner() / FixedU128::DIV {
+		result = result.checked_mul(&base)?;
+	}
+	if remain.into_inner() == 0 {
+		return Some(result)
+	}
+	result.checked_mul(&pow_approx(base, remain)?)
+}
+
+/// `base ^ exponent` for a fractional exponent, as the binomial series of `(1 + x) ^ exponent`.
+fn pow_approx(base: FixedU128, exponent: FixedU128) -> Option<FixedU128> {
+	let (x, x_negative) = sub_sign(base, one());
+	let mut term = one();
+	let mut sum = one();
+	let mut negative = false;
+	let mut i: u128 = 1;
+	while term.into_inner() >= POW_PRECISION {
+		if i > MAX_POW_ITERATIONS {
+			return None
+		}
+		let big_k = FixedU128::checked_from_integer(i)?;
+		let (c, c_negative) = sub_sign(exponent, big_k.saturating_sub(one()));
+		term = term.checked_mul(&c.checked_mul(&x)?)?.checked_div(&big_k)?;
+		if term.into_inner() == 0 {
+			break
+		}
+		if x_negative {
+			negative = !negative;
+		}
+		if c_negative {
+			negative = !negative;
+		}
+		sum = if negative { sum.saturating_sub(term) } else { sum.checked_add(&term)? };
+		i += 1;
+	}
+	Some(sum)
+}
+
+/// Amount of the output asset paid for `amount_in` of the input asset.
+pub fn out_given_in(
+	balance_in: u64,
+	weight_in: u32,
+	balance_out: u64,
+	weight_out: u32,
+	amount_in: u64,
+	swap_fee: Permill,
+) -> Option<u64> {
+	let weight_ratio = FixedU128::checked_from_rational(weight_in, weight_out)?;
+	let adjusted_in = amount_in.saturating_sub(swap_fee.mul_ceil(amount_in));
+	let y = FixedU128::checked_from_rational(balance_in, balance_in as u128 + adjusted_in as u128)?;
+	let bar = one().saturating_sub(pow(y, weight_ratio)?);
+	bar.checked_mul_int(balance_out)
+}
+
+/// Price of the asset with `balance_in` and `weight_in` in the asset with `balance_out` and
+/// `weight_out`, before fees.
+pub fn spot_price(balance_in: u64, weight_in: u32, balance_out: u64, weight_out: u32) -> Option<FixedU128> {
+	FixedU128::checked_from_rational(
+		balance_out as u128 * weight_in as u128,
+		balance_in as u128 * weight_out as u128,
+	)
+}
+
+/// LP shares minted for depositing `amount_in` of a single asset. The part of the deposit that
+/// would have to be swapped into the other assets pays the swap fee.
+pub fn pool_out_given_single_in(
+	balance_in: u64,
+	weight_in: u32,
+	total_weight: u32,
+	total_supply: u64,
+	amount_in: u64,
+	swap_fee: Permill,
+) -> Option<u64> {
+	let normalized_weight = FixedU128::checked_from_rational(weight_in, total_weight)?;
+	let zaz = one().saturating_sub(normalized_weight).checked_mul(&fee_fraction(swap_fee)?)?;
+	let amount_after_fee = one().saturating_sub(zaz).checked_mul_int(amount_in)?;
+	let ratio = FixedU128::checked_from_rational(
+		balance_in as u128 + amount_after_fee as u128,
+		balance_in,
+	)?;
+	let new_supply = pow(ratio, normalized_weight)?.checked_mul_int(total_supply)?;
+	Some(new_supply.saturating_sub(total_supply))
+}
+
+/// Amount of a single asset paid out for burning `pool_in` LP shares.
+pub fn single_out_given_pool_in(
+	balance_out: u64,
+	weight_out: u32,
+	total_weight: u32,
+	total_supply: u64,
+	pool_in: u64,
+	swap_fee: Permill,
+) -> Option<u64> {
+	let normalized_weight = FixedU128::checked_from_rational(weight_out, total_weight)?;
+	let pool_ratio = FixedU128::checked_from_rational(total_supply.checked_sub(pool_in)?, total_supply)?;
+	let exponent = FixedU128::checked_from_rational(total_weight, weight_out)?;
+	let new_balance_out = pow(pool_ratio, exponent)?.checked_mul_int(balance_out)?;
+	let amount_out_before_fee = balance_out.saturating_sub(new_balance_out);
+	let zaz = one().saturating_sub(normalized_weight).checked_mul(&fee_fraction(swap_fee)?)?;
+	one().saturating_sub(zaz).checked_mul_int(amount_out_before_fee)
+}
+
+impl<T: Config> Pallet<T> {
+	fn weighted_pool(pool_id: PoolId) -> Result<(WeightedPool<T>, T::AccountId), DispatchError> {
+		let pool = WeightedPools::<T>::get(pool_id).ok_or(Error::<T>::NoPool)?;
+		Ok((pool, Self::pool_account(pool_id)))
+	}
+
+	fn weighted_asset_index(pool: &WeightedPool<T>, token_id: u64) -> Result<usize, DispatchError> {
+		pool.assets.iter().position(|id| *id == token_id).ok_or_else(|| Error::<T>::UnknownToken.into())
+	}
+
+	pub(crate) fn do_create_weighted_pool(
+		creator: &T::AccountId,
+		assets: Vec<u64>,
+		weights: Vec<u32>,
+		amounts: Vec<u64>,
+		swap_fee: Permill,
+	) -> DispatchResult {
+		ensure!(
+			assets.len() == weights.len() && assets.len() == amounts.len(),
+			Error::<T>::ShouldBeSameLength
+		);
+		ensure!(assets.len() >= 2, Error::<T>::TooFewTokens);
+		for (index, id) in assets.iter().enumerate() {
+			ensure!(!assets[..index].contains(id), Error::<T>::DuplicateToken);
+		}
+		ensure!(amounts.iter().all(|amount| *amount > 0), Error::<T>::ZeroAmount);
+		ensure!(swap_fee <= MAX_SWAP_FEE, Error::<T>::InvalidFee);
+		let total_weight = weights.iter().try_fold(0u32, |acc, w| acc.checked_add(*w));
+		let total_weight = total_weight.ok_or(Error::<T>::InvalidWeights)?;
+		ensure!(
+			weights.iter().all(|w| *w > 0 && *w as u64 * 100 >= total_weight as u64 * MIN_WEIGHT_PERCENT as u64),
+			Error::<T>::InvalidWeights
+		);
+		let assets: BoundedVec<u64, T::MaxPoolAssets> =
+			assets.try_into().map_err(|_| Error::<T>::TooManyTokens)?;
+		let weights: BoundedVec<u32, T::MaxPoolAssets> =
+			weights.try_into().map_err(|_| Error::<T>::TooManyTokens)?;
+
+		let (pool_id, pool_address) = Self::register_pool(creator, PoolKind::Weighted)?;
+		for (id, amount) in assets.iter().zip(amounts.iter()) {
+			T::MultiToken::transfer_to(creator, &pool_address, id, amount)?;
+		}
+		WeightedPools::<T>::insert(pool_id, WeightedPool { assets, weights, swap_fee });
+		LPTokenTotalSupply::<T>::insert(pool_id, INITIAL_SUPPLY);
+		BalanceOf::<T>::insert(pool_id, creator, INITIAL_SUPPLY);
+		Ok(())
+	}
+
+	/// Mints exactly `liquidity` shares for a proportional deposit of every asset.
+	pub(crate) fn do_join_weighted_pool(
+		who: &T::AccountId,
+		pool_id: PoolId,
+		liquidity: u64,
+		max_amounts: Vec<u64>,
+	) -> DispatchResult {
+		let (pool, pool_address) = Self::weighted_pool(pool_id)?;
+		Self::ensure_pool_active(pool_id)?;
+		ensure!(max_amounts.len() == pool.assets.len(), Error::<T>::ShouldBeSameLength);
+		ensure!(liquidity > 0, Error::<T>::ZeroAmount);
+		let total_supply = LPTokenTotalSupply::<T>::get(pool_id);
+		ensure!(total_supply > 0, Error::<T>::NoLiquidity);
+
+		let mut amounts = Vec::with_capacity(pool.assets.len());
+		for (id, max_amount) in pool.assets.iter().zip(max_amounts.iter()) {
+			let balance = T::MultiToken::balances(id, &pool_address) as u128;
+			let amount = (balance * liquidity as u128 + total_supply as u128 - 1) / total_supply as u128;
+			let amount: u64 = amount.try_into().map_err(|_| Error::<T>::Overflow)?;
+			ensure!(amount <= *max_amount, Error::<T>::SlippageExceeded);
+			T::MultiToken::transfer_to(who, &pool_address, id, &amount)?;
+			amounts.push(amount);
+		}
+		LPTokenTotalSupply::<T>::insert(pool_id, total_supply + liquidity);
+		BalanceOf::<T>::mutate(pool_id, who, |x| *x += liquidity);
+		Self::deposit_event(Event::<T>::WeightedPoolJoined { who: who.clone(), pool_id, amounts, liquidity });
+		Ok(())
+	}
+
+	/// Burns `liquidity` shares for a proportional part of every asset.
+	pub(crate) fn do_exit_weighted_pool(
+		who: &T::AccountId,
+		pool_id: PoolId,
+		liquidity: u64,
+		min_amounts: Vec<u64>,
+	) -> DispatchResult {
+		let (pool, pool_address) = Self::weighted_pool(pool_id)?;
+		ensure!(min_amounts.len() == pool.assets.len(), Error::<T>::ShouldBeSameLength);
+		ensure!(liquidity > 0, Error::<T>::ZeroAmount);
+		ensure!(BalanceOf::<T>::get(pool_id, who) >= liquidity, Error::<T>::NoLiquidity);
+		let total_supply = LPTokenTotalSupply::<T>::get(pool_id);
+
+		let mut amounts = Vec::with_capacity(pool.assets.len());
+		for (id, min_amount) in pool.assets.iter().zip(min_amounts.iter()) {
+			let balance = T::MultiToken::balances(id, &pool_address) as u128;
+			let amount = (balance * liquidity as u128 / total_supply as u128) as u64;
+			ensure!(amount >= *min_amount, Error::<T>::SlippageExceeded);
+			T::MultiToken::transfer_to(&pool_address, who, id, &amount)?;
+			amounts.push(amount);
+		}
+		LPTokenTotalSupply::<T>::insert(pool_id, total_supply - liquidity);
+		BalanceOf::<T>::mutate(pool_id, who, |x| *x -= liquidity);
+		Self::deposit_event(Event::<T>::WeightedPoolExited { who: who.clone(), pool_id, liquidity, amounts });
+		Ok(())
+	}
+
+	pub(crate) fn do_join_weighted_pool_single(
+		who: &T::AccountId,
+		pool_id: PoolId,
+		token_in: u64,
+		amount_in: u64,
+		min_liquidity: u64,
+	) -> Result<u64, DispatchError> {
+		let (pool, pool_address) = Self::weighted_pool(pool_id)?;
+		Self::ensure_pool_active(pool_id)?;
+		let index = Self::weighted_asset_index(&pool, token_in)?;
+		ensure!(amount_in > 0, Error::<T>::ZeroAmount);
+		let balance_in = T::MultiToken::balances(&token_in, &pool_address);
+		ensure!(amount_in <= balance_in / MAX_IN_RATIO_DIVISOR, Error::<T>::MaxInRatio);
+
+		let total_supply = LPTokenTotalSupply::<T>::get(pool_id);
+		let liquidity = pool_out_given_single_in(
+			balance_in,
+			pool.weights[index],
+			pool.weights.iter().sum(),
+			total_supply,
+			amount_in,
+			pool.swap_fee,
+		)
+		.ok_or(Error::<T>::Overflow)?;
+		ensure!(liquidity > 0, Error::<T>::ZeroAmount);
+		ensure!(liquidity >= min_liquidity, Error::<T>::SlippageExceeded);
+
+		T::MultiToken::transfer_to(who, &pool_address, &token_in, &amount_in)?;
+		LPTokenTotalSupply::<T>::insert(pool_id, total_supply + liquidity);
+		BalanceOf::<T>::mutate(pool_id, who, |x| *x += liquidity);
+		// Single-asset joins report the same event, with nothing paid in the other assets.
+		let mut amounts = vec![0; pool.assets.len()];
+		amounts[index] = amount_in;
+		Self::deposit_event(Event::<T>::WeightedPoolJoined { who: who.clone(), pool_id, amounts, liquidity });
+		Ok(liquidity)
+	}
+
+	pub(crate) fn do_exit_weighted_pool_single(
+		who: &T::AccountId,
+		pool_id: PoolId,
+		token_out: u64,
+		liquidity: u64,
+		min_amount_out: u64,
+	) -> Result<u64, DispatchError> {
+		let (pool, pool_address) = Self::weighted_pool(pool_id)?;
+		let index = Self::weighted_asset_index(&pool, token_out)?;
+		ensure!(liquidity > 0, Error::<T>::ZeroAmount);
+		ensure!(BalanceOf::<T>::get(pool_id, who) >= liquidity, Error::<T>::NoLiquidity);
+		let balance_out = T::MultiToken::balances(&token_out, &pool_address);
+
+		let total_supply = LPTokenTotalSupply::<T>::get(pool_id);
+		let amount_out = single_out_given_pool_in(
+			balance_out,
+			pool.weights[index],
+			pool.weights.iter().sum(),
+			total_supply,
+			liquidity,
+			pool.swap_fee,
+		)
+		.ok_or(Error::<T>::Overflow)?;
+		ensure!(amount_out <= balance_out / MAX_OUT_RATIO_DIVISOR, Error::<T>::MaxOutRatio);
+		ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+
+		T::MultiToken::transfer_to(&pool_address, who, &token_out, &amount_out)?;
+		LPTokenTotalSupply::<T>::insert(pool_id, total_supply - liquidity);
+		BalanceOf::<T>::mutate(pool_id, who, |x| *x -= liquidity);
+		let mut amounts = vec![0; pool.assets.len()];
+		amounts[index] = amount_out;
+		Self::deposit_event(Event::<T>::WeightedPoolExited { who: who.clone(), pool_id, liquidity, amounts });
+		Ok(amount_out)
+	}
+
+	pub(crate) fn do_weighted_swap(
+		who: &T::AccountId,
+		pool_id: PoolId,
+		token_in: u64,
+		token_out: u64,
+		amount_in: u64,
+		min_amount_out: u64,
+	) -> Result<u64, DispatchError> {
+		let (pool, pool_address) = Self::weighted_pool(pool_id)?;
+		Self::ensure_pool_active(pool_id)?;
+		let i = Self::weighted_asset_index(&pool, token_in)?;
+		let j = Self::weighted_asset_index(&pool, token_out)?;
+		ensure!(i != j, Error::<T>::DuplicateToken);
+		ensure!(amount_in > 0, Error::<T>::ZeroAmount);
+		let balance_in = T::MultiToken::balances(&token_in, &pool_address);
+		let balance_out = T::MultiToken::balances(&token_out, &pool_address);
+		ensure!(amount_in <= balance_in / MAX_IN_RATIO_DIVISOR, Error::<T>::MaxInRatio);
+
+		let amount_out = out_given_in(
+			balance_in,
+			pool.weights[i],
+			balance_out,
+			pool.weights[j],
+			amount_in,
+			pool.swap_fee,
+		)
+		.ok_or(Error::<T>::Overflow)?;
+		ensure!(amount_out <= balance_out / MAX_OUT_RATIO_DIVISOR, Error::<T>::MaxOutRatio);
+		ensure!(amount_out > 0, Error::<T>::NoLiquidity);
+		ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+		let price = spot_price(balance_in, pool.weights[i], balance_out, pool.weights[j]).ok_or(Error::<T>::Overflow)?;
+		let new_price =
+			spot_price(balance_in + amount_in, pool.weights[i], balance_out - amount_out, pool.weights[j])
+				.ok_or(Error::<T>::Overflow)?;
+		Self::check_swap_price_move(pool_id, token_in, token_out, price, new_price)?;
+
+		T::MultiToken::transfer_to(who, &pool_address, &token_in, &amount_in)?;
+		T::MultiToken::transfer_to(&pool_address, who, &token_out, &amount_out)?;
+		Self::deposit_event(Event::<T>::WeightedSwapped {
+			who: who.clone(),
+			pool_id,
+			token_in,
+			amount_in,
+			token_out,
+			amount_out,
+		});
+		Ok(amount_out)
+	}
+}
@@ -0,0 +1,179 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Halts calls of other pallets during an incident without a runtime upgrade.
+//!
+//! A privileged origin pauses whole pallets or single calls by name, as reported by
+//! `GetCallMetadata`. Emergency-exit mode halts every guarded call at once. Either way, calls
+//! the runtime lists as exits, such as withdrawals, keep working. The runtime enforces all of it
+//! by using [`PauseFilter`] as its `BaseCallFilter`.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use frame_support::traits::{CallMetadata, Contains, GetCallMetadata};
+use sp_std::marker::PhantomData;
+
+/// `Contains<Call>` filter rejecting the guarded calls that are paused.
+///
+/// `Guarded` selects the calls pauses apply to, any other call always passes. `Exits` selects
+/// the guarded calls that stay open even in emergency-exit mode.
+pub struct PauseFilter<T, Guarded, Exits>(PhantomData<(T, Guarded, Exits)>);
+
+impl<T, Guarded, Exits, Call> Contains<Call> for PauseFilter<T, Guarded, Exits>
+where
+	T: Config,
+	Guarded: Contains<Call>,
+	Exits: Contains<Call>,
+	Call: GetCallMetadata,
+{
+	fn contains(call: &Call) -> bool {
+		if !Guarded::contains(call) || Exits::contains(call) {
+			return true
+		}
+		if EmergencyExit::<T>::get() {
+			return false
+		}
+		let CallMetadata { pallet_name, function_name } = call.get_call_metadata();
+		!Pallet::<T>::is_paused(pallet_name.as_bytes(), function_name.as_bytes())
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{pallet_prelude::*, traits::PalletInfoAccess};
+	use frame_system::pallet_prelude::*;
+	use sp_std::vec::Vec;
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// Name of a pallet or of one of its calls.
+	pub type NameOf<T> = BoundedVec<u8, <T as Config>::MaxNameLen>;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+		/// Origin allowed to pause and resume calls.
+		type PauseOrigin: EnsureOrigin<Self::Origin>;
+		/// Longest pallet or call name that can be paused.
+		#[pallet::constant]
+		type MaxNameLen: Get<u32>;
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		PalletPaused { pallet_name: NameOf<T> },
+		PalletResumed { pallet_name: NameOf<T> },
+		CallPaused { pallet_name: NameOf<T>, call_name: NameOf<T> },
+		CallResumed { pallet_name: NameOf<T>, call_name: NameOf<T> },
+		EmergencyExitSet { enabled: bool },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		NameTooLong,
+		AlreadyPaused,
+		NotPaused,
+		/// This pallet can't pause itself, or nothing could resume it.
+		Unpausable,
+	}
+
+	/// Pallets with every call halted.
+	#[pallet::storage]
+	pub type PausedPallets<T: Config> = StorageMap<_, Blake2_128Concat, NameOf<T>, (), OptionQuery>;
+
+	/// Halted calls, by pallet and call name.
+	#[pallet::storage]
+	pub type PausedCalls<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, NameOf<T>, Blake2_128Concat, NameOf<T>, (), OptionQuery>;
+
+	/// Halts every guarded call except the exits while set.
+	#[pallet::storage]
+	#[pallet::getter(fn emergency_exit)]
+	pub type EmergencyExit<T> = StorageValue<_, bool, ValueQuery>;
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		#[pallet::weight(1000)]
+		pub fn pause_pallet(origin: OriginFor<T>, pallet_name: Vec<u8>) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			let pallet_name = Self::pausable_name(pallet_name)?;
+			ensure!(!PausedPallets::<T>::contains_key(&pallet_name), Error::<T>::AlreadyPaused);
+			PausedPallets::<T>::insert(&pallet_name, ());
+			Self::deposit_event(Event::<T>::PalletPaused { pallet_name });
+			Ok(())
+		}
+
+		#[pallet::weight(1000)]
+		pub fn resume_pallet(origin: OriginFor<T>, pallet_name: Vec<u8>) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			let pallet_name = Self::bounded_name(pallet_name)?;
+			ensure!(PausedPallets::<T>::take(&pallet_name).is_some(), Error::<T>::NotPaused);
+			Self::deposit_event(Event::<T>::PalletResumed { pallet_name });
+			Ok(())
+		}
+
+		#[pallet::weight(1000)]
+		pub fn pause_call(origin: OriginFor<T>, pallet_name: Vec<u8>, call_name: Vec<u8>) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			let pallet_name = Self::pausable_name(pallet_name)?;
+			let call_name = Self::bounded_name(call_name)?;
+			ensure!(!PausedCalls::<T>::contains_key(&pallet_name, &call_name), Error::<T>::AlreadyPaused);
+			PausedCalls::<T>::insert(&pallet_name, &call_name, ());
+			Self::deposit_event(Event::<T>::CallPaused { pallet_name, call_name });
+			Ok(())
+		}
+
+		#[pallet::weight(1000)]
+		pub fn resume_call(origin: OriginFor<T>, pallet_name: Vec<u8>, call_name: Vec<u8>) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			let pallet_name = Self::bounded_name(pallet_name)?;
+			let call_name = Self::bounded_name(call_name)?;
+			ensure!(PausedCalls::<T>::take(&pallet_name, &call_name).is_some(), Error::<T>::NotPaused);
+			Self::deposit_event(Event::<T>::CallResumed { pallet_name, call_name });
+			Ok(())
+		}
+
+		/// Switches emergency-exit mode, where only the exit calls of guarded pallets work.
+		#[pallet::weight(1000)]
+		pub fn set_emergency_exit(origin: OriginFor<T>, enabled: bool) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			EmergencyExit::<T>::put(enabled);
+			Self::deposit_event(Event::<T>::EmergencyExitSet { enabled });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Whether `call_name` of `pallet_name` is paused, on its own or with its pallet.
+		pub fn is_paused(pallet_name: &[u8], call_name: &[u8]) -> bool {
+			let pallet_name = match NameOf::<T>::try_from(pallet_name.to_vec()) {
+				Ok(name) => name,
+				Err(_) => return false,
+			};
+			if PausedPallets::<T>::contains_key(&pallet_name) {
+				return true
+			}
+			match NameOf::<T>::try_from(call_name.to_vec()) {
+				Ok(call_name) => PausedCalls::<T>::contains_key(&pallet_name, &call_name),
+				Err(_) => false,
+			}
+		}
+
+		fn bounded_name(name: Vec<u8>) -> Result<NameOf<T>, DispatchError> {
+			Ok(NameOf::<T>::try_from(name).map_err(|_| Error::<T>::NameTooLong)?)
+		}
+
+		fn pausable_name(pallet_name: Vec<u8>) -> Result<NameOf<T>, DispatchError> {
+			ensure!(pallet_name != <Self as PalletInfoAccess>::name().as_bytes(), Error::<T>::Unpausable);
+			Self::bounded_name(pallet_name)
+		}
+	}
+}
@@ -0,0 +1,77 @@
+use crate as pallet_emergency_pause;
+use frame_support::traits::{ConstU16, ConstU32, ConstU64, Contains};
+use frame_system as system;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		EmergencyPause: pallet_emergency_pause,
+	}
+);
+
+/// Pauses apply to the calls of `System`.
+pub struct GuardedCalls;
+impl Contains<Call> for GuardedCalls {
+	fn contains(call: &Call) -> bool {
+		matches!(call, Call::System(_))
+	}
+}
+
+/// `remark_with_event` plays the part of a withdrawal.
+pub struct ExitCalls;
+impl Contains<Call> for ExitCalls {
+	fn contains(call: &Call) -> bool {
+		matches!(call, Call::System(frame_system::Call::remark_with_event { .. }))
+	}
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = pallet_emergency_pause::PauseFilter<Test, GuardedCalls, ExitCalls>;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_emergency_pause::Config for Test {
+	type Event = Event;
+	type PauseOrigin = frame_system::EnsureRoot<u64>;
+	type MaxNameLen = ConstU32<32>;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+}
@@ -0,0 +1,78 @@
+use crate::{mock::*, Error};
+use frame_support::{assert_noop, assert_ok, dispatch::Dispatchable};
+use sp_runtime::DispatchError;
+
+fn remark() -> Call {
+	Call::System(frame_system::Call::remark { remark: [1].to_vec() })
+}
+
+fn remark_with_event() -> Call {
+	Call::System(frame_system::Call::remark_with_event { remark: [1].to_vec() })
+}
+
+fn filtered(call: Call) -> bool {
+	call.dispatch(Origin::signed(1)).err().map(|e| e.error) == Some(frame_system::Error::<Test>::CallFiltered.into())
+}
+
+#[test]
+fn should_pause_and_resume_single_calls() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(EmergencyPause::pause_call(Origin::signed(1), b"System".to_vec(), b"remark".to_vec()), DispatchError::BadOrigin);
+		assert_ok!(EmergencyPause::pause_call(Origin::root(), b"System".to_vec(), b"remark".to_vec()));
+		assert_noop!(
+			EmergencyPause::pause_call(Origin::root(), b"System".to_vec(), b"remark".to_vec()),
+			Error::<Test>::AlreadyPaused
+		);
+		assert!(filtered(remark()));
+		assert!(!filtered(remark_with_event()));
+
+		assert_ok!(EmergencyPause::resume_call(Origin::root(), b"System".to_vec(), b"remark".to_vec()));
+		assert_noop!(
+			EmergencyPause::resume_call(Origin::root(), b"System".to_vec(), b"remark".to_vec()),
+			Error::<Test>::NotPaused
+		);
+		assert!(!filtered(remark()));
+	});
+}
+
+#[test]
+fn should_pause_whole_pallet_but_not_exits() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(EmergencyPause::pause_pallet(Origin::root(), b"System".to_vec()));
+		assert!(filtered(remark()));
+		assert!(filtered(Call::System(frame_system::Call::set_heap_pages { pages: 1 })));
+		assert!(!filtered(remark_with_event()));
+
+		assert_ok!(EmergencyPause::resume_pallet(Origin::root(), b"System".to_vec()));
+		assert!(!filtered(remark()));
+	});
+}
+
+#[test]
+fn should_only_allow_exits_in_emergency_exit_mode() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(EmergencyPause::set_emergency_exit(Origin::signed(1), true), DispatchError::BadOrigin);
+		assert_ok!(EmergencyPause::set_emergency_exit(Origin::root(), true));
+		assert!(EmergencyPause::emergency_exit());
+		assert!(filtered(remark()));
+		assert!(!filtered(remark_with_event()));
+		// Unguarded calls are never filtered.
+		assert!(!filtered(Call::EmergencyPause(crate::Call::set_emergency_exit { enabled: true })));
+
+		assert_ok!(EmergencyPause::set_emergency_exit(Origin::root(), false));
+		assert!(!filtered(remark()));
+	});
+}
+
+#[test]
+fn should_reject_invalid_names() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(EmergencyPause::pause_pallet(Origin::root(), b"EmergencyPause".to_vec()), Error::<Test>::Unpausable);
+		assert_noop!(EmergencyPause::pause_pallet(Origin::root(), [b'a'; 33].to_vec()), Error::<Test>::NameTooLong);
+		assert_noop!(
+			EmergencyPause::pause_call(Origin::root(), b"System".to_vec(), [b'a'; 33].to_vec()),
+			Error::<Test>::NameTooLong
+		);
+		assert_noop!(EmergencyPause::resume_pallet(Origin::root(), b"System".to_vec()), Error::<Test>::NotPaused);
+	});
+}
@@ -6,7 +6,19 @@ use frame_benchmarking::{account, benchmarks, whitelisted_caller, vec};
 use frame_system::RawOrigin;
 use frame_support::{
 	ensure,
+	traits::{Currency, Get},
 };
+use sp_runtime::traits::Bounded;
+
+/// Creates an asset issued by the whitelisted caller, who mints some of it, and returns its id.
+fn create_issued_asset<T: Config>() -> u64 {
+	let caller: T::AccountId = whitelisted_caller();
+	let id = T::FirstIssuedTokenId::get();
+	T::Currency::make_free_balance_be(&caller, Bounded::max_value());
+	MultiToken::<T>::create_asset(RawOrigin::Signed(caller.clone()).into(), id).unwrap();
+	MultiToken::<T>::mint(RawOrigin::Signed(caller).into(), id, 10).unwrap();
+	id
+}
 
 benchmarks! {
 	set_approval {
@@ -60,5 +72,127 @@ benchmarks! {
         ensure!(data.len() == data.len(), "arrays should be the same length");
 	}
 
+	wrap {
+		let caller: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&caller, Bounded::max_value());
+	}: _(RawOrigin::Signed(caller), 1_000_000)
+	verify {
+		ensure!(MultiToken::<T>::wrapped_supply() == 1_000_000, "wrapped supply should grow");
+	}
+
+	unwrap {
+		let caller: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&caller, Bounded::max_value());
+		MultiToken::<T>::wrap(RawOrigin::Signed(caller.clone()).into(), 2_000_000)?;
+	}: _(RawOrigin::Signed(caller), 1_000_000)
+	verify {
+		ensure!(MultiToken::<T>::wrapped_supply() == 1_000_000, "wrapped supply should shrink");
+	}
+
+	create_asset {
+		let caller: T::AccountId = whitelisted_caller();
+		let id = T::FirstIssuedTokenId::get();
+		T::Currency::make_free_balance_be(&caller, Bounded::max_value());
+	}: _(RawOrigin::Signed(caller), id)
+	verify {
+		ensure!(MultiToken::<T>::issuer(id) == Some(whitelisted_caller()), "caller should issue the asset");
+	}
+
+	freeze {
+		let recipient: T::AccountId = account("recipient", 0, 1);
+		let id = create_issued_asset::<T>();
+	}: _(RawOrigin::Signed(whitelisted_caller()), id, recipient.clone())
+	verify {
+		ensure!(MultiToken::<T>::is_frozen(id, recipient), "balance should be frozen");
+	}
+
+	thaw {
+		let recipient: T::AccountId = account("recipient", 0, 1);
+		let id = create_issued_asset::<T>();
+		MultiToken::<T>::freeze(RawOrigin::Signed(whitelisted_caller()).into(), id, recipient.clone())?;
+	}: _(RawOrigin::Signed(whitelisted_caller()), id, recipient.clone())
+	verify {
+		ensure!(!MultiToken::<T>::is_frozen(id, recipient), "balance should be thawed");
+	}
+
+	freeze_asset {
+		let id = create_issued_asset::<T>();
+	}: _(RawOrigin::Signed(whitelisted_caller()), id)
+	verify {
+		ensure!(MultiToken::<T>::is_asset_frozen(id), "asset should be frozen");
+	}
+
+	thaw_asset {
+		let id = create_issued_asset::<T>();
+		MultiToken::<T>::freeze_asset(RawOrigin::Signed(whitelisted_caller()).into(), id)?;
+	}: _(RawOrigin::Signed(whitelisted_caller()), id)
+	verify {
+		ensure!(!MultiToken::<T>::is_asset_frozen(id), "asset should be thawed");
+	}
+
+	set_restricted {
+		let id = create_issued_asset::<T>();
+	}: _(RawOrigin::Signed(whitelisted_caller()), id, true)
+	verify {
+		ensure!(MultiToken::<T>::is_restricted(id), "asset should be restricted");
+	}
+
+	set_allowed {
+		let recipient: T::AccountId = account("recipient", 0, 1);
+		let id = create_issued_asset::<T>();
+	}: _(RawOrigin::Signed(whitelisted_caller()), id, recipient.clone(), true)
+	verify {
+		ensure!(MultiToken::<T>::is_allowed(id, recipient), "account should be allowed");
+	}
+
+	safe_transfer_from {
+		let recipient: T::AccountId = account("recipient", 0, 1);
+		let caller_origin = <T as frame_system::Config>::Origin::from(RawOrigin::Signed(recipient.clone()));
+		MultiToken::<T>::mint(caller_origin.clone(), 0, 10)?;
+		MultiToken::<T>::set_approval(caller_origin, whitelisted_caller(), true)?;
+	}: _(RawOrigin::Signed(whitelisted_caller()), recipient, whitelisted_caller(), 0, 10, vec![0; 32])
+
+	safe_batch_transfer_from {
+		let recipient: T::AccountId = account("recipient", 0, 1);
+		let l in 0 .. 10 as u64;
+		let data = vec![1; l as usize];
+		let caller_origin = <T as frame_system::Config>::Origin::from(RawOrigin::Signed(recipient.clone()));
+		MultiToken::<T>::mint_batch(caller_origin.clone(), data.clone(), data.clone())?;
+		MultiToken::<T>::set_approval(caller_origin, whitelisted_caller(), true)?;
+	}: _(RawOrigin::Signed(whitelisted_caller()), recipient, whitelisted_caller(), data.clone(), data.clone(), vec![0; 32])
+
+	create_collection {
+	}: _(RawOrigin::Signed(whitelisted_caller()), 0)
+
+	mint_nft {
+		let id = T::FirstIssuedTokenId::get();
+		MultiToken::<T>::create_collection(RawOrigin::Signed(whitelisted_caller()).into(), 0)?;
+	}: _(RawOrigin::Signed(whitelisted_caller()), 0, id)
+	verify {
+		ensure!(MultiToken::<T>::owner_of(id) == Some(whitelisted_caller()), "caller should own the nft");
+	}
+
+	set_metadata {
+		let s = T::StringLimit::get();
+		let id = T::FirstIssuedTokenId::get();
+		MultiToken::<T>::create_collection(RawOrigin::Signed(whitelisted_caller()).into(), 0)?;
+		MultiToken::<T>::mint_nft(RawOrigin::Signed(whitelisted_caller()).into(), 0, id)?;
+	}: _(RawOrigin::Signed(whitelisted_caller()), id, vec![0; s as usize])
+
+	set_attribute {
+		let s = T::StringLimit::get();
+		let id = T::FirstIssuedTokenId::get();
+		MultiToken::<T>::create_collection(RawOrigin::Signed(whitelisted_caller()).into(), 0)?;
+		MultiToken::<T>::mint_nft(RawOrigin::Signed(whitelisted_caller()).into(), 0, id)?;
+	}: _(RawOrigin::Signed(whitelisted_caller()), id, vec![0; s as usize], vec![0; s as usize])
+
+	clear_attribute {
+		let s = T::StringLimit::get();
+		let id = T::FirstIssuedTokenId::get();
+		MultiToken::<T>::create_collection(RawOrigin::Signed(whitelisted_caller()).into(), 0)?;
+		MultiToken::<T>::mint_nft(RawOrigin::Signed(whitelisted_caller()).into(), 0, id)?;
+		MultiToken::<T>::set_attribute(RawOrigin::Signed(whitelisted_caller()).into(), id, vec![0; s as usize], vec![0; s as usize])?;
+	}: _(RawOrigin::Signed(whitelisted_caller()), id, vec![0; s as usize])
+
 }
 
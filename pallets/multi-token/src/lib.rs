@@ -20,8 +20,13 @@ use frame_support::pallet_prelude::DispatchResult;
 // use frame_system::pallet_prelude::OriginFor;
 use sp_std::vec::Vec;
 pub mod multitoken;
+pub mod transfer_hook;
+pub mod migrations;
 use frame_support::pallet_prelude::*;
 use frame_system::pallet_prelude::*;
+use sp_std::marker::PhantomData;
+use crate::transfer_hook::TransferHook;
+use frame_support::traits::Contains;
 
 impl<T:Config> MultiToken<T::AccountId> for Pallet<T> {
 	fn balances(id: &u64, account: &T::AccountId) -> u64 {
@@ -33,6 +38,7 @@ impl<T:Config> MultiToken<T::AccountId> for Pallet<T> {
 
 	fn transfer_to(_origin: &T::AccountId, _to: &T::AccountId, _id: &u64, _amount: &u64) -> DispatchResult {
 		ensure!(Self::balances(_id, _origin) >= Some(*_amount), Error::<T>::NotEnoughOwned);
+		Self::ensure_transferable(*_id, _origin, _to, *_amount)?;
 		Balances::<T>::try_mutate(_id, _origin, |balance| {
 			match balance {
 				Some(x) => {
@@ -47,27 +53,68 @@ impl<T:Config> MultiToken<T::AccountId> for Pallet<T> {
 			Some(x) => Balances::<T>::insert(_id, _to, x + _amount),
 			None => Balances::<T>::insert(_id, _to, _amount),
 		};
+		Self::note_nft_owner(*_id, _to);
+		Ok(())
+	}
+
+	fn refund(_origin: &T::AccountId, _to: &T::AccountId, _id: &u64, _amount: &u64) -> DispatchResult {
+		if !T::ExemptAccounts::contains(_origin) {
+			return Self::do_transfer(*_id, _origin, _to, *_amount)
+		}
+		ensure!(Self::balances(_id, _origin) >= Some(*_amount), Error::<T>::NotEnoughOwned);
+		ensure!(*_amount == 1 || !NftItems::<T>::contains_key(_id), Error::<T>::NonFungible);
+		Self::move_balance(*_id, _origin, _to, *_amount);
 		Ok(())
 	}
 
 	fn mint(_origin: &T::AccountId, _id: &u64, _amount: &u64) -> DispatchResult {
+		Self::increase_supply(*_id, _origin, *_amount)?;
 		match Balances::<T>::get(_id, _origin){
 			Some(x) => Balances::<T>::insert(_id, _origin, x + _amount),
 			None => Balances::<T>::insert(_id, _origin, _amount),
 		};
 		Ok(())
 	}
+
+	fn unwrap_to(_origin: &T::AccountId, _to: &T::AccountId, _amount: &u64) -> DispatchResult {
+		Self::do_unwrap(_origin, _to, *_amount)
+	}
+}
+
+/// Restricts transfers of the ids their issuer marked as restricted to allow-listed accounts.
+pub struct AllowList<T>(PhantomData<T>);
+
+impl<T: Config> TransferHook<T::AccountId> for AllowList<T> {
+	fn on_transfer(id: u64, from: &T::AccountId, to: &T::AccountId, _amount: u64) -> DispatchResult {
+		if RestrictedAssets::<T>::get(id) {
+			ensure!(AllowLists::<T>::get(id, from) && AllowLists::<T>::get(id, to), Error::<T>::NotAllowListed);
+		}
+		Ok(())
+	}
 }
 
 #[frame_support::pallet]
 pub mod pallet {
-	use frame_support::pallet_prelude::*;
+	use frame_support::{
+		pallet_prelude::*,
+		traits::{Contains, Currency, ExistenceRequirement, ReservableCurrency},
+		PalletId,
+	};
 	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::{AccountIdConversion, SaturatedConversion};
 	use sp_std::vec::Vec;
 	use crate::weights::WeightInfo;
+	use crate::transfer_hook::{OnReceived, TransferHook};
+
+	pub type DepositBalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+	/// Version 1 tracks `TotalSupply` and only has issuers for ids created with `create_asset`.
+	pub(crate) const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
 
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	/// Configure the pallet by specifying the parameters and types on which it depends.
@@ -76,6 +123,38 @@ pub mod pallet {
 		/// Because this pallet emits events, it depends on the runtime's definition of an event.
 		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
 		type WeightInfo: WeightInfo;
+		/// Native currency `wrap` turns into `NativeTokenId` tokens, also reserved for deposits.
+		type Currency: ReservableCurrency<Self::AccountId>;
+		/// Token id of the wrapped native currency, only minted by `wrap`.
+		#[pallet::constant]
+		type NativeTokenId: Get<u64>;
+		/// Used to derive the account holding the native currency backing wrapped tokens.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+		/// Ids from this one on are left to `create_asset` and `mint_nft`, so that minting
+		/// them without an issuer can't take them before their issuers do.
+		#[pallet::constant]
+		type FirstIssuedTokenId: Get<u64>;
+		/// Reserved from the issuer of a token id created with `create_asset`.
+		#[pallet::constant]
+		type AssetDeposit: Get<DepositBalanceOf<Self>>;
+		/// Checks every transfer, `AllowList` to restrict ids to allow-listed accounts.
+		type OnTransfer: TransferHook<Self::AccountId>;
+		/// Escrows of other pallets, which `refund` deposits regardless of freezes and
+		/// `OnTransfer`. Their other transfers are checked like any account's.
+		type ExemptAccounts: Contains<Self::AccountId>;
+		/// Lets recipients of `safe_transfer_from` and `safe_batch_transfer_from` reject them.
+		type OnReceived: OnReceived<Self::AccountId>;
+		/// Maximum length of NFT metadata and of attribute keys and values.
+		#[pallet::constant]
+		type StringLimit: Get<u32>;
+	}
+
+	/// A non-fungible token, the only unit of its id.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct NftItem<AccountId> {
+		pub collection: u64,
+		pub owner: AccountId,
 	}
 
 	// Pallets use events to inform users when important changes are made.
@@ -87,14 +166,41 @@ pub mod pallet {
 		MintBatch { account: T::AccountId, id: Vec<u64>, value: Vec<u64> },
 		TransferSingle { operator: T::AccountId, from: T::AccountId, to: T::AccountId, id: u64, value: u64 },
 		TransferBatch { operator: T::AccountId, from: T::AccountId, to: T::AccountId, id: Vec<u64>, value: Vec<u64> },
-		ApprovalForAll { account: T::AccountId, operator: T::AccountId, approved: bool }
+		ApprovalForAll { account: T::AccountId, operator: T::AccountId, approved: bool },
+		AssetCreated { id: u64, issuer: T::AccountId },
+		Wrapped { account: T::AccountId, value: u64 },
+		Unwrapped { account: T::AccountId, value: u64 },
+		Frozen { id: u64, account: T::AccountId },
+		Thawed { id: u64, account: T::AccountId },
+		AssetFrozen { id: u64 },
+		AssetThawed { id: u64 },
+		RestrictionSet { id: u64, restricted: bool },
+		AllowListSet { id: u64, account: T::AccountId, allowed: bool },
+		CollectionCreated { collection: u64, owner: T::AccountId },
+		NftMinted { collection: u64, id: u64, owner: T::AccountId },
+		MetadataSet { id: u64, data: Vec<u8> },
+		AttributeSet { id: u64, key: Vec<u8>, value: Vec<u8> },
+		AttributeCleared { id: u64, key: Vec<u8> }
 	}
 
 	#[pallet::error]
 	pub enum Error<T> {
 		ShouldBeSameLength,
 		NotEnoughOwned,
-		NotApproved
+		NotApproved,
+		ReservedTokenId,
+		NotIssuer,
+		Overflow,
+		BalanceFrozen,
+		AssetFrozen,
+		NotAllowListed,
+		CollectionExists,
+		NoCollection,
+		NotCollectionOwner,
+		TokenIdInUse,
+		NoNft,
+		NonFungible,
+		TooLong
 	}
 
 	#[pallet::storage]
@@ -106,6 +212,66 @@ pub mod pallet {
 	#[pallet::getter(fn approvals)]
 	pub type Approvals<T: Config> = StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, T::AccountId, bool>;
 
+	/// Wrapped native currency in circulation, always backed one to one by the native balance
+	/// of `account_id`.
+	#[pallet::storage]
+	#[pallet::getter(fn wrapped_supply)]
+	pub type WrappedSupply<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+	/// Minted amount of each token id, the wrapped native currency aside.
+	#[pallet::storage]
+	#[pallet::getter(fn total_supply)]
+	pub type TotalSupply<T: Config> = StorageMap<_, Blake2_128Concat, u64, u64, ValueQuery>;
+
+	/// Creators of token ids, the only accounts minting them and which may freeze them.
+	#[pallet::storage]
+	#[pallet::getter(fn issuer)]
+	pub type Issuers<T: Config> = StorageMap<_, Blake2_128Concat, u64, T::AccountId>;
+
+	/// Token ids of which no balance can be transferred.
+	#[pallet::storage]
+	#[pallet::getter(fn is_asset_frozen)]
+	pub type FrozenAssets<T: Config> = StorageMap<_, Blake2_128Concat, u64, bool, ValueQuery>;
+
+	/// Balances which can't be transferred by their owner.
+	#[pallet::storage]
+	#[pallet::getter(fn is_frozen)]
+	pub type FrozenBalances<T: Config> = StorageDoubleMap<_, Blake2_128Concat, u64, Blake2_128Concat, T::AccountId, bool, ValueQuery>;
+
+	/// Token ids only moving between allow-listed accounts when `AllowList` checks transfers.
+	#[pallet::storage]
+	#[pallet::getter(fn is_restricted)]
+	pub type RestrictedAssets<T: Config> = StorageMap<_, Blake2_128Concat, u64, bool, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn is_allowed)]
+	pub type AllowLists<T: Config> = StorageDoubleMap<_, Blake2_128Concat, u64, Blake2_128Concat, T::AccountId, bool, ValueQuery>;
+
+	/// Owners of NFT collections, which mint their items.
+	#[pallet::storage]
+	#[pallet::getter(fn collection_owner)]
+	pub type Collections<T: Config> = StorageMap<_, Blake2_128Concat, u64, T::AccountId>;
+
+	/// Token ids minted as NFTs, each with a supply of exactly one.
+	#[pallet::storage]
+	#[pallet::getter(fn nft_item)]
+	pub type NftItems<T: Config> = StorageMap<_, Blake2_128Concat, u64, NftItem<T::AccountId>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn item_metadata)]
+	pub type ItemMetadata<T: Config> = StorageMap<_, Blake2_128Concat, u64, BoundedVec<u8, T::StringLimit>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn attribute)]
+	pub type Attributes<T: Config> = StorageDoubleMap<_, Blake2_128Concat, u64, Blake2_128Concat, BoundedVec<u8, T::StringLimit>, BoundedVec<u8, T::StringLimit>>;
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_runtime_upgrade() -> Weight {
+			crate::migrations::migrate_to_v1::<T>()
+		}
+	}
+
 	#[pallet:: call]
 	impl<T: Config> Pallet<T> {
 		#[pallet::weight(<T as Config>::WeightInfo::set_approval())]
@@ -123,6 +289,7 @@ pub mod pallet {
 		pub fn mint(origin: OriginFor<T>, _id: u64, _amount: u64) -> DispatchResult {
 			
 			let sender = &ensure_signed(origin)?;
+			Self::increase_supply(_id, sender, _amount)?;
 
 			match Balances::<T>::get(_id, sender){
 				Some(x) => Balances::<T>::insert(&_id, sender, x + &_amount),
@@ -141,6 +308,7 @@ pub mod pallet {
 			ensure!(_id.len() == _amount.len(), Error::<T>::ShouldBeSameLength);
 
 			for i in 0.._id.len() {
+				Self::increase_supply(_id[i], sender, _amount[i])?;
 				match Balances::<T>::get(_id[i], sender){
 					Some(x) => Balances::<T>::insert(_id[i], sender, x + _amount[i]),
 					None => Balances::<T>::insert(_id[i], sender, _amount[i]),
@@ -157,6 +325,7 @@ pub mod pallet {
 
 			let sender = &ensure_signed(origin)?;
 			ensure!(Balances::<T>::get(&_id, sender) >= Some(_amount), Error::<T>::NotEnoughOwned);
+			Self::ensure_transferable(_id, sender, &_to, _amount)?;
 
 			Balances::<T>::try_mutate(&_id, sender, |balance| {
 				match balance {
@@ -172,6 +341,7 @@ pub mod pallet {
 				Some(x) => Balances::<T>::insert(&_id, &_to, x + &_amount),
 				None => Balances::<T>::insert(&_id, &_to, &_amount),
 			};
+			Self::note_nft_owner(_id, &_to);
 
 			Self::deposit_event(Event::<T>::TransferSingle {
 				operator: sender.clone(),
@@ -193,6 +363,7 @@ pub mod pallet {
 			for it in _id.iter().zip(_amount.iter()) {
 				let (&id, &amount) = it;
 				ensure!(Balances::<T>::get(&id, &sender) >= Some(amount), Error::<T>::NotEnoughOwned);
+				Self::ensure_transferable(id, sender, &_to, amount)?;
 
 				Balances::<T>::try_mutate(&id, &sender, |balance| {
 					match balance {
@@ -208,6 +379,7 @@ pub mod pallet {
 					Some(x) => Balances::<T>::insert(&id, &_to, x + &amount),
 					None => Balances::<T>::insert(&id, &_to, &amount),
 				};
+				Self::note_nft_owner(id, &_to);
 			}
 			
 			Self::deposit_event(Event::<T>::TransferBatch {
@@ -228,6 +400,7 @@ pub mod pallet {
 
 			ensure!(Balances::<T>::get(&_id, &_from) >= Some(_amount), Error::<T>::NotEnoughOwned);
 			ensure!(Approvals::<T>::get(&_from, &_to) == Some(true), Error::<T>::NotApproved);
+			Self::ensure_transferable(_id, &_from, &_to, _amount)?;
 
 			Balances::<T>::try_mutate(&_id, &_from, |balance| {
 				match balance {
@@ -243,6 +416,7 @@ pub mod pallet {
 				Some(x) => Balances::<T>::insert(&_id,& _to, x + _amount),
 				None => Balances::<T>::insert(&_id, &_to, _amount),
 			};
+			Self::note_nft_owner(_id, &_to);
 
 			Self::deposit_event(Event::<T>::TransferSingle {
 				operator: sender.clone(),
@@ -265,6 +439,7 @@ pub mod pallet {
 			for it in _id.iter().zip(_amount.iter()) {
 				let (&id, &amount) = it;
 				ensure!(Balances::<T>::get(id, &_from) >= Some(amount), Error::<T>::NotEnoughOwned);
+				Self::ensure_transferable(id, &_from, &_to, amount)?;
 
 				Balances::<T>::try_mutate(id, &_from, |balance| {
 					match balance {
@@ -280,6 +455,7 @@ pub mod pallet {
 					Some(x) => Balances::<T>::insert(id, &_to, x + amount),
 					None => Balances::<T>::insert(id, &_to, amount),
 				};
+				Self::note_nft_owner(id, &_to);
 			}
 
 			Self::deposit_event(Event::<T>::TransferBatch {
@@ -292,6 +468,338 @@ pub mod pallet {
 			
 			Ok(())
 		}
-	
+
+		/// Locks `amount` of the native currency, minting as many `NativeTokenId` tokens.
+		#[pallet::weight(<T as Config>::WeightInfo::wrap())]
+		pub fn wrap(origin: OriginFor<T>, amount: u64) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			let id = T::NativeTokenId::get();
+			let balance = Balances::<T>::get(id, sender).unwrap_or(0).checked_add(amount).ok_or(Error::<T>::Overflow)?;
+			let supply = WrappedSupply::<T>::get().checked_add(amount).ok_or(Error::<T>::Overflow)?;
+
+			T::Currency::transfer(sender, &Self::account_id(), amount.saturated_into(), ExistenceRequirement::KeepAlive)?;
+			Balances::<T>::insert(id, sender, balance);
+			WrappedSupply::<T>::put(supply);
+
+			Self::deposit_event(Event::<T>::Wrapped { account: sender.clone(), value: amount });
+
+			Ok(())
+		}
+
+		/// Burns `amount` of `NativeTokenId` tokens, releasing as much of the native currency.
+		#[pallet::weight(<T as Config>::WeightInfo::unwrap())]
+		pub fn unwrap(origin: OriginFor<T>, amount: u64) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			Self::do_unwrap(sender, sender, amount)
+		}
+
+		/// Creates the token id `id`, at least `FirstIssuedTokenId` and never minted before,
+		/// reserving `AssetDeposit` from the sender, who becomes its issuer and the only account
+		/// minting it.
+		#[pallet::weight(<T as Config>::WeightInfo::create_asset())]
+		pub fn create_asset(origin: OriginFor<T>, id: u64) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			ensure!(id >= T::FirstIssuedTokenId::get() && id != T::NativeTokenId::get(), Error::<T>::ReservedTokenId);
+			ensure!(!Issuers::<T>::contains_key(id) && TotalSupply::<T>::get(id) == 0, Error::<T>::TokenIdInUse);
+
+			T::Currency::reserve(sender, T::AssetDeposit::get())?;
+			Issuers::<T>::insert(id, sender);
+			Self::deposit_event(Event::<T>::AssetCreated { id, issuer: sender.clone() });
+
+			Ok(())
+		}
+
+		/// Blocks `account` from transferring its balance of `id`. Only the issuer of `id` may.
+		#[pallet::weight(<T as Config>::WeightInfo::freeze())]
+		pub fn freeze(origin: OriginFor<T>, id: u64, account: T::AccountId) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			Self::ensure_issuer(id, sender)?;
+
+			FrozenBalances::<T>::insert(id, &account, true);
+			Self::deposit_event(Event::<T>::Frozen { id, account });
+
+			Ok(())
+		}
+
+		/// Lets `account` transfer its balance of `id` again.
+		#[pallet::weight(<T as Config>::WeightInfo::thaw())]
+		pub fn thaw(origin: OriginFor<T>, id: u64, account: T::AccountId) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			Self::ensure_issuer(id, sender)?;
+
+			FrozenBalances::<T>::remove(id, &account);
+			Self::deposit_event(Event::<T>::Thawed { id, account });
+
+			Ok(())
+		}
+
+		/// Blocks all transfers of `id`. Only its issuer may.
+		#[pallet::weight(<T as Config>::WeightInfo::freeze_asset())]
+		pub fn freeze_asset(origin: OriginFor<T>, id: u64) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			Self::ensure_issuer(id, sender)?;
+
+			FrozenAssets::<T>::insert(id, true);
+			Self::deposit_event(Event::<T>::AssetFrozen { id });
+
+			Ok(())
+		}
+
+		/// Allows transfers of `id` again.
+		#[pallet::weight(<T as Config>::WeightInfo::thaw_asset())]
+		pub fn thaw_asset(origin: OriginFor<T>, id: u64) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			Self::ensure_issuer(id, sender)?;
+
+			FrozenAssets::<T>::remove(id);
+			Self::deposit_event(Event::<T>::AssetThawed { id });
+
+			Ok(())
+		}
+
+		/// Restricts transfers of `id` to allow-listed accounts, issuer included. Only its issuer
+		/// may, and it only has effect with `AllowList` as `OnTransfer`.
+		#[pallet::weight(<T as Config>::WeightInfo::set_restricted())]
+		pub fn set_restricted(origin: OriginFor<T>, id: u64, restricted: bool) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			Self::ensure_issuer(id, sender)?;
+
+			RestrictedAssets::<T>::insert(id, restricted);
+			Self::deposit_event(Event::<T>::RestrictionSet { id, restricted });
+
+			Ok(())
+		}
+
+		/// Adds `account` to the allow list of `id`, or removes it. Only the issuer of `id` may.
+		#[pallet::weight(<T as Config>::WeightInfo::set_allowed())]
+		pub fn set_allowed(origin: OriginFor<T>, id: u64, account: T::AccountId, allowed: bool) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			Self::ensure_issuer(id, sender)?;
+
+			AllowLists::<T>::insert(id, &account, allowed);
+			Self::deposit_event(Event::<T>::AllowListSet { id, account, allowed });
+
+			Ok(())
+		}
+
+		/// Transfers `amount` of `id` from `from`, the sender or an account approving it, to `to`
+		/// which must accept it along with `data` through `OnReceived`.
+		#[pallet::weight(<T as Config>::WeightInfo::safe_transfer_from())]
+		pub fn safe_transfer_from(origin: OriginFor<T>, from: T::AccountId, to: T::AccountId, id: u64, amount: u64, data: Vec<u8>) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			Self::ensure_operator(&from, sender)?;
+
+			Self::do_transfer(id, &from, &to, amount)?;
+			T::OnReceived::on_received(sender, &from, &to, id, amount, &data)?;
+
+			Self::deposit_event(Event::<T>::TransferSingle {
+				operator: sender.clone(),
+				from,
+				to,
+				id,
+				value: amount
+			});
+
+			Ok(())
+		}
+
+		/// Batch version of `safe_transfer_from`, the recipient accepting all of it or nothing.
+		#[pallet::weight(<T as Config>::WeightInfo::safe_batch_transfer_from(ids.len() as u64))]
+		pub fn safe_batch_transfer_from(origin: OriginFor<T>, from: T::AccountId, to: T::AccountId, ids: Vec<u64>, amounts: Vec<u64>, data: Vec<u8>) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			ensure!(ids.len() == amounts.len(), Error::<T>::ShouldBeSameLength);
+			Self::ensure_operator(&from, sender)?;
+
+			for (&id, &amount) in ids.iter().zip(amounts.iter()) {
+				Self::do_transfer(id, &from, &to, amount)?;
+			}
+			T::OnReceived::on_batch_received(sender, &from, &to, &ids, &amounts, &data)?;
+
+			Self::deposit_event(Event::<T>::TransferBatch {
+				operator: sender.clone(),
+				from,
+				to,
+				id: ids,
+				value: amounts
+			});
+
+			Ok(())
+		}
+
+		/// Creates the NFT collection `collection`, owned by the sender.
+		#[pallet::weight(<T as Config>::WeightInfo::create_collection())]
+		pub fn create_collection(origin: OriginFor<T>, collection: u64) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			ensure!(!Collections::<T>::contains_key(collection), Error::<T>::CollectionExists);
+
+			Collections::<T>::insert(collection, sender);
+			Self::deposit_event(Event::<T>::CollectionCreated { collection, owner: sender.clone() });
+
+			Ok(())
+		}
+
+		/// Mints the single token of `id`, at least `FirstIssuedTokenId` and never minted before,
+		/// as an item of `collection`. Only the owner of the collection may, becoming the issuer
+		/// of `id`.
+		#[pallet::weight(<T as Config>::WeightInfo::mint_nft())]
+		pub fn mint_nft(origin: OriginFor<T>, collection: u64, id: u64) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			ensure!(Collections::<T>::get(collection).ok_or(Error::<T>::NoCollection)? == *sender, Error::<T>::NotCollectionOwner);
+			ensure!(id >= T::FirstIssuedTokenId::get() && id != T::NativeTokenId::get(), Error::<T>::ReservedTokenId);
+			ensure!(!Issuers::<T>::contains_key(id) && TotalSupply::<T>::get(id) == 0, Error::<T>::TokenIdInUse);
+
+			Issuers::<T>::insert(id, sender);
+			TotalSupply::<T>::insert(id, 1);
+			Balances::<T>::insert(id, sender, 1);
+			NftItems::<T>::insert(id, NftItem { collection, owner: sender.clone() });
+			Self::deposit_event(Event::<T>::NftMinted { collection, id, owner: sender.clone() });
+
+			Ok(())
+		}
+
+		/// Sets the metadata of the NFT `id`. Only the owner of its collection may.
+		#[pallet::weight(<T as Config>::WeightInfo::set_metadata())]
+		pub fn set_metadata(origin: OriginFor<T>, id: u64, data: Vec<u8>) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			Self::ensure_collection_owner(id, sender)?;
+			let bounded: BoundedVec<u8, T::StringLimit> = data.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
+
+			ItemMetadata::<T>::insert(id, bounded);
+			Self::deposit_event(Event::<T>::MetadataSet { id, data });
+
+			Ok(())
+		}
+
+		/// Sets the attribute `key` of the NFT `id` to `value`. Only the owner of its collection may.
+		#[pallet::weight(<T as Config>::WeightInfo::set_attribute())]
+		pub fn set_attribute(origin: OriginFor<T>, id: u64, key: Vec<u8>, value: Vec<u8>) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			Self::ensure_collection_owner(id, sender)?;
+			let bounded_key: BoundedVec<u8, T::StringLimit> = key.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
+			let bounded_value: BoundedVec<u8, T::StringLimit> = value.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
+
+			Attributes::<T>::insert(id, bounded_key, bounded_value);
+			Self::deposit_event(Event::<T>::AttributeSet { id, key, value });
+
+			Ok(())
+		}
+
+		/// Removes the attribute `key` of the NFT `id`. Only the owner of its collection may.
+		#[pallet::weight(<T as Config>::WeightInfo::clear_attribute())]
+		pub fn clear_attribute(origin: OriginFor<T>, id: u64, key: Vec<u8>) -> DispatchResult {
+
+			let sender = &ensure_signed(origin)?;
+			Self::ensure_collection_owner(id, sender)?;
+			let bounded_key: BoundedVec<u8, T::StringLimit> = key.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
+
+			Attributes::<T>::remove(id, bounded_key);
+			Self::deposit_event(Event::<T>::AttributeCleared { id, key });
+
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Account holding the native currency backing wrapped tokens.
+		pub fn account_id() -> T::AccountId {
+			T::PalletId::get().into_account_truncating()
+		}
+
+		/// Burns `amount` of the wrapped tokens of `from`, paying the native currency backing
+		/// them to `to`.
+		fn do_unwrap(from: &T::AccountId, to: &T::AccountId, amount: u64) -> DispatchResult {
+			let id = T::NativeTokenId::get();
+			ensure!(Balances::<T>::get(id, from) >= Some(amount), Error::<T>::NotEnoughOwned);
+
+			Balances::<T>::mutate(id, from, |balance| *balance = balance.map(|x| x - amount));
+			WrappedSupply::<T>::mutate(|supply| *supply -= amount);
+			T::Currency::transfer(&Self::account_id(), to, amount.saturated_into(), ExistenceRequirement::AllowDeath)?;
+
+			Self::deposit_event(Event::<T>::Unwrapped { account: from.clone(), value: amount });
+			Ok(())
+		}
+
+		/// Adds `amount` to the supply of `id` minted by `account`, which must be its issuer if
+		/// it has one. Ids from `FirstIssuedTokenId` on are only minted once they have one.
+		pub(crate) fn increase_supply(id: u64, account: &T::AccountId, amount: u64) -> DispatchResult {
+			ensure!(id != T::NativeTokenId::get(), Error::<T>::ReservedTokenId);
+			ensure!(!NftItems::<T>::contains_key(id), Error::<T>::NonFungible);
+			match Issuers::<T>::get(id) {
+				Some(issuer) => ensure!(issuer == *account, Error::<T>::NotIssuer),
+				None => ensure!(id < T::FirstIssuedTokenId::get(), Error::<T>::NotIssuer),
+			}
+			TotalSupply::<T>::try_mutate(id, |supply| {
+				*supply = supply.checked_add(amount).ok_or(Error::<T>::Overflow)?;
+				Ok(())
+			})
+		}
+
+		pub(crate) fn ensure_issuer(id: u64, account: &T::AccountId) -> DispatchResult {
+			ensure!(Issuers::<T>::get(id).as_ref() == Some(account), Error::<T>::NotIssuer);
+			Ok(())
+		}
+
+		/// Checks that NFTs move one at a time, that neither `id` nor the balance of it held by
+		/// `from` is frozen and that `OnTransfer` lets `amount` of it move to `to`.
+		pub(crate) fn ensure_transferable(id: u64, from: &T::AccountId, to: &T::AccountId, amount: u64) -> DispatchResult {
+			ensure!(amount == 1 || !NftItems::<T>::contains_key(id), Error::<T>::NonFungible);
+			ensure!(!FrozenAssets::<T>::get(id), Error::<T>::AssetFrozen);
+			ensure!(!FrozenBalances::<T>::get(id, from), Error::<T>::BalanceFrozen);
+			T::OnTransfer::on_transfer(id, from, to, amount)
+		}
+
+		/// Owner of the NFT `id`, `None` for fungible ids.
+		pub fn owner_of(id: u64) -> Option<T::AccountId> {
+			NftItems::<T>::get(id).map(|item| item.owner)
+		}
+
+		pub(crate) fn ensure_collection_owner(id: u64, account: &T::AccountId) -> DispatchResult {
+			let item = NftItems::<T>::get(id).ok_or(Error::<T>::NoNft)?;
+			ensure!(Collections::<T>::get(item.collection).as_ref() == Some(account), Error::<T>::NotCollectionOwner);
+			Ok(())
+		}
+
+		/// Records `to` as the owner of `id` after it received it, if `id` is an NFT.
+		pub(crate) fn note_nft_owner(id: u64, to: &T::AccountId) {
+			if let Some(mut item) = NftItems::<T>::get(id) {
+				item.owner = to.clone();
+				NftItems::<T>::insert(id, item);
+			}
+		}
+
+		pub(crate) fn ensure_operator(from: &T::AccountId, operator: &T::AccountId) -> DispatchResult {
+			ensure!(from == operator || Approvals::<T>::get(from, operator) == Some(true), Error::<T>::NotApproved);
+			Ok(())
+		}
+
+		/// Moves `amount` of `id` from `from` to `to`.
+		pub(crate) fn do_transfer(id: u64, from: &T::AccountId, to: &T::AccountId, amount: u64) -> DispatchResult {
+			ensure!(Balances::<T>::get(id, from) >= Some(amount), Error::<T>::NotEnoughOwned);
+			Self::ensure_transferable(id, from, to, amount)?;
+			Self::move_balance(id, from, to, amount);
+			Ok(())
+		}
+
+		/// Moves `amount` of `id` from `from` to `to` without any checks.
+		pub(crate) fn move_balance(id: u64, from: &T::AccountId, to: &T::AccountId, amount: u64) {
+			Balances::<T>::mutate(id, from, |balance| *balance = balance.map(|x| x - amount));
+			Balances::<T>::mutate(id, to, |balance| *balance = Some(balance.unwrap_or(0) + amount));
+			Self::note_nft_owner(id, to);
+		}
 	}
 }
\ No newline at end of file
@@ -0,0 +1,43 @@
+use crate::{
+	AllowLists, Balances, Config, FrozenAssets, FrozenBalances, Issuers, NftItems, Pallet, RestrictedAssets,
+	TotalSupply, STORAGE_VERSION,
+};
+use frame_support::{
+	traits::{Get, GetStorageVersion},
+	weights::Weight,
+};
+use sp_std::vec::Vec;
+
+/// Computes `TotalSupply` from the balances minted so far and drops the issuers earlier versions
+/// gave the first minter of an id, with the freezes and restrictions they set. Those ids stay
+/// mintable by anyone and can't be frozen; NFTs keep the owner of their collection as issuer.
+pub fn migrate_to_v1<T: Config>() -> Weight {
+	if Pallet::<T>::on_chain_storage_version() >= STORAGE_VERSION {
+		return 0
+	}
+
+	let native_token_id = T::NativeTokenId::get();
+	let mut reads: u64 = 0;
+	let mut writes: u64 = 0;
+	for (id, _, balance) in Balances::<T>::iter() {
+		reads += 1;
+		if id != native_token_id && !NftItems::<T>::contains_key(id) {
+			TotalSupply::<T>::mutate(id, |supply| *supply = supply.saturating_add(balance));
+			writes += 1;
+		}
+	}
+
+	let squatted: Vec<u64> = Issuers::<T>::iter_keys().filter(|id| !NftItems::<T>::contains_key(id)).collect();
+	reads += squatted.len() as u64;
+	for id in squatted {
+		Issuers::<T>::remove(id);
+		FrozenAssets::<T>::remove(id);
+		RestrictedAssets::<T>::remove(id);
+		let _ = FrozenBalances::<T>::clear_prefix(id, u32::MAX, None);
+		let _ = AllowLists::<T>::clear_prefix(id, u32::MAX, None);
+		writes += 5;
+	}
+
+	STORAGE_VERSION.put::<Pallet<T>>();
+	T::DbWeight::get().reads_writes(reads + 1, writes + 1)
+}
@@ -1,16 +1,24 @@
 use crate as pallet_multi_token;
-use frame_support::traits::{ConstU16, ConstU64};
+use frame_support::{ensure, pallet_prelude::DispatchResult, traits::{ConstU16, ConstU64, Contains}, PalletId};
 use frame_system as system;
 use sp_core::H256;
 use sp_runtime::{
 	testing::Header,
 	traits::{BlakeTwo256, IdentityLookup},
+	DispatchError,
 };
-use crate::weights;
+use crate::{transfer_hook::OnReceived, weights};
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
 
+frame_support::parameter_types! {
+	pub const NativeTokenId: u64 = u64::MAX;
+	pub const FirstIssuedTokenId: u64 = 100;
+	pub const MultiTokenPalletId: PalletId = PalletId(*b"py/mtokn");
+	pub const AssetDeposit: u64 = 10;
+}
+
 // Configure a mock runtime to test the pallet.
 frame_support::construct_runtime!(
 	pub enum Test where
@@ -19,6 +27,7 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system,
+		Balances: pallet_balances,
 		MultiToken: pallet_multi_token,
 	}
 );
@@ -41,7 +50,7 @@ impl system::Config for Test {
 	type BlockHashCount = ConstU64<250>;
 	type Version = ();
 	type PalletInfo = PalletInfo;
-	type AccountData = ();
+	type AccountData = pallet_balances::AccountData<u64>;
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 	type SystemWeightInfo = ();
@@ -50,12 +59,63 @@ impl system::Config for Test {
 	type MaxConsumers = frame_support::traits::ConstU32<16>;
 }
 
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU64<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
 impl pallet_multi_token::Config for Test {
 	type Event = Event;
 	type WeightInfo = weights::SubstrateWeight<Test>;
+	type Currency = Balances;
+	type NativeTokenId = NativeTokenId;
+	type PalletId = MultiTokenPalletId;
+	type FirstIssuedTokenId = FirstIssuedTokenId;
+	type AssetDeposit = AssetDeposit;
+	type OnTransfer = pallet_multi_token::AllowList<Test>;
+	type ExemptAccounts = Escrow;
+	type OnReceived = Receiver;
+	type StringLimit = frame_support::traits::ConstU32<8>;
 }
 
+/// Accepts any transfer, except to `REJECTING_ACCOUNT` or with the data `b"reject"`.
+pub struct Receiver;
+impl OnReceived<u64> for Receiver {
+	fn on_received(_operator: &u64, _from: &u64, to: &u64, _id: u64, _amount: u64, data: &[u8]) -> DispatchResult {
+		ensure!(*to != REJECTING_ACCOUNT && data != b"reject", DispatchError::Other("rejected"));
+		Ok(())
+	}
+
+	fn on_batch_received(_operator: &u64, _from: &u64, to: &u64, _ids: &[u64], _amounts: &[u64], data: &[u8]) -> DispatchResult {
+		ensure!(*to != REJECTING_ACCOUNT && data != b"reject", DispatchError::Other("rejected"));
+		Ok(())
+	}
+}
+
+pub const REJECTING_ACCOUNT: u64 = 666;
+
+/// Pays out regardless of freezes and allow lists.
+pub struct Escrow;
+impl Contains<u64> for Escrow {
+	fn contains(who: &u64) -> bool {
+		*who == ESCROW_ACCOUNT
+	}
+}
+
+pub const ESCROW_ACCOUNT: u64 = 777;
+
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	let mut storage = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> { balances: vec![(frame_benchmarking::whitelisted_caller(), 1_000)] }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+	storage.into()
 }
\ No newline at end of file
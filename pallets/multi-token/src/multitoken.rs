@@ -3,5 +3,11 @@ use frame_support::pallet_prelude::DispatchResult;
 pub trait MultiToken<AccountId> {
 	fn balances(id: &u64, account: &AccountId) -> u64;
 	fn transfer_to(_origin: &AccountId, _to: &AccountId, _id: &u64, _amount: &u64) -> DispatchResult;
+	/// Returns `_amount` of `_id` an escrow holds for `_to` back to it. Escrows exempted by
+	/// the token pallet skip freezes and transfer checks here, so callers must only ever pay
+	/// the account the tokens were deposited by.
+	fn refund(_origin: &AccountId, _to: &AccountId, _id: &u64, _amount: &u64) -> DispatchResult;
 	fn mint(_origin: &AccountId, _id: &u64, _amount: &u64) -> DispatchResult;
+	/// Burns `_amount` of the wrapped native currency of `_origin`, releasing as much to `_to`.
+	fn unwrap_to(_origin: &AccountId, _to: &AccountId, _amount: &u64) -> DispatchResult;
 }
\ No newline at end of file
@@ -183,3 +183,331 @@ fn should_revert_transfer_batch_from_if_not_enough_value() {
 		);
 	});	
 }
+
+#[test]
+fn should_wrap_and_unwrap_native_currency() {
+	new_test_ext().execute_with(|| {
+		let caller: AccountId = whitelisted_caller();
+		assert_ok!(MultiToken::wrap(Origin::signed(caller), 600));
+		assert_eq!(MultiToken::balances(NativeTokenId::get(), caller), Some(600));
+		assert_eq!(Balances::free_balance(caller), 400);
+		assert_eq!(Balances::free_balance(MultiToken::account_id()), 600);
+
+		assert_ok!(MultiToken::transfer_to(Origin::signed(caller), 2, NativeTokenId::get(), 100));
+		assert_ok!(MultiToken::unwrap(Origin::signed(2), 100));
+		assert_eq!(Balances::free_balance(2), 100);
+		assert_noop!(MultiToken::unwrap(Origin::signed(caller), 501), Error::<Test>::NotEnoughOwned);
+		assert_ok!(MultiToken::unwrap(Origin::signed(caller), 500));
+
+		assert_eq!(MultiToken::wrapped_supply(), 0);
+		assert_eq!(Balances::free_balance(MultiToken::account_id()), 0);
+		assert_eq!(Balances::free_balance(caller), 900);
+	});
+}
+
+#[test]
+fn should_revert_wrap_overflowing_supply() {
+	new_test_ext().execute_with(|| {
+		let caller: AccountId = whitelisted_caller();
+		crate::WrappedSupply::<Test>::put(u64::MAX - 100);
+		assert_noop!(MultiToken::wrap(Origin::signed(caller), 600), Error::<Test>::Overflow);
+		crate::WrappedSupply::<Test>::put(0);
+		crate::Balances::<Test>::insert(NativeTokenId::get(), caller, u64::MAX - 100);
+		assert_noop!(MultiToken::wrap(Origin::signed(caller), 600), Error::<Test>::Overflow);
+		assert_eq!(Balances::free_balance(caller), 1_000);
+	});
+}
+
+#[test]
+fn should_revert_mint_of_wrapped_native_currency() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			MultiToken::mint(Origin::signed(whitelisted_caller()), NativeTokenId::get(), 10),
+			Error::<Test>::ReservedTokenId
+		);
+		assert_noop!(
+			MultiToken::mint_batch(Origin::signed(whitelisted_caller()), [0, NativeTokenId::get()].to_vec(), [2, 10].to_vec()),
+			Error::<Test>::ReservedTokenId
+		);
+	});
+}
+
+#[test]
+fn should_create_asset_only_minted_by_its_issuer() {
+	new_test_ext().execute_with(|| {
+		let caller: AccountId = whitelisted_caller();
+		let recipient: AccountId = account("recipient", 0, 1);
+		assert_ok!(MultiToken::mint(Origin::signed(recipient), 0, 0));
+		assert_noop!(MultiToken::freeze_asset(Origin::signed(recipient), 0), Error::<Test>::NotIssuer);
+		assert_noop!(MultiToken::create_asset(Origin::signed(caller), 0), Error::<Test>::ReservedTokenId);
+		// Ids left to issuers can't be minted before they are created.
+		assert_noop!(MultiToken::mint(Origin::signed(recipient), 100, 1), Error::<Test>::NotIssuer);
+
+		assert_ok!(MultiToken::create_asset(Origin::signed(caller), 100));
+		assert_eq!(Balances::reserved_balance(caller), AssetDeposit::get());
+		assert_eq!(MultiToken::issuer(100), Some(caller));
+		assert_noop!(MultiToken::create_asset(Origin::signed(recipient), 100), Error::<Test>::TokenIdInUse);
+		assert_noop!(MultiToken::mint(Origin::signed(recipient), 100, 10), Error::<Test>::NotIssuer);
+		assert_noop!(
+			MultiToken::mint_batch(Origin::signed(recipient), [1, 100].to_vec(), [10, 10].to_vec()),
+			Error::<Test>::NotIssuer
+		);
+		assert_ok!(MultiToken::mint(Origin::signed(caller), 100, 10));
+		assert_eq!(MultiToken::total_supply(100), 10);
+	});
+}
+
+#[test]
+fn should_migrate_supplies_and_drop_squatted_issuers() {
+	new_test_ext().execute_with(|| {
+		let caller: AccountId = whitelisted_caller();
+		let recipient: AccountId = account("recipient", 0, 1);
+		crate::Balances::<Test>::insert(3, caller, 10);
+		crate::Balances::<Test>::insert(3, recipient, 5);
+		crate::Issuers::<Test>::insert(3, recipient);
+		crate::FrozenAssets::<Test>::insert(3, true);
+
+		crate::migrations::migrate_to_v1::<Test>();
+		assert_eq!(MultiToken::total_supply(3), 15);
+		assert_eq!(MultiToken::issuer(3), None);
+		assert!(!MultiToken::is_asset_frozen(3));
+		assert_ok!(MultiToken::transfer_to(Origin::signed(recipient), caller, 3, 5));
+	});
+}
+
+#[test]
+fn should_freeze_and_thaw_balance() {
+	new_test_ext().execute_with(|| {
+		let caller: AccountId = whitelisted_caller();
+		let recipient: AccountId = account("recipient", 0, 1);
+		assert_ok!(MultiToken::create_asset(Origin::signed(caller), 100));
+		assert_ok!(MultiToken::mint(Origin::signed(caller), 100, 10));
+		assert_ok!(MultiToken::transfer_to(Origin::signed(caller), recipient, 100, 5));
+		assert_noop!(MultiToken::freeze(Origin::signed(recipient), 100, caller), Error::<Test>::NotIssuer);
+
+		assert_ok!(MultiToken::freeze(Origin::signed(caller), 100, recipient));
+		assert_noop!(MultiToken::transfer_to(Origin::signed(recipient), caller, 100, 1), Error::<Test>::BalanceFrozen);
+		assert_noop!(
+			MultiToken::transfer_batch_to(Origin::signed(recipient), caller, [100].to_vec(), [1].to_vec()),
+			Error::<Test>::BalanceFrozen
+		);
+		assert_noop!(
+			<MultiToken as crate::multitoken::MultiToken<AccountId>>::transfer_to(&recipient, &caller, &100, &1),
+			Error::<Test>::BalanceFrozen
+		);
+		// The frozen account still receives.
+		assert_ok!(MultiToken::transfer_to(Origin::signed(caller), recipient, 100, 1));
+
+		assert_ok!(MultiToken::thaw(Origin::signed(caller), 100, recipient));
+		assert_ok!(MultiToken::transfer_to(Origin::signed(recipient), caller, 100, 6));
+		assert_eq!(MultiToken::balances(100, caller), Some(10));
+	});
+}
+
+#[test]
+fn should_freeze_and_thaw_asset() {
+	new_test_ext().execute_with(|| {
+		let caller: AccountId = whitelisted_caller();
+		let recipient: AccountId = account("recipient", 0, 1);
+		assert_ok!(MultiToken::create_asset(Origin::signed(caller), 101));
+		assert_ok!(MultiToken::mint_batch(Origin::signed(caller), [0, 101].to_vec(), [10, 10].to_vec()));
+		assert_ok!(MultiToken::set_approval(Origin::signed(caller), recipient, true));
+		assert_ok!(MultiToken::freeze_asset(Origin::signed(caller), 101));
+
+		assert_noop!(MultiToken::transfer_to(Origin::signed(caller), recipient, 101, 1), Error::<Test>::AssetFrozen);
+		assert_noop!(
+			MultiToken::transfer_from(Origin::signed(recipient), caller, recipient, 101, 1),
+			Error::<Test>::AssetFrozen
+		);
+		assert_noop!(
+			MultiToken::transfer_batch_from(Origin::signed(recipient), caller, recipient, [0, 101].to_vec(), [1, 1].to_vec()),
+			Error::<Test>::AssetFrozen
+		);
+		assert_ok!(MultiToken::transfer_to(Origin::signed(caller), recipient, 0, 1));
+
+		assert_noop!(MultiToken::thaw_asset(Origin::signed(recipient), 101), Error::<Test>::NotIssuer);
+		assert_ok!(MultiToken::thaw_asset(Origin::signed(caller), 101));
+		assert_ok!(MultiToken::transfer_batch_to(Origin::signed(caller), recipient, [0, 101].to_vec(), [1, 1].to_vec()));
+		assert_eq!(MultiToken::balances(101, recipient), Some(1));
+	});
+}
+
+#[test]
+fn should_restrict_transfers_to_allow_list() {
+	new_test_ext().execute_with(|| {
+		let caller: AccountId = whitelisted_caller();
+		let recipient: AccountId = account("recipient", 0, 1);
+		assert_ok!(MultiToken::create_asset(Origin::signed(caller), 100));
+		assert_ok!(MultiToken::mint(Origin::signed(caller), 100, 10));
+		assert_noop!(MultiToken::set_restricted(Origin::signed(recipient), 100, true), Error::<Test>::NotIssuer);
+		assert_ok!(MultiToken::set_restricted(Origin::signed(caller), 100, true));
+		assert_ok!(MultiToken::set_allowed(Origin::signed(caller), 100, caller, true));
+
+		assert_noop!(MultiToken::transfer_to(Origin::signed(caller), recipient, 100, 1), Error::<Test>::NotAllowListed);
+		assert_noop!(
+			<MultiToken as crate::multitoken::MultiToken<AccountId>>::transfer_to(&caller, &recipient, &100, &1),
+			Error::<Test>::NotAllowListed
+		);
+
+		assert_ok!(MultiToken::set_allowed(Origin::signed(caller), 100, recipient, true));
+		assert_ok!(MultiToken::transfer_to(Origin::signed(caller), recipient, 100, 4));
+
+		assert_ok!(MultiToken::set_allowed(Origin::signed(caller), 100, caller, false));
+		assert_noop!(MultiToken::transfer_to(Origin::signed(recipient), caller, 100, 1), Error::<Test>::NotAllowListed);
+		assert_ok!(MultiToken::set_restricted(Origin::signed(caller), 100, false));
+		assert_ok!(MultiToken::transfer_to(Origin::signed(recipient), caller, 100, 1));
+		assert_eq!(MultiToken::balances(100, recipient), Some(3));
+	});
+}
+
+#[test]
+fn should_safe_transfer_when_recipient_accepts() {
+	new_test_ext().execute_with(|| {
+		let caller: AccountId = whitelisted_caller();
+		let recipient: AccountId = account("recipient", 0, 1);
+		assert_ok!(MultiToken::mint_batch(Origin::signed(caller), [0, 1].to_vec(), [10, 10].to_vec()));
+		assert_noop!(
+			MultiToken::safe_transfer_from(Origin::signed(recipient), caller, recipient, 0, 1, Vec::new()),
+			Error::<Test>::NotApproved
+		);
+
+		assert_ok!(MultiToken::safe_transfer_from(Origin::signed(caller), caller, recipient, 0, 4, b"hello".to_vec()));
+		assert_ok!(MultiToken::set_approval(Origin::signed(caller), recipient, true));
+		assert_ok!(MultiToken::safe_batch_transfer_from(
+			Origin::signed(recipient),
+			caller,
+			recipient,
+			[0, 1].to_vec(),
+			[1, 2].to_vec(),
+			Vec::new()
+		));
+		assert_eq!(MultiToken::balances(0, recipient), Some(5));
+		assert_eq!(MultiToken::balances(1, recipient), Some(2));
+	});
+}
+
+#[test]
+fn should_revert_safe_transfer_rejected_by_recipient() {
+	new_test_ext().execute_with(|| {
+		let caller: AccountId = whitelisted_caller();
+		let recipient: AccountId = account("recipient", 0, 1);
+		assert_ok!(MultiToken::mint_batch(Origin::signed(caller), [0, 1].to_vec(), [10, 10].to_vec()));
+
+		assert_noop!(
+			MultiToken::safe_transfer_from(Origin::signed(caller), caller, REJECTING_ACCOUNT, 0, 1, Vec::new()),
+			sp_runtime::DispatchError::Other("rejected")
+		);
+		assert_noop!(
+			MultiToken::safe_batch_transfer_from(
+				Origin::signed(caller),
+				caller,
+				recipient,
+				[0, 1].to_vec(),
+				[1, 1].to_vec(),
+				b"reject".to_vec()
+			),
+			sp_runtime::DispatchError::Other("rejected")
+		);
+		assert_eq!(MultiToken::balances(0, caller), Some(10));
+		assert_eq!(MultiToken::balances(1, caller), Some(10));
+	});
+}
+
+#[test]
+fn should_mint_and_transfer_nft() {
+	new_test_ext().execute_with(|| {
+		let caller: AccountId = whitelisted_caller();
+		let recipient: AccountId = account("recipient", 0, 1);
+		assert_ok!(MultiToken::create_collection(Origin::signed(caller), 1));
+		assert_noop!(MultiToken::create_collection(Origin::signed(recipient), 1), Error::<Test>::CollectionExists);
+		assert_noop!(MultiToken::mint_nft(Origin::signed(recipient), 1, 107), Error::<Test>::NotCollectionOwner);
+		// Fungible ids can't be turned into NFTs, whether they are left to issuers or not.
+		assert_ok!(MultiToken::mint(Origin::signed(recipient), 0, 10));
+		assert_noop!(MultiToken::mint_nft(Origin::signed(caller), 1, 0), Error::<Test>::ReservedTokenId);
+		assert_ok!(MultiToken::create_asset(Origin::signed(caller), 103));
+		assert_noop!(MultiToken::mint_nft(Origin::signed(caller), 1, 103), Error::<Test>::TokenIdInUse);
+
+		assert_ok!(MultiToken::mint_nft(Origin::signed(caller), 1, 107));
+		assert_eq!(MultiToken::owner_of(107), Some(caller));
+		assert_eq!(MultiToken::owner_of(0), None);
+		assert_noop!(MultiToken::mint(Origin::signed(caller), 107, 1), Error::<Test>::NonFungible);
+		assert_noop!(MultiToken::mint_nft(Origin::signed(caller), 1, 107), Error::<Test>::TokenIdInUse);
+
+		assert_noop!(MultiToken::transfer_to(Origin::signed(caller), recipient, 107, 0), Error::<Test>::NonFungible);
+		assert_ok!(MultiToken::transfer_to(Origin::signed(caller), recipient, 107, 1));
+		assert_eq!(MultiToken::owner_of(107), Some(recipient));
+		assert_noop!(MultiToken::transfer_to(Origin::signed(caller), recipient, 107, 1), Error::<Test>::NotEnoughOwned);
+
+		assert_ok!(<MultiToken as crate::multitoken::MultiToken<AccountId>>::transfer_to(&recipient, &caller, &107, &1));
+		assert_eq!(MultiToken::owner_of(107), Some(caller));
+		assert_eq!(MultiToken::balances(107, caller), Some(1));
+		assert_eq!(MultiToken::balances(107, recipient), Some(0));
+	});
+}
+
+#[test]
+fn should_set_nft_metadata_and_attributes() {
+	new_test_ext().execute_with(|| {
+		let caller: AccountId = whitelisted_caller();
+		let recipient: AccountId = account("recipient", 0, 1);
+		assert_ok!(MultiToken::create_collection(Origin::signed(caller), 1));
+		assert_ok!(MultiToken::mint_nft(Origin::signed(caller), 1, 107));
+		assert_ok!(MultiToken::transfer_to(Origin::signed(caller), recipient, 107, 1));
+
+		assert_noop!(MultiToken::set_metadata(Origin::signed(recipient), 107, b"ipfs".to_vec()), Error::<Test>::NotCollectionOwner);
+		assert_noop!(MultiToken::set_metadata(Origin::signed(caller), 108, b"ipfs".to_vec()), Error::<Test>::NoNft);
+		assert_noop!(MultiToken::set_metadata(Origin::signed(caller), 107, b"too long!".to_vec()), Error::<Test>::TooLong);
+		assert_ok!(MultiToken::set_metadata(Origin::signed(caller), 107, b"ipfs".to_vec()));
+		assert_eq!(MultiToken::item_metadata(107).unwrap().into_inner(), b"ipfs".to_vec());
+
+		let key: frame_support::BoundedVec<u8, frame_support::traits::ConstU32<8>> = b"color".to_vec().try_into().unwrap();
+		assert_ok!(MultiToken::set_attribute(Origin::signed(caller), 107, b"color".to_vec(), b"red".to_vec()));
+		assert_eq!(MultiToken::attribute(107, &key).unwrap().into_inner(), b"red".to_vec());
+		assert_ok!(MultiToken::clear_attribute(Origin::signed(caller), 107, b"color".to_vec()));
+		assert_eq!(MultiToken::attribute(107, &key), None);
+	});
+}
+
+#[test]
+fn should_revert_restricting_token_without_issuer() {
+	new_test_ext().execute_with(|| {
+		let caller: AccountId = whitelisted_caller();
+		let recipient: AccountId = account("recipient", 0, 1);
+		assert_ok!(MultiToken::mint(Origin::signed(caller), 0, 10));
+		assert_ok!(MultiToken::mint(Origin::signed(recipient), 0, 0));
+
+		assert_noop!(MultiToken::set_restricted(Origin::signed(recipient), 0, true), Error::<Test>::NotIssuer);
+		assert_noop!(MultiToken::set_restricted(Origin::signed(caller), 0, true), Error::<Test>::NotIssuer);
+		assert_noop!(MultiToken::set_allowed(Origin::signed(recipient), 0, recipient, true), Error::<Test>::NotIssuer);
+		assert_noop!(MultiToken::create_asset(Origin::signed(recipient), 0), Error::<Test>::ReservedTokenId);
+		assert_ok!(MultiToken::transfer_to(Origin::signed(caller), recipient, 0, 10));
+	});
+}
+
+#[test]
+fn should_refund_out_of_exempt_accounts_despite_freezes_and_restrictions() {
+	new_test_ext().execute_with(|| {
+		let caller: AccountId = whitelisted_caller();
+		let recipient: AccountId = account("recipient", 0, 1);
+		assert_ok!(MultiToken::create_asset(Origin::signed(caller), 100));
+		assert_ok!(MultiToken::mint(Origin::signed(caller), 100, 10));
+		assert_ok!(MultiToken::transfer_to(Origin::signed(caller), ESCROW_ACCOUNT, 100, 10));
+		assert_ok!(MultiToken::freeze_asset(Origin::signed(caller), 100));
+		assert_ok!(MultiToken::freeze(Origin::signed(caller), 100, ESCROW_ACCOUNT));
+		assert_ok!(MultiToken::set_restricted(Origin::signed(caller), 100, true));
+
+		assert_ok!(<MultiToken as crate::multitoken::MultiToken<AccountId>>::refund(&ESCROW_ACCOUNT, &recipient, &100, &4));
+		assert_noop!(MultiToken::transfer_to(Origin::signed(recipient), ESCROW_ACCOUNT, 100, 1), Error::<Test>::AssetFrozen);
+		assert_eq!(MultiToken::balances(100, recipient), Some(4));
+
+		// Anything but a refund out of the escrow is checked like any other transfer.
+		assert_noop!(
+			<MultiToken as crate::multitoken::MultiToken<AccountId>>::transfer_to(&ESCROW_ACCOUNT, &recipient, &100, &4),
+			Error::<Test>::AssetFrozen
+		);
+		assert_noop!(
+			<MultiToken as crate::multitoken::MultiToken<AccountId>>::refund(&recipient, &caller, &100, &4),
+			Error::<Test>::AssetFrozen
+		);
+	});
+}
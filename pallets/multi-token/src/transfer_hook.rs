@@ -0,0 +1,47 @@
+use frame_support::pallet_prelude::DispatchResult;
+
+/// Called before any balance of a token moves between accounts.
+pub trait TransferHook<AccountId> {
+	/// Vetoes moving `amount` of `id` from `from` to `to` by returning the reason as an error.
+	fn on_transfer(id: u64, from: &AccountId, to: &AccountId, amount: u64) -> DispatchResult;
+}
+
+impl<AccountId> TransferHook<AccountId> for () {
+	fn on_transfer(_id: u64, _from: &AccountId, _to: &AccountId, _amount: u64) -> DispatchResult {
+		Ok(())
+	}
+}
+
+/// Acknowledges tokens received through the safe transfers, as ERC-1155 receivers do.
+pub trait OnReceived<AccountId> {
+	/// Accepts `amount` of `id` sent by `operator` from `from` to `to`, with `data` from the
+	/// sender. An error rejects it, reverting the transfer.
+	fn on_received(operator: &AccountId, from: &AccountId, to: &AccountId, id: u64, amount: u64, data: &[u8]) -> DispatchResult;
+
+	/// Accepts a batch of `amounts` of `ids`, like `on_received`.
+	fn on_batch_received(
+		operator: &AccountId,
+		from: &AccountId,
+		to: &AccountId,
+		ids: &[u64],
+		amounts: &[u64],
+		data: &[u8],
+	) -> DispatchResult;
+}
+
+impl<AccountId> OnReceived<AccountId> for () {
+	fn on_received(_operator: &AccountId, _from: &AccountId, _to: &AccountId, _id: u64, _amount: u64, _data: &[u8]) -> DispatchResult {
+		Ok(())
+	}
+
+	fn on_batch_received(
+		_operator: &AccountId,
+		_from: &AccountId,
+		_to: &AccountId,
+		_ids: &[u64],
+		_amounts: &[u64],
+		_data: &[u8],
+	) -> DispatchResult {
+		Ok(())
+	}
+}
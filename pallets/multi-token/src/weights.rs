@@ -38,6 +38,22 @@ pub trait WeightInfo {
 	fn transfer_batch_to(l: u64, ) -> Weight;
 	fn transfer_from() -> Weight;
 	fn transfer_batch_from(l: u64, ) -> Weight;
+	fn wrap() -> Weight;
+	fn unwrap() -> Weight;
+	fn create_asset() -> Weight;
+	fn freeze() -> Weight;
+	fn thaw() -> Weight;
+	fn freeze_asset() -> Weight;
+	fn thaw_asset() -> Weight;
+	fn set_restricted() -> Weight;
+	fn set_allowed() -> Weight;
+	fn safe_transfer_from() -> Weight;
+	fn safe_batch_transfer_from(l: u64, ) -> Weight;
+	fn create_collection() -> Weight;
+	fn mint_nft() -> Weight;
+	fn set_metadata() -> Weight;
+	fn set_attribute() -> Weight;
+	fn clear_attribute() -> Weight;
 }
 
 /// Weight functions for `pallet_template`.
@@ -48,20 +64,26 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 		(14_500_000 as Weight)
 			.saturating_add(T::DbWeight::get().writes(1 as Weight))
 	}
+	// Storage: TemplateModule NftItems (r:1 w:0)
+	// Storage: TemplateModule Issuers (r:1 w:0)
+	// Storage: TemplateModule TotalSupply (r:1 w:1)
 	// Storage: TemplateModule Balances (r:1 w:1)
 	fn mint() -> Weight {
-		(15_300_000 as Weight)
-			.saturating_add(T::DbWeight::get().reads(1 as Weight))
-			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+		(21_300_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
 	}
+	// Storage: TemplateModule NftItems (r:1 w:0)
+	// Storage: TemplateModule Issuers (r:1 w:0)
+	// Storage: TemplateModule TotalSupply (r:1 w:1)
 	// Storage: TemplateModule Balances (r:1 w:1)
 	/// The range of component `l` is `[0, 10]`.
 	fn mint_batch(l: u64, ) -> Weight {
 		(13_221_000 as Weight)
 			// Standard Error: 47_000
-			.saturating_add((1_495_000 as Weight).saturating_mul(l as Weight))
-			.saturating_add(T::DbWeight::get().reads(1 as Weight))
-			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+			.saturating_add((4_495_000 as Weight).saturating_mul(l as Weight))
+			.saturating_add(T::DbWeight::get().reads((4 as Weight).saturating_mul(l as Weight)))
+			.saturating_add(T::DbWeight::get().writes((2 as Weight).saturating_mul(l as Weight)))
 	}
 	// Storage: TemplateModule Balances (r:2 w:2)
 	fn transfer_to() -> Weight {
@@ -95,4 +117,132 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 			.saturating_add(T::DbWeight::get().reads(2 as Weight))
 			.saturating_add(T::DbWeight::get().writes(1 as Weight))
 	}
+	// Storage: System Account (r:2 w:2)
+	// Storage: TemplateModule Balances (r:1 w:1)
+	// Storage: TemplateModule WrappedSupply (r:1 w:1)
+	fn wrap() -> Weight {
+		(38_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	// Storage: TemplateModule Balances (r:1 w:1)
+	// Storage: TemplateModule WrappedSupply (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	fn unwrap() -> Weight {
+		(38_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	// Storage: TemplateModule Issuers (r:1 w:1)
+	// Storage: TemplateModule TotalSupply (r:1 w:0)
+	// Storage: System Account (r:1 w:1)
+	fn create_asset() -> Weight {
+		(27_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	// Storage: TemplateModule Issuers (r:1 w:0)
+	// Storage: TemplateModule FrozenBalances (r:0 w:1)
+	fn freeze() -> Weight {
+		(16_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: TemplateModule Issuers (r:1 w:0)
+	// Storage: TemplateModule FrozenBalances (r:0 w:1)
+	fn thaw() -> Weight {
+		(16_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: TemplateModule Issuers (r:1 w:0)
+	// Storage: TemplateModule FrozenAssets (r:0 w:1)
+	fn freeze_asset() -> Weight {
+		(15_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: TemplateModule Issuers (r:1 w:0)
+	// Storage: TemplateModule FrozenAssets (r:0 w:1)
+	fn thaw_asset() -> Weight {
+		(15_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: TemplateModule Issuers (r:1 w:0)
+	// Storage: TemplateModule RestrictedAssets (r:0 w:1)
+	fn set_restricted() -> Weight {
+		(15_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: TemplateModule Issuers (r:1 w:0)
+	// Storage: TemplateModule AllowLists (r:0 w:1)
+	fn set_allowed() -> Weight {
+		(15_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: TemplateModule Approvals (r:1 w:0)
+	// Storage: TemplateModule FrozenAssets (r:1 w:0)
+	// Storage: TemplateModule FrozenBalances (r:1 w:0)
+	// Storage: TemplateModule Balances (r:2 w:2)
+	fn safe_transfer_from() -> Weight {
+		(27_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	// Storage: TemplateModule Approvals (r:1 w:0)
+	// Storage: TemplateModule FrozenAssets (r:1 w:0)
+	// Storage: TemplateModule FrozenBalances (r:1 w:0)
+	// Storage: TemplateModule Balances (r:2 w:2)
+	/// The range of component `l` is `[0, 10]`.
+	fn safe_batch_transfer_from(l: u64, ) -> Weight {
+		(19_000_000 as Weight)
+			// Standard Error: 50_000
+			.saturating_add((5_500_000 as Weight).saturating_mul(l as Weight))
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().reads((4 as Weight).saturating_mul(l as Weight)))
+			.saturating_add(T::DbWeight::get().writes((2 as Weight).saturating_mul(l as Weight)))
+	}
+	// Storage: TemplateModule Collections (r:1 w:1)
+	fn create_collection() -> Weight {
+		(16_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: TemplateModule Collections (r:1 w:0)
+	// Storage: TemplateModule Issuers (r:1 w:1)
+	// Storage: TemplateModule TotalSupply (r:1 w:1)
+	// Storage: TemplateModule Balances (r:0 w:1)
+	// Storage: TemplateModule NftItems (r:0 w:1)
+	fn mint_nft() -> Weight {
+		(26_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	// Storage: TemplateModule NftItems (r:1 w:0)
+	// Storage: TemplateModule Collections (r:1 w:0)
+	// Storage: TemplateModule ItemMetadata (r:0 w:1)
+	fn set_metadata() -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: TemplateModule NftItems (r:1 w:0)
+	// Storage: TemplateModule Collections (r:1 w:0)
+	// Storage: TemplateModule Attributes (r:0 w:1)
+	fn set_attribute() -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: TemplateModule NftItems (r:1 w:0)
+	// Storage: TemplateModule Collections (r:1 w:0)
+	// Storage: TemplateModule Attributes (r:0 w:1)
+	fn clear_attribute() -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
 }
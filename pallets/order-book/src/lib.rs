@@ -0,0 +1,416 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Central limit order book for pairs of multi-token ids.
+//!
+//! A market is a `(base, quote)` pair. Prices are fixed-point quote units per unit of base,
+//! amounts in base units of at least `MinOrderAmount`. Orders escrow what they may spend in the
+//! account of the pallet: base for asks, `amount * price` quote, rounded down, for bids. An
+//! incoming order first matches the best opposite price levels it crosses, oldest order first
+//! within a level, and trades at the resting price. Any rest of it is added at the back of its
+//! own price level. Matching stops after `MaxFillsPerOrder` trades; if the rest still crosses
+//! the book then, it is refunded instead.
+//!
+//! Fees are taken from what each side receives and paid to `FeeCollector`, `MakerFee` from the
+//! resting order and `TakerFee` from the incoming one.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_runtime::{FixedU128, RuntimeDebug};
+
+/// Identifier of an order.
+pub type OrderId = u64;
+
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum Side {
+	/// Bid, buys base with quote.
+	Buy,
+	/// Ask, sells base for quote.
+	Sell,
+}
+
+impl Side {
+	fn opposite(self) -> Self {
+		match self {
+			Side::Buy => Side::Sell,
+			Side::Sell => Side::Buy,
+		}
+	}
+
+	/// Whether `price` is at least as good as `other` for this side.
+	fn better_or_equal(self, price: FixedU128, other: FixedU128) -> bool {
+		match self {
+			Side::Buy => price >= other,
+			Side::Sell => price <= other,
+		}
+	}
+}
+
+/// A resting order.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct Order<T: frame_system::Config> {
+	pub owner: T::AccountId,
+	pub base: u64,
+	pub quote: u64,
+	pub side: Side,
+	pub price: FixedU128,
+	/// Base amount not filled yet.
+	pub amount: u64,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use crate::{Order, OrderId, Side};
+	use frame_support::{pallet_prelude::*, PalletId};
+	use frame_system::pallet_prelude::*;
+	use pallet_multi_token::multitoken::MultiToken;
+	use sp_runtime::{
+		traits::{AccountIdConversion, Zero},
+		FixedPointNumber, FixedU128, PerThing, Permill,
+	};
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+		type MultiToken: MultiToken<Self::AccountId>;
+		/// Used to derive the account holding the escrow of open orders.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+		/// Fee taken from what the resting order receives.
+		#[pallet::constant]
+		type MakerFee: Get<Permill>;
+		/// Fee taken from what the incoming order receives.
+		#[pallet::constant]
+		type TakerFee: Get<Permill>;
+		/// Account the fees are paid to.
+		type FeeCollector: Get<Self::AccountId>;
+		/// Maximum number of price levels on each side of a market.
+		#[pallet::constant]
+		type MaxPriceLevels: Get<u32>;
+		/// Maximum number of orders resting at a single price.
+		#[pallet::constant]
+		type MaxOrdersPerLevel: Get<u32>;
+		/// Maximum number of trades an incoming order makes.
+		#[pallet::constant]
+		type MaxFillsPerOrder: Get<u32>;
+		/// Smallest base amount an order can be placed for.
+		#[pallet::constant]
+		type MinOrderAmount: Get<u64>;
+	}
+
+	/// Side of a market, `(base, quote, side)`.
+	pub type BookSide = (u64, u64, Side);
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		OrderPlaced { order_id: OrderId, owner: T::AccountId, base: u64, quote: u64, side: Side, price: FixedU128, amount: u64 },
+		/// `amount` of base traded at the `price` of the maker order.
+		Traded {
+			maker_order_id: OrderId,
+			taker_order_id: OrderId,
+			maker: T::AccountId,
+			taker: T::AccountId,
+			base: u64,
+			quote: u64,
+			price: FixedU128,
+			amount: u64,
+			maker_fee: u64,
+			taker_fee: u64,
+		},
+		OrderCancelled { order_id: OrderId, owner: T::AccountId, remaining: u64 },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		SameToken,
+		ZeroAmount,
+		OrderTooSmall,
+		ZeroPrice,
+		Overflow,
+		TooManyPriceLevels,
+		TooManyOrders,
+		NoOrder,
+		NotOrderOwner,
+	}
+
+	#[pallet::storage]
+	pub type NextOrderId<T: Config> = StorageValue<_, OrderId, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn orders)]
+	pub type Orders<T: Config> = StorageMap<_, Blake2_128Concat, OrderId, Order<T>, OptionQuery>;
+
+	/// Prices with resting orders on each side of a market, best first.
+	#[pallet::storage]
+	#[pallet::getter(fn price_levels)]
+	pub type PriceLevels<T: Config> =
+		StorageMap<_, Blake2_128Concat, BookSide, BoundedVec<FixedU128, T::MaxPriceLevels>, ValueQuery>;
+
+	/// Orders resting at a price, oldest first.
+	#[pallet::storage]
+	#[pallet::getter(fn level_orders)]
+	pub type LevelOrders<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		BookSide,
+		Blake2_128Concat,
+		FixedU128,
+		BoundedVec<OrderId, T::MaxOrdersPerLevel>,
+		ValueQuery,
+	>;
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Trades `amount` of `base` at `price` or better, leaving the rest on the book.
+		#[pallet::weight(Pallet::<T>::place_order_weight(T::MaxFillsPerOrder::get()))]
+		pub fn place_order(
+			origin: OriginFor<T>,
+			base: u64,
+			quote: u64,
+			side: Side,
+			price: FixedU128,
+			amount: u64,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			ensure!(base != quote, Error::<T>::SameToken);
+			ensure!(amount > 0, Error::<T>::ZeroAmount);
+			ensure!(amount >= T::MinOrderAmount::get(), Error::<T>::OrderTooSmall);
+			ensure!(!price.is_zero(), Error::<T>::ZeroPrice);
+			let escrow = match side {
+				Side::Buy => (quote, price.checked_mul_int(amount).ok_or(Error::<T>::Overflow)?),
+				Side::Sell => (base, amount),
+			};
+			T::MultiToken::transfer_to(&who, &Self::account_id(), &escrow.0, &escrow.1)?;
+
+			let order_id = NextOrderId::<T>::get();
+			NextOrderId::<T>::put(order_id + 1);
+			Self::deposit_event(Event::<T>::OrderPlaced { order_id, owner: who.clone(), base, quote, side, price, amount });
+
+			let (remaining, fills) = Self::match_order(&who, order_id, base, quote, side, price, amount)?;
+			if remaining > 0 {
+				let order = Order { owner: who.clone(), base, quote, side, price, amount: remaining };
+				if Self::crosses_book(&order) {
+					let (token, unspent) = Self::unspent(&order);
+					Self::refund(&who, token, unspent)?;
+					Self::deposit_event(Event::<T>::OrderCancelled { order_id, owner: who, remaining });
+				} else {
+					Self::insert_order(order_id, order)?;
+				}
+			}
+			Ok(Some(Self::place_order_weight(fills)).into())
+		}
+
+		/// Takes an order off the book and refunds what it has not spent.
+		#[pallet::weight(1000)]
+		pub fn cancel_order(origin: OriginFor<T>, order_id: OrderId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let order = Orders::<T>::get(order_id).ok_or(Error::<T>::NoOrder)?;
+			ensure!(order.owner == who, Error::<T>::NotOrderOwner);
+
+			let (token, unspent) = Self::unspent(&order);
+			Self::refund(&who, token, unspent)?;
+			Self::remove_order(order_id, &order);
+			Self::deposit_event(Event::<T>::OrderCancelled { order_id, owner: who, remaining: order.amount });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Account holding the escrow of open orders.
+		pub fn account_id() -> T::AccountId {
+			T::PalletId::get().into_account_truncating()
+		}
+
+		/// Weight of `place_order` making `fills` trades: escrowing and resting the order, plus
+		/// updating the maker order and the four payouts of every trade.
+		pub fn place_order_weight(fills: u32) -> Weight {
+			T::DbWeight::get()
+				.reads_writes(7, 7)
+				.saturating_add(T::DbWeight::get().reads_writes(11, 9).saturating_mul(fills as Weight))
+		}
+
+		/// Token and amount still escrowed for the unfilled part of `order`.
+		fn unspent(order: &Order<T>) -> (u64, u64) {
+			match order.side {
+				Side::Buy => (order.quote, order.price.saturating_mul_int(order.amount)),
+				Side::Sell => (order.base, order.amount),
+			}
+		}
+
+		/// Whether `order` would trade against the best opposite price level.
+		fn crosses_book(order: &Order<T>) -> bool {
+			PriceLevels::<T>::get((order.base, order.quote, order.side.opposite()))
+				.first()
+				.map_or(false, |best| order.side.better_or_equal(order.price, *best))
+		}
+
+		/// Pays `amount` of `token` out of escrow.
+		fn pay(to: &T::AccountId, token: u64, amount: u64) -> DispatchResult {
+			if amount == 0 {
+				return Ok(())
+			}
+			T::MultiToken::transfer_to(&Self::account_id(), to, &token, &amount)
+		}
+
+		/// Returns `amount` of `token` out of escrow to `to`, who escrowed it.
+		fn refund(to: &T::AccountId, token: u64, amount: u64) -> DispatchResult {
+			if amount == 0 {
+				return Ok(())
+			}
+			T::MultiToken::refund(&Self::account_id(), to, &token, &amount)
+		}
+
+		/// Fills the incoming order against the opposite side of the book in at most
+		/// `MaxFillsPerOrder` trades, returns the base amount left unfilled and the trades made.
+		fn match_order(
+			taker: &T::AccountId,
+			taker_order_id: OrderId,
+			base: u64,
+			quote: u64,
+			side: Side,
+			limit_price: FixedU128,
+			amount: u64,
+		) -> Result<(u64, u32), DispatchError> {
+			let book_side = (base, quote, side.opposite());
+			let mut prices = PriceLevels::<T>::get(book_side);
+			let mut remaining = amount;
+			let max_fills = T::MaxFillsPerOrder::get();
+			let mut fills = 0;
+
+			while remaining > 0 && fills < max_fills {
+				let price = match prices.first() {
+					Some(price) if side.better_or_equal(limit_price, *price) => *price,
+					_ => break,
+				};
+				let mut queue = LevelOrders::<T>::get(book_side, price);
+				while remaining > 0 && fills < max_fills && !queue.is_empty() {
+					let maker_order_id = queue[0];
+					let mut maker_order = Orders::<T>::get(maker_order_id).ok_or(Error::<T>::NoOrder)?;
+					let fill = remaining.min(maker_order.amount);
+					let (maker_fee, taker_fee) = Self::settle(taker, &maker_order, side, limit_price, fill)?;
+
+					remaining -= fill;
+					fills += 1;
+					maker_order.amount -= fill;
+					if maker_order.amount == 0 {
+						Orders::<T>::remove(maker_order_id);
+						queue.remove(0);
+					} else {
+						Orders::<T>::insert(maker_order_id, &maker_order);
+					}
+					Self::deposit_event(Event::<T>::Traded {
+						maker_order_id,
+						taker_order_id,
+						maker: maker_order.owner,
+						taker: taker.clone(),
+						base,
+						quote,
+						price,
+						amount: fill,
+						maker_fee,
+						taker_fee,
+					});
+				}
+
+				if queue.is_empty() {
+					LevelOrders::<T>::remove(book_side, price);
+					prices.remove(0);
+				} else {
+					LevelOrders::<T>::insert(book_side, price, queue);
+				}
+			}
+
+			if prices.is_empty() {
+				PriceLevels::<T>::remove(book_side);
+			} else {
+				PriceLevels::<T>::insert(book_side, prices);
+			}
+			Ok((remaining, fills))
+		}
+
+		/// Exchanges `fill` base at the price of `maker_order` out of escrow, returns the maker
+		/// and taker fees. A bid taking a cheaper ask gets the difference to its limit back.
+		/// Quote amounts are rounded down, so no fill pays out more than was escrowed for it.
+		fn settle(
+			taker: &T::AccountId,
+			maker_order: &Order<T>,
+			side: Side,
+			limit_price: FixedU128,
+			fill: u64,
+		) -> Result<(u64, u64), DispatchError> {
+			let quote_amount = maker_order.price.checked_mul_int(fill).ok_or(Error::<T>::Overflow)?;
+			let (taker_token, taker_gross, maker_token, maker_gross) = match side {
+				Side::Buy => (maker_order.base, fill, maker_order.quote, quote_amount),
+				Side::Sell => (maker_order.quote, quote_amount, maker_order.base, fill),
+			};
+			let maker_fee = T::MakerFee::get().mul_floor(maker_gross);
+			let taker_fee = T::TakerFee::get().mul_floor(taker_gross);
+			let fee_collector = T::FeeCollector::get();
+
+			Self::pay(taker, taker_token, taker_gross - taker_fee)?;
+			Self::pay(&maker_order.owner, maker_token, maker_gross - maker_fee)?;
+			Self::pay(&fee_collector, taker_token, taker_fee)?;
+			Self::pay(&fee_collector, maker_token, maker_fee)?;
+			if side == Side::Buy {
+				let price_improvement = limit_price.saturating_mul_int(fill).saturating_sub(quote_amount);
+				Self::refund(taker, maker_order.quote, price_improvement)?;
+			}
+			Ok((maker_fee, taker_fee))
+		}
+
+		/// Adds an order at the back of its price level.
+		fn insert_order(order_id: OrderId, order: Order<T>) -> DispatchResult {
+			let book_side = (order.base, order.quote, order.side);
+			PriceLevels::<T>::try_mutate(book_side, |prices| -> DispatchResult {
+				if prices.contains(&order.price) {
+					return Ok(())
+				}
+				// Best first: descending for bids, ascending for asks.
+				let index = prices
+					.iter()
+					.position(|price| !order.side.better_or_equal(*price, order.price))
+					.unwrap_or(prices.len());
+				prices.try_insert(index, order.price).map_err(|_| Error::<T>::TooManyPriceLevels)?;
+				Ok(())
+			})?;
+			LevelOrders::<T>::try_mutate(book_side, order.price, |queue| queue.try_push(order_id))
+				.map_err(|_| Error::<T>::TooManyOrders)?;
+			Orders::<T>::insert(order_id, order);
+			Ok(())
+		}
+
+		fn remove_order(order_id: OrderId, order: &Order<T>) {
+			let book_side = (order.base, order.quote, order.side);
+			Orders::<T>::remove(order_id);
+			let mut queue = LevelOrders::<T>::get(book_side, order.price);
+			queue.retain(|id| *id != order_id);
+			if queue.is_empty() {
+				LevelOrders::<T>::remove(book_side, order.price);
+				PriceLevels::<T>::mutate_exists(book_side, |prices| {
+					if let Some(levels) = prices {
+						levels.retain(|price| *price != order.price);
+						if levels.is_empty() {
+							*prices = None;
+						}
+					}
+				});
+			} else {
+				LevelOrders::<T>::insert(book_side, order.price, queue);
+			}
+		}
+	}
+}
@@ -0,0 +1,113 @@
+use crate as pallet_order_book;
+use frame_support::{
+	traits::{ConstU16, ConstU32, ConstU64},
+	PalletId,
+};
+use frame_system as system;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	Permill,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::parameter_types! {
+	pub const OrderBookPalletId: PalletId = PalletId(*b"py/obook");
+	pub const MakerFee: Permill = Permill::from_parts(1_000);
+	pub const TakerFee: Permill = Permill::from_parts(2_000);
+	pub const FeeCollector: u64 = 99;
+	pub const MaxPriceLevels: u32 = 4;
+	pub const MaxOrdersPerLevel: u32 = 2;
+	pub static MaxFillsPerOrder: u32 = 4;
+	pub static MinOrderAmount: u64 = 1;
+	pub const NativeTokenId: u64 = u64::MAX;
+	pub const MultiTokenPalletId: PalletId = PalletId(*b"py/mtokn");
+}
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		Balances: pallet_balances,
+		MultiToken: pallet_multi_token,
+		OrderBook: pallet_order_book,
+	}
+);
+
+impl system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU64<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+impl pallet_multi_token::Config for Test {
+	type Event = Event;
+	type WeightInfo = pallet_multi_token::weights::SubstrateWeight<Test>;
+	type Currency = Balances;
+	type NativeTokenId = NativeTokenId;
+	type PalletId = MultiTokenPalletId;
+	type FirstIssuedTokenId = ConstU64<100>;
+	type AssetDeposit = ConstU64<10>;
+	type OnTransfer = ();
+	type ExemptAccounts = frame_support::traits::Nothing;
+	type OnReceived = ();
+	type StringLimit = ConstU32<64>;
+}
+
+impl pallet_order_book::Config for Test {
+	type Event = Event;
+	type MultiToken = MultiToken;
+	type PalletId = OrderBookPalletId;
+	type MakerFee = MakerFee;
+	type TakerFee = TakerFee;
+	type FeeCollector = FeeCollector;
+	type MaxPriceLevels = MaxPriceLevels;
+	type MaxOrdersPerLevel = MaxOrdersPerLevel;
+	type MaxFillsPerOrder = MaxFillsPerOrder;
+	type MinOrderAmount = MinOrderAmount;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+}
@@ -0,0 +1,156 @@
+use crate::{mock::*, Error, Side};
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::{FixedPointNumber, FixedU128};
+
+const BASE: u64 = 0;
+const QUOTE: u64 = 1;
+
+fn price(price: u64) -> FixedU128 {
+	FixedU128::saturating_from_integer(price)
+}
+
+#[test]
+fn should_match_by_price_then_time() {
+	new_test_ext().execute_with(|| {
+		let (first_seller, second_seller, cheap_seller, buyer) = (1, 2, 3, 4);
+		for seller in [first_seller, second_seller, cheap_seller] {
+			assert_ok!(MultiToken::mint(Origin::signed(seller), BASE, 10_000));
+		}
+		assert_ok!(MultiToken::mint(Origin::signed(buyer), QUOTE, 250_000));
+		assert_ok!(OrderBook::place_order(Origin::signed(first_seller), BASE, QUOTE, Side::Sell, price(10), 10_000));
+		assert_ok!(OrderBook::place_order(Origin::signed(second_seller), BASE, QUOTE, Side::Sell, price(10), 10_000));
+		assert_ok!(OrderBook::place_order(Origin::signed(cheap_seller), BASE, QUOTE, Side::Sell, price(9), 10_000));
+		assert_eq!(OrderBook::price_levels((BASE, QUOTE, Side::Sell)).into_inner(), [price(9), price(10)].to_vec());
+		assert_eq!(OrderBook::level_orders((BASE, QUOTE, Side::Sell), price(10)).into_inner(), [0, 1].to_vec());
+
+		// Takes the cheaper level first, then the older order at 10, then part of the newer one.
+		assert_ok!(OrderBook::place_order(Origin::signed(buyer), BASE, QUOTE, Side::Buy, price(10), 25_000));
+		assert_eq!(MultiToken::balances(BASE, buyer), Some(25_000 - 20 - 20 - 10));
+		// Bought 10_000 for 9 instead of 10.
+		assert_eq!(MultiToken::balances(QUOTE, buyer), Some(10_000));
+		assert_eq!(MultiToken::balances(QUOTE, cheap_seller), Some(90_000 - 90));
+		assert_eq!(MultiToken::balances(QUOTE, first_seller), Some(100_000 - 100));
+		assert_eq!(MultiToken::balances(QUOTE, second_seller), Some(50_000 - 50));
+		assert_eq!(MultiToken::balances(BASE, FeeCollector::get()), Some(50));
+		assert_eq!(MultiToken::balances(QUOTE, FeeCollector::get()), Some(240));
+
+		assert!(OrderBook::orders(0).is_none());
+		assert_eq!(OrderBook::orders(1).unwrap().amount, 5_000);
+		assert!(OrderBook::orders(3).is_none());
+		assert_eq!(OrderBook::price_levels((BASE, QUOTE, Side::Sell)).into_inner(), [price(10)].to_vec());
+		assert_eq!(OrderBook::level_orders((BASE, QUOTE, Side::Sell), price(10)).into_inner(), [1].to_vec());
+		assert!(OrderBook::price_levels((BASE, QUOTE, Side::Buy)).is_empty());
+		assert_eq!(MultiToken::balances(BASE, OrderBook::account_id()), Some(5_000));
+		assert_eq!(MultiToken::balances(QUOTE, OrderBook::account_id()), Some(0));
+	});
+}
+
+#[test]
+fn should_rest_partially_filled_orders_until_cancelled() {
+	new_test_ext().execute_with(|| {
+		let (buyer, seller, other) = (1, 2, 3);
+		assert_ok!(MultiToken::mint(Origin::signed(buyer), QUOTE, 5_000));
+		assert_ok!(MultiToken::mint(Origin::signed(seller), BASE, 400));
+		assert_ok!(OrderBook::place_order(Origin::signed(buyer), BASE, QUOTE, Side::Buy, price(5), 1_000));
+		assert_eq!(MultiToken::balances(QUOTE, buyer), Some(0));
+
+		// Sells at the resting price, above its own limit.
+		assert_ok!(OrderBook::place_order(Origin::signed(seller), BASE, QUOTE, Side::Sell, price(4), 400));
+		assert_eq!(MultiToken::balances(QUOTE, seller), Some(2_000 - 4));
+		assert_eq!(MultiToken::balances(BASE, buyer), Some(400));
+		assert_eq!(OrderBook::orders(0).unwrap().amount, 600);
+		assert!(OrderBook::price_levels((BASE, QUOTE, Side::Sell)).is_empty());
+
+		assert_noop!(OrderBook::cancel_order(Origin::signed(other), 0), Error::<Test>::NotOrderOwner);
+		assert_ok!(OrderBook::cancel_order(Origin::signed(buyer), 0));
+		assert_noop!(OrderBook::cancel_order(Origin::signed(buyer), 0), Error::<Test>::NoOrder);
+		assert_eq!(MultiToken::balances(QUOTE, buyer), Some(3_000));
+		assert!(OrderBook::price_levels((BASE, QUOTE, Side::Buy)).is_empty());
+		assert!(OrderBook::level_orders((BASE, QUOTE, Side::Buy), price(5)).is_empty());
+	});
+}
+
+#[test]
+fn should_revert_invalid_orders() {
+	new_test_ext().execute_with(|| {
+		let seller = 1;
+		assert_ok!(MultiToken::mint(Origin::signed(seller), BASE, 1_000));
+		assert_noop!(OrderBook::place_order(Origin::signed(seller), BASE, BASE, Side::Sell, price(1), 1), Error::<Test>::SameToken);
+		assert_noop!(OrderBook::place_order(Origin::signed(seller), BASE, QUOTE, Side::Sell, price(1), 0), Error::<Test>::ZeroAmount);
+		assert_noop!(OrderBook::place_order(Origin::signed(seller), BASE, QUOTE, Side::Sell, price(0), 1), Error::<Test>::ZeroPrice);
+		MinOrderAmount::set(100);
+		assert_noop!(
+			OrderBook::place_order(Origin::signed(seller), BASE, QUOTE, Side::Sell, price(1), 99),
+			Error::<Test>::OrderTooSmall
+		);
+		assert_noop!(
+			OrderBook::place_order(Origin::signed(seller), BASE, QUOTE, Side::Buy, price(u64::MAX), 2),
+			Error::<Test>::Overflow
+		);
+
+		for level in [4, 1, 3, 2] {
+			assert_ok!(OrderBook::place_order(Origin::signed(seller), BASE, QUOTE, Side::Sell, price(level), 1));
+		}
+		assert_eq!(
+			OrderBook::price_levels((BASE, QUOTE, Side::Sell)).into_inner(),
+			[price(1), price(2), price(3), price(4)].to_vec()
+		);
+		assert_noop!(
+			OrderBook::place_order(Origin::signed(seller), BASE, QUOTE, Side::Sell, price(5), 1),
+			Error::<Test>::TooManyPriceLevels
+		);
+		assert_ok!(OrderBook::place_order(Origin::signed(seller), BASE, QUOTE, Side::Sell, price(4), 1));
+		assert_noop!(
+			OrderBook::place_order(Origin::signed(seller), BASE, QUOTE, Side::Sell, price(4), 1),
+			Error::<Test>::TooManyOrders
+		);
+	});
+}
+
+#[test]
+fn should_trade_at_fractional_prices() {
+	new_test_ext().execute_with(|| {
+		let (seller, buyer) = (1, 2);
+		assert_ok!(MultiToken::mint(Origin::signed(seller), BASE, 1_000));
+		assert_ok!(MultiToken::mint(Origin::signed(buyer), QUOTE, 300));
+		let quarter = FixedU128::from_rational(1, 4);
+		assert_ok!(OrderBook::place_order(Origin::signed(seller), BASE, QUOTE, Side::Sell, quarter, 1_000));
+
+		assert_ok!(OrderBook::place_order(
+			Origin::signed(buyer),
+			BASE,
+			QUOTE,
+			Side::Buy,
+			FixedU128::from_rational(3, 10),
+			1_000
+		));
+		assert_eq!(MultiToken::balances(QUOTE, seller), Some(250));
+		assert_eq!(MultiToken::balances(BASE, buyer), Some(1_000 - 2));
+		// Escrowed 300 at its limit and paid 250 at the resting price.
+		assert_eq!(MultiToken::balances(QUOTE, buyer), Some(50));
+		assert!(OrderBook::orders(0).is_none());
+		assert!(OrderBook::orders(1).is_none());
+	});
+}
+
+#[test]
+fn should_refund_rest_crossing_the_book_after_max_fills() {
+	new_test_ext().execute_with(|| {
+		let (first_seller, second_seller, buyer) = (1, 2, 3);
+		MaxFillsPerOrder::set(1);
+		for seller in [first_seller, second_seller] {
+			assert_ok!(MultiToken::mint(Origin::signed(seller), BASE, 100));
+			assert_ok!(OrderBook::place_order(Origin::signed(seller), BASE, QUOTE, Side::Sell, price(10), 100));
+		}
+		assert_ok!(MultiToken::mint(Origin::signed(buyer), QUOTE, 2_000));
+
+		let info = OrderBook::place_order(Origin::signed(buyer), BASE, QUOTE, Side::Buy, price(10), 200).unwrap();
+		assert_eq!(info.actual_weight, Some(OrderBook::place_order_weight(1)));
+		assert_eq!(MultiToken::balances(BASE, buyer), Some(100));
+		// The rest would have crossed the second ask, so it was refunded instead of resting.
+		assert_eq!(MultiToken::balances(QUOTE, buyer), Some(1_000));
+		assert!(OrderBook::orders(2).is_none());
+		assert!(OrderBook::price_levels((BASE, QUOTE, Side::Buy)).is_empty());
+		assert_eq!(OrderBook::orders(1).unwrap().amount, 100);
+	});
+}
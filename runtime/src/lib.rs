@@ -14,7 +14,9 @@ use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
-	traits::{AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, Verify},
+	traits::{
+		AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, Verify,
+	},
 	transaction_validity::{TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature,
 };
@@ -27,13 +29,14 @@ use sp_version::RuntimeVersion;
 pub use frame_support::{
 	construct_runtime, parameter_types,
 	traits::{
-		ConstU128, ConstU32, ConstU64, ConstU8, KeyOwnerProofSystem, Randomness, StorageInfo,
+		ConstU128, ConstU32, ConstU64, ConstU8, Contains, KeyOwnerProofSystem, Randomness, SortedMembers,
+		StorageInfo,
 	},
 	weights::{
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
 		IdentityFee, Weight,
 	},
-	StorageValue,
+	PalletId, StorageValue,
 };
 pub use frame_system::Call as SystemCall;
 pub use pallet_balances::Call as BalancesCall;
@@ -45,6 +48,8 @@ pub use sp_runtime::{Perbill, Permill};
 
 pub use pallet_multi_token;
 pub use pallet_dex;
+pub use pallet_emergency_pause;
+pub use pallet_order_book;
 
 /// An index to a block.
 pub type BlockNumber = u32;
@@ -143,13 +148,94 @@ parameter_types! {
 		::max_with_normal_ratio(5 * 1024 * 1024, NORMAL_DISPATCH_RATIO);
 	pub const SS58Prefix: u8 = 42;
 	pub const Fee: u64 = 3;
+	pub const MaxObservations: u32 = 64;
+	pub const DexPalletId: PalletId = PalletId(*b"py/dexpl");
+	pub const MaxPoolAssets: u32 = 8;
+	pub const StableSwapFee: Permill = Permill::from_parts(400);
+	pub const MaxTicks: u32 = 512;
+	pub const PoolDeposit: Balance = 100 * EXISTENTIAL_DEPOSIT;
+	pub const MaxPriceMove: Permill = Permill::from_percent(20);
+	pub const MaxOrdersPerPool: u32 = 64;
+	pub const MinLimitOrderAmount: u64 = 1_000;
+	pub const MaxOrderLifetime: BlockNumber = 7 * DAYS;
+	pub const MaxIntentsPerBatch: u32 = 256;
+	pub const MaxAuctionsPerBlock: u32 = 16;
+	pub const CommitmentDeposit: Balance = 10 * EXISTENTIAL_DEPOSIT;
+	pub const RevealPeriod: BlockNumber = 10;
+	pub const MaxCommitmentsPerBlock: u32 = 256;
+	pub const MaxPauseNameLen: u32 = 64;
+	pub const OrderBookPalletId: PalletId = PalletId(*b"py/obook");
+	pub OrderBookFeeCollector: AccountId = PalletId(*b"py/obfee").into_account_truncating();
+	pub const MakerFee: Permill = Permill::from_parts(1_000);
+	pub const TakerFee: Permill = Permill::from_parts(2_000);
+	pub const MaxPriceLevels: u32 = 128;
+	pub const MaxOrdersPerLevel: u32 = 64;
+	pub const MaxFillsPerOrder: u32 = 32;
+	pub const MinOrderAmount: u64 = 1_000;
+	pub const NativeTokenId: u64 = u64::MAX;
+	pub const MultiTokenPalletId: PalletId = PalletId(*b"py/mtokn");
+	pub const FirstIssuedTokenId: u64 = 1 << 32;
+	pub const AssetDeposit: Balance = 100 * EXISTENTIAL_DEPOSIT;
+	pub const TxPaymentPalletId: PalletId = PalletId(*b"py/txpay");
+	pub TxFeeCollector: AccountId = PalletId(*b"py/txfee").into_account_truncating();
+}
+
+/// Calls the emergency pause applies to.
+pub struct GuardedCalls;
+impl Contains<Call> for GuardedCalls {
+	fn contains(call: &Call) -> bool {
+		matches!(call, Call::MultiToken(_) | Call::Dex(_) | Call::OrderBook(_))
+	}
+}
+
+/// Escrow and pool accounts, refunding multi-token deposits regardless of freezes and allow lists.
+pub struct PalletEscrows;
+impl Contains<AccountId> for PalletEscrows {
+	fn contains(who: &AccountId) -> bool {
+		pallet_dex::PalletAccounts::<Runtime>::contains(who) ||
+			*who == OrderBook::account_id() ||
+			*who == DexTxPayment::account_id()
+	}
+}
+
+/// Withdrawals, reward claims and unwrapping, which stay open while calls are paused and in
+/// emergency-exit mode.
+pub struct ExitCalls;
+impl Contains<Call> for ExitCalls {
+	fn contains(call: &Call) -> bool {
+		matches!(
+			call,
+			Call::Dex(
+				pallet_dex::Call::withdraw { .. } |
+					pallet_dex::Call::remove_liquidity { .. } |
+					pallet_dex::Call::remove_stable_liquidity { .. } |
+					pallet_dex::Call::exit_weighted_pool { .. } |
+					pallet_dex::Call::exit_weighted_pool_single { .. } |
+					pallet_dex::Call::burn_position { .. } |
+					pallet_dex::Call::collect { .. } |
+					pallet_dex::Call::cancel_limit_order { .. } |
+					pallet_dex::Call::unstake { .. } |
+					pallet_dex::Call::claim { .. } |
+					pallet_dex::Call::remove_lbp_liquidity { .. }
+			) | Call::OrderBook(pallet_order_book::Call::cancel_order { .. }) |
+				Call::MultiToken(pallet_multi_token::Call::unwrap { .. })
+		)
+	}
+}
+
+/// The sudo key, the only account allowed to fund farms.
+pub struct SudoKey;
+impl SortedMembers<AccountId> for SudoKey {
+	fn sorted_members() -> Vec<AccountId> {
+		Sudo::key().into_iter().collect()
+	}
 }
 
 // Configure FRAME pallets to include in runtime.
 
 impl frame_system::Config for Runtime {
 	/// The basic call filter to use in dispatchable.
-	type BaseCallFilter = frame_support::traits::Everything;
+	type BaseCallFilter = pallet_emergency_pause::PauseFilter<Runtime, GuardedCalls, ExitCalls>;
 	/// Block & extrinsics weights: base values and limits.
 	type BlockWeights = BlockWeights;
 	/// The maximum length of a block (in bytes).
@@ -269,12 +355,68 @@ impl pallet_sudo::Config for Runtime {
 impl pallet_multi_token::Config for Runtime {
 	type Event = Event;
 	type WeightInfo = pallet_multi_token::weights::SubstrateWeight<Runtime>;
+	type Currency = Balances;
+	type NativeTokenId = NativeTokenId;
+	type PalletId = MultiTokenPalletId;
+	type FirstIssuedTokenId = FirstIssuedTokenId;
+	type AssetDeposit = AssetDeposit;
+	type OnTransfer = pallet_multi_token::AllowList<Runtime>;
+	type ExemptAccounts = PalletEscrows;
+	type OnReceived = ();
+	type StringLimit = ConstU32<128>;
 }
 
 impl pallet_dex::Config for Runtime {
 	type Event = Event;
 	type MultiToken = MultiToken;
 	type Fee = Fee;
+	type MaxObservations = MaxObservations;
+	type PalletId = DexPalletId;
+	type MaxPoolAssets = MaxPoolAssets;
+	type StableSwapFee = StableSwapFee;
+	type MaxTicks = MaxTicks;
+	type FlashBorrower = ();
+	type Currency = Balances;
+	type PoolDeposit = PoolDeposit;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxPriceMove = MaxPriceMove;
+	type MaxOrdersPerPool = MaxOrdersPerPool;
+	type MinLimitOrderAmount = MinLimitOrderAmount;
+	type MaxOrderLifetime = MaxOrderLifetime;
+	type MaxIntentsPerBatch = MaxIntentsPerBatch;
+	type MaxAuctionsPerBlock = MaxAuctionsPerBlock;
+	type CommitmentDeposit = CommitmentDeposit;
+	type RevealPeriod = RevealPeriod;
+	type MaxCommitmentsPerBlock = MaxCommitmentsPerBlock;
+	type FarmOrigin = frame_system::EnsureSignedBy<SudoKey, AccountId>;
+}
+
+impl pallet_order_book::Config for Runtime {
+	type Event = Event;
+	type MultiToken = MultiToken;
+	type PalletId = OrderBookPalletId;
+	type MakerFee = MakerFee;
+	type TakerFee = TakerFee;
+	type FeeCollector = OrderBookFeeCollector;
+	type MaxPriceLevels = MaxPriceLevels;
+	type MaxOrdersPerLevel = MaxOrdersPerLevel;
+	type MaxFillsPerOrder = MaxFillsPerOrder;
+	type MinOrderAmount = MinOrderAmount;
+}
+
+impl pallet_dex_tx_payment::Config for Runtime {
+	type Event = Event;
+	type MultiToken = MultiToken;
+	type Dex = Dex;
+	type NativeTokenId = NativeTokenId;
+	type FeeCollector = TxFeeCollector;
+	type PalletId = TxPaymentPalletId;
+}
+
+impl pallet_emergency_pause::Config for Runtime {
+	type Event = Event;
+	type PauseOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxNameLen = MaxPauseNameLen;
 }
 // Create the runtime by composing the FRAME pallets that were previously configured.
 construct_runtime!(
@@ -294,6 +436,9 @@ construct_runtime!(
 		Sudo: pallet_sudo,
 		MultiToken: pallet_multi_token,
 		Dex: pallet_dex,
+		EmergencyPause: pallet_emergency_pause,
+		OrderBook: pallet_order_book,
+		DexTxPayment: pallet_dex_tx_payment,
 	}
 );
 
@@ -312,7 +457,7 @@ pub type SignedExtra = (
 	frame_system::CheckEra<Runtime>,
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
-	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	pallet_dex_tx_payment::ChargeDexTxPayment<Runtime>,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;